@@ -285,6 +285,8 @@ pub mod cpi_examples {
             ix.data.insert(0, 0 as u8);
             // PsyOptions Validation discriminator
             ix.data.insert(0, 0 as u8);
+            // Prepend the account for the PsyOptions TradingAllowlists middleware
+            ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.trading_allowlist.key(), false));
 
             // Handle the insertion of the dex program id one for time for the general proxy IX
             ix.accounts.insert(0, ctx.accounts.dex_program.to_account_metas(Some(false))[0].clone());
@@ -300,6 +302,7 @@ pub mod cpi_examples {
                 &ix,
                 &[
                     ctx.accounts.psy_american_program.to_account_info(),
+                    ctx.accounts.trading_allowlist.to_account_info(),
                     ctx.accounts.dex_program.to_account_info(),
                     ctx.accounts.open_orders.to_account_info(),
                     ctx.accounts.vault_authority.to_account_info(),
@@ -338,6 +341,16 @@ pub mod cpi_examples {
             NonZeroU64::new(max_native_pc_qty_including_fees).unwrap()
         ).map_err(|_x| CpiExampleErrors::ErrorCode::DexIxError)?;
         new_order_ix.program_id = *cpi_program.key;
+        // Prepend the account for the PsyOptions TradingAllowlists middleware
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.trading_allowlist.key(), false));
+        // Prepend the accounts for the PsyOptions PositionLimits middleware
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.position_limit.key(), false));
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.psy_option_market.key(), false));
+        // Prepend the accounts for the PsyOptions PriceBands middleware
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.oracle.key(), false));
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.oracle_config.key(), false));
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.price_band.key(), false));
+        new_order_ix.accounts.insert(0, AccountMeta::new_readonly(ctx.accounts.psy_option_market.key(), false));
         // insert data for the OpenOrdersPDA middleware
         new_order_ix.data.insert(0, 1 as u8);
         // insert data for the PsyOptions Validation middleware
@@ -355,6 +368,12 @@ pub mod cpi_examples {
         solana_program::program::invoke_signed(
             &new_order_ix,
             &[
+                ctx.accounts.psy_option_market.to_account_info(),
+                ctx.accounts.price_band.to_account_info(),
+                ctx.accounts.oracle_config.to_account_info(),
+                ctx.accounts.oracle.to_account_info(),
+                ctx.accounts.position_limit.to_account_info(),
+                ctx.accounts.trading_allowlist.to_account_info(),
                 ctx.accounts.market.to_account_info(),
                 ctx.accounts.open_orders.to_account_info(),
                 ctx.accounts.request_queue.to_account_info(),
@@ -603,6 +622,24 @@ pub struct PlaceOrder<'info> {
     #[account(mut)]
     pub pc_vault: AccountInfo<'info>,
 
+    //// PsyOptions PriceBands, PositionLimits and TradingAllowlists middleware accounts
+    /// The PsyOptions OptionMarket the Serum market trades
+    /// CHECK: TODO
+    pub psy_option_market: AccountInfo<'info>,
+    /// The OptionMarket's PriceBand PDA, it does not need to be initialized
+    /// CHECK: TODO
+    pub price_band: AccountInfo<'info>,
+    /// CHECK: TODO
+    pub oracle_config: AccountInfo<'info>,
+    /// CHECK: TODO
+    pub oracle: AccountInfo<'info>,
+    /// The OptionMarket's PositionLimit PDA, it does not need to be initialized
+    /// CHECK: TODO
+    pub position_limit: AccountInfo<'info>,
+    /// The Serum market's TradingAllowlist PDA, it does not need to be initialized
+    /// CHECK: TODO
+    pub trading_allowlist: AccountInfo<'info>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
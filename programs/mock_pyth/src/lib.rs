@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+declare_id!("F5kWTMzBvF43TNukRQLXaNojfpXEJfxkAUujTVf7mxxV");
+
+/// Stands in for the Pyth oracle on localnet. The tests create price accounts owned by
+/// this program and write the price, exponent and publish time into them directly.
+#[program]
+pub mod mock_pyth {
+    use super::*;
+
+    /// Write `data` into the price account starting at `offset`
+    pub fn write(ctx: Context<Write>, offset: u64, data: Vec<u8>) -> Result<()> {
+        let mut account_data = ctx.accounts.price.try_borrow_mut_data()?;
+        let start = offset as usize;
+        let end = start.checked_add(data.len()).ok_or(ProgramError::InvalidArgument)?;
+        if end > account_data.len() {
+            return Err(ProgramError::AccountDataTooSmall.into())
+        }
+        account_data[start..end].copy_from_slice(&data);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Write<'info> {
+    /// CHECK: Raw Pyth price account data, only this program can write it
+    #[account(mut, owner = crate::ID)]
+    pub price: AccountInfo<'info>,
+}
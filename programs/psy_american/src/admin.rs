@@ -0,0 +1,11 @@
+/// The admin_key is allowed to configure the per market policies that the Serum proxy
+/// enforces (referral recipients, allowlists, risk limits). Until governance is in place
+/// this is the same key as the fee owner.
+pub mod admin_key {
+  use solana_program::declare_id;
+  #[cfg(not(feature = "test-admin"))]
+  declare_id!("6c33US7ErPmLXZog9SyChQUYUrrJY51k4GmzdhrbhNnD");
+  /// Localnet builds sign admin instructions with tests/keys/admin.json
+  #[cfg(feature = "test-admin")]
+  declare_id!("fkKQE5ibEegX7dMhcuKn2TmSbqpKa7bAgYXsjpfGBu2");
+}
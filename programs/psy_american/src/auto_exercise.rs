@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, settlement::SETTLEMENT_WINDOW};
+
+/// How many seconds after expiration keepers can auto exercise at the recorded
+/// settlement price. It outlasts the settlement window, so a price recorded at the end
+/// of that window still leaves keepers time to exercise.
+pub const AUTO_EXERCISE_WINDOW: i64 = SETTLEMENT_WINDOW + 5 * 60;
+
+/// Check keepers can no longer auto exercise the OptionMarket, so its writers can reclaim
+/// the underlying assets. Until the auto exercise window closes, writers have to wait for
+/// a settlement price that leaves the options out of the money.
+pub fn auto_exercise_closed(option_market: &OptionMarket, timestamp: i64) -> Result<()> {
+    let window_end = option_market.expiration_unix_timestamp
+        .saturating_add(AUTO_EXERCISE_WINDOW);
+    let settled_out_of_the_money = option_market.settlement_slot != 0
+        && option_market.settlement_price <= option_market.quote_amount_per_contract;
+    if timestamp <= window_end && !settled_out_of_the_money {
+        return Err(errors::ErrorCode::AutoExerciseWindowOpen.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AutoExercise<'info> {
+    pub keeper: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    /// Holders opt in by delegating their OptionTokens and quote assets to this PDA
+    /// CHECK: Only signs as the delegate
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"exerciseAuthority"],
+        bump,
+    )]
+    pub exercise_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_src: Box<Account<'info, TokenAccount>>,
+    /// Receives the keeper fee
+    #[account(mut)]
+    pub keeper_underlying_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> AutoExercise<'info> {
+    pub fn accounts(ctx: &Context<AutoExercise>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        // Validate the pools and option mint are the same as on the OptionMarket
+        if ctx.accounts.quote_asset_pool.key() != option_market.quote_asset_pool {
+            return Err(errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.option_mint.key() != option_market.option_mint {
+            return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_dest.mint != option_market.underlying_asset_mint
+            || ctx.accounts.keeper_underlying_dest.mint != option_market.underlying_asset_mint {
+            return Err(errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset.into())
+        }
+
+        // The delegation only covers the holder's own accounts, so the proceeds must
+        // go back to the holder
+        let holder = ctx.accounts.exerciser_option_token_src.owner;
+        if ctx.accounts.quote_asset_src.owner != holder || ctx.accounts.underlying_asset_dest.owner != holder {
+            return Err(errors::ErrorCode::AutoExerciseAccountNotOwnedByHolder.into())
+        }
+        Ok(())
+    }
+
+    /// Keepers can auto exercise for `AUTO_EXERCISE_WINDOW` seconds after expiration,
+    /// once the settlement price is recorded. Writers can't reclaim the underlying
+    /// assets until the window closes, unless the options settled out of the money.
+    pub fn in_window(ctx: &Context<AutoExercise>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let expiration = ctx.accounts.option_market.expiration_unix_timestamp;
+        if now < expiration || now > expiration.saturating_add(AUTO_EXERCISE_WINDOW) {
+            return Err(errors::ErrorCode::AutoExerciseWindowNotOpen.into())
+        }
+        if ctx.accounts.option_market.settlement_slot == 0 {
+            return Err(errors::ErrorCode::SettlementPriceNotRecorded.into())
+        }
+        Ok(())
+    }
+}
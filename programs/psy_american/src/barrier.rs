@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{OptionMarket, admin, errors, oracle::OracleConfig};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BarrierDirection {
+    /// Knocked out when the price rises to the barrier
+    UpAndOut,
+    /// Knocked out when the price falls to the barrier
+    DownAndOut,
+}
+
+#[account]
+/// The price at which an OptionMarket is knocked out. Prices are the value of
+/// `underlying_amount_per_contract` in the quote asset's native units.
+pub struct KnockOutBarrier {
+    /// The OptionMarket the barrier applies to
+    pub option_market: Pubkey,
+    pub barrier_price: u64,
+    pub direction: BarrierDirection,
+    /// Bump seed for the KnockOutBarrier PDA
+    pub bump_seed: u8,
+}
+
+impl KnockOutBarrier {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 1;
+
+    pub fn is_breached(&self, contract_value: u64) -> bool {
+        match self.direction {
+            BarrierDirection::UpAndOut => contract_value >= self.barrier_price,
+            BarrierDirection::DownAndOut => contract_value <= self.barrier_price,
+        }
+    }
+}
+
+#[event]
+/// Record of an OptionMarket being knocked out
+pub struct KnockedOut {
+    pub option_market: Pubkey,
+    pub oracle: Pubkey,
+    pub contract_value: u64,
+    pub publish_time: i64,
+}
+
+/// Validate the OptionMarket has not been knocked out
+pub fn not_knocked_out(option_market: &OptionMarket) -> Result<()> {
+    if option_market.knocked_out {
+        return Err(errors::ErrorCode::OptionMarketKnockedOut.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitKnockOutBarrier<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    /// The barrier can only be added before anyone holds the options
+    #[account(
+        address = option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket,
+        constraint = option_mint.supply == 0 @ errors::ErrorCode::BarrierMarketAlreadyMinted
+    )]
+    pub option_mint: Box<Account<'info, Mint>>,
+    /// Knock outs can't be triggered without an oracle, so it must be configured first
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump = oracle_config.bump_seed,
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"knockOutBarrier"],
+        bump,
+        payer = admin,
+        space = KnockOutBarrier::SPACE
+    )]
+    pub knock_out_barrier: Box<Account<'info, KnockOutBarrier>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TriggerKnockOut<'info> {
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"knockOutBarrier"],
+        bump = knock_out_barrier.bump_seed,
+    )]
+    pub knock_out_barrier: Box<Account<'info, KnockOutBarrier>>,
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump = oracle_config.bump_seed,
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+    /// CHECK: Validated against the OracleConfig when the price is loaded
+    pub oracle: AccountInfo<'info>,
+}
+impl<'info> TriggerKnockOut<'info> {
+    pub fn unexpired_market(ctx: &Context<TriggerKnockOut>) -> Result<()> {
+        not_knocked_out(&ctx.accounts.option_market)?;
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantKnockOut.into())
+        }
+        Ok(())
+    }
+}
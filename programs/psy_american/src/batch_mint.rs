@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::{OptionMarket, errors, fees};
+
+/// The remaining accounts each leg of a batch mint takes, in order: the OptionMarket, its
+/// option mint, the OptionToken destination, its writer token mint, the WriterToken
+/// destination, its underlying asset pool and its mint fee account. Markets without a mint
+/// fee still take the slot, it's ignored.
+pub const BATCH_MINT_ACCOUNTS_PER_LEG: usize = 7;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct BatchMintLeg {
+    pub option_market: Pubkey,
+    pub size: u64,
+}
+
+/// Validate a leg's accounts against its OptionMarket
+pub fn validate_batch_mint_leg(
+    option_market: &Account<OptionMarket>,
+    leg: &BatchMintLeg,
+    leg_accounts: &[AccountInfo],
+    underlying_asset_mint: &Pubkey,
+    unix_timestamp: i64,
+) -> Result<()> {
+    if option_market.key() != leg.option_market {
+        return Err(errors::ErrorCode::BatchMintAccountsDoNotMatchLegs.into())
+    }
+    if option_market.underlying_asset_mint != *underlying_asset_mint {
+        return Err(errors::ErrorCode::UnderlyingAssetMintDoesNotMatchMarket.into())
+    }
+    if *leg_accounts[1].key != option_market.option_mint {
+        return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+    }
+    if *leg_accounts[3].key != option_market.writer_token_mint {
+        return Err(errors::ErrorCode::WriterTokenMintDoesNotMatchMarket.into())
+    }
+    if *leg_accounts[5].key != option_market.underlying_asset_pool {
+        return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+    }
+    if option_market.expiration_unix_timestamp < unix_timestamp {
+        return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+    }
+    if leg.size == 0 {
+        return Err(errors::ErrorCode::SizeCantBeLessThanEqZero.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct BatchMint<'info> {
+    /// The owner of the `underlying_asset_src`
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    /// Collateral and underlying mint fees for every leg are pulled from this account, one
+    /// transfer into each leg's pool
+    #[account(mut)]
+    pub underlying_asset_src: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for legs too small to take an underlying fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> BatchMint<'info> {
+    pub fn accounts(ctx: &Context<BatchMint>, legs: &[BatchMintLeg]) -> Result<()> {
+        if legs.is_empty() || ctx.remaining_accounts.len() != legs.len() * BATCH_MINT_ACCOUNTS_PER_LEG {
+            return Err(errors::ErrorCode::BatchMintAccountsDoNotMatchLegs.into())
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{errors, oracle::{self, OracleSource}, settlement::{SETTLEMENT_WINDOW, expiration_in_settlement_window}};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BinaryPayoutCondition {
+    /// Pays out when the settlement price is above the strike
+    Above,
+    /// Pays out when the settlement price is below the strike
+    Below,
+}
+
+#[account]
+/// A cash-or-nothing market. Each contract pays `payout_per_contract` of the quote asset
+/// when the settlement price meets the payout condition, and nothing otherwise. Writers
+/// lock the payout in the quote asset pool when minting.
+pub struct BinaryMarket {
+    /// The SPL Token mint address for the tokens that denote an option
+    pub option_mint: Pubkey,
+    /// The SPL Token mint address for Writer Tokens that denote a written option
+    pub writer_token_mint: Pubkey,
+    /// The asset the oracle prices, no underlying is ever held by the market
+    pub underlying_asset_mint: Pubkey,
+    /// The asset the payout is locked and paid in
+    pub quote_asset_mint: Pubkey,
+    /// The amount of the underlying asset the strike and settlement price are quoted for
+    pub underlying_amount_per_contract: u64,
+    /// The value of `underlying_amount_per_contract` in the quote asset's native units the
+    /// settlement price is compared against
+    pub strike: u64,
+    /// The amount of quote asset each contract pays out
+    pub payout_per_contract: u64,
+    pub payout_condition: BinaryPayoutCondition,
+    /// The Unix timestamp at which the contracts in this market expire
+    pub expiration_unix_timestamp: i64,
+    /// Address for the pool that holds the locked payouts
+    pub quote_asset_pool: Pubkey,
+    /// How the oracle account is parsed
+    pub oracle_source: OracleSource,
+    /// The oracle that settles the market
+    pub oracle: Pubkey,
+    /// Decimals of the underlying asset mint
+    pub underlying_decimals: u8,
+    /// Decimals of the quote asset mint
+    pub quote_decimals: u8,
+    /// The value of `underlying_amount_per_contract` at expiration, in the quote asset's
+    /// native units. Written once by `record_binary_settlement_price`
+    pub settlement_price: u64,
+    /// The slot the settlement price was recorded in, 0 until it is recorded
+    pub settlement_slot: u64,
+    /// Bump seed for the market PDA
+    pub bump_seed: u8,
+}
+
+impl BinaryMarket {
+    pub const SPACE: usize = 8 + 32 * 4 + 8 * 3 + 1 + 8 + 32 + 1 + 32 + 1 + 1 + 8 + 8 + 1;
+
+    /// Whether the settlement window passed without a price being recorded. Writers get
+    /// the payouts back, as no side can be paid out.
+    pub fn settlement_lapsed(&self, timestamp: i64) -> bool {
+        self.settlement_slot == 0
+            && timestamp > self.expiration_unix_timestamp.saturating_add(SETTLEMENT_WINDOW)
+    }
+
+    /// Whether the option holders receive the payout at the recorded settlement price
+    pub fn pays_holders(&self) -> bool {
+        match self.payout_condition {
+            BinaryPayoutCondition::Above => self.settlement_price > self.strike,
+            BinaryPayoutCondition::Below => self.settlement_price < self.strike,
+        }
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(
+    underlying_amount_per_contract: u64,
+    strike: u64,
+    payout_per_contract: u64,
+    expiration_unix_timestamp: i64,
+    payout_condition: BinaryPayoutCondition,
+)]
+pub struct InitializeBinaryMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Parsed according to `oracle_source` before the market is created
+    pub oracle: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&binary_market.key().to_bytes()[..], b"optionToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = binary_market
+    )]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&binary_market.key().to_bytes()[..], b"writerToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = binary_market
+    )]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&binary_market.key().to_bytes()[..], b"quoteAssetPool"],
+        bump,
+        payer = authority,
+        token::mint = quote_asset_mint,
+        token::authority = binary_market,
+    )]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    // The oracle is part of the seeds so a market can't be created with a different one
+    #[account(
+        init,
+        seeds = [
+            underlying_asset_mint.key().as_ref(),
+            quote_asset_mint.key().as_ref(),
+            &underlying_amount_per_contract.to_le_bytes(),
+            &strike.to_le_bytes(),
+            &payout_per_contract.to_le_bytes(),
+            &expiration_unix_timestamp.to_le_bytes(),
+            &[payout_condition as u8],
+            oracle.key().as_ref(),
+            b"binaryMarket"
+        ],
+        bump,
+        payer = authority,
+        space = BinaryMarket::SPACE
+    )]
+    pub binary_market: Box<Account<'info, BinaryMarket>>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> InitializeBinaryMarket<'info> {
+    pub fn accounts(
+        ctx: &Context<InitializeBinaryMarket>,
+        underlying_amount_per_contract: u64,
+        strike: u64,
+        payout_per_contract: u64,
+        expiration_unix_timestamp: i64,
+        oracle_source: OracleSource,
+    ) -> Result<()> {
+        if expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::ExpirationIsInThePast.into())
+        }
+        if underlying_amount_per_contract == 0 || strike == 0 || payout_per_contract == 0 {
+            return Err(errors::ErrorCode::QuoteOrUnderlyingAmountCannotBe0.into())
+        }
+        if ctx.accounts.underlying_asset_mint.key() == ctx.accounts.quote_asset_mint.key() {
+            return Err(errors::ErrorCode::QuoteAndUnderlyingAssetMustDiffer.into())
+        }
+        oracle::validate_oracle(oracle_source, &ctx.accounts.oracle)
+    }
+}
+
+#[derive(Accounts)]
+pub struct MintBinaryOption<'info> {
+    /// The owner of the `quote_asset_src`
+    pub user_authority: Signer<'info>,
+    pub binary_market: Box<Account<'info, BinaryMarket>>,
+    #[account(mut)]
+    pub quote_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = binary_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = binary_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = binary_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> MintBinaryOption<'info> {
+    pub fn unexpired_market(ctx: &Context<MintBinaryOption>) -> Result<()> {
+        if ctx.accounts.binary_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordBinarySettlementPrice<'info> {
+    #[account(mut)]
+    pub binary_market: Box<Account<'info, BinaryMarket>>,
+    /// CHECK: Checked against the BinaryMarket and parsed according to its oracle source
+    #[account(address = binary_market.oracle @ errors::ErrorCode::OracleDoesNotMatchConfig)]
+    pub oracle: AccountInfo<'info>,
+}
+impl<'info> RecordBinarySettlementPrice<'info> {
+    pub fn unrecorded(ctx: &Context<RecordBinarySettlementPrice>) -> Result<()> {
+        let binary_market = &ctx.accounts.binary_market;
+        // The settlement price can never be overwritten
+        if binary_market.settlement_slot != 0 {
+            return Err(errors::ErrorCode::SettlementPriceAlreadyRecorded.into())
+        }
+        if !expiration_in_settlement_window(binary_market.expiration_unix_timestamp, Clock::get()?.unix_timestamp) {
+            return Err(errors::ErrorCode::OutsideSettlementWindow.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimBinaryPayout<'info> {
+    /// The owner of the `claim_token_src`
+    pub user_authority: Signer<'info>,
+    pub binary_market: Box<Account<'info, BinaryMarket>>,
+    /// Either the market's option mint or its writer token mint
+    #[account(mut)]
+    pub claim_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub claim_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = binary_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> ClaimBinaryPayout<'info> {
+    pub fn accounts(ctx: &Context<ClaimBinaryPayout>) -> Result<()> {
+        let binary_market = &ctx.accounts.binary_market;
+        let now = Clock::get()?.unix_timestamp;
+        if now <= binary_market.expiration_unix_timestamp {
+            return Err(errors::ErrorCode::BinaryMarketNotExpired.into())
+        }
+        let is_holder = ctx.accounts.claim_token_mint.key() == binary_market.option_mint;
+        if !is_holder && ctx.accounts.claim_token_mint.key() != binary_market.writer_token_mint {
+            return Err(errors::ErrorCode::BinaryClaimMintDoesNotMatchMarket.into())
+        }
+        if binary_market.settlement_lapsed(now) {
+            if is_holder {
+                return Err(errors::ErrorCode::BinaryClaimNotInTheMoney.into())
+            }
+            return Ok(())
+        }
+        if binary_market.settlement_slot == 0 {
+            return Err(errors::ErrorCode::SettlementPriceNotRecorded.into())
+        }
+        // Holders are paid when the condition is met, writers get the payout back otherwise
+        if is_holder != binary_market.pays_holders() {
+            return Err(errors::ErrorCode::BinaryClaimNotInTheMoney.into())
+        }
+        Ok(())
+    }
+}
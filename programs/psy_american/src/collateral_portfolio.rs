@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, fees};
+
+/// The most OptionMarkets a CollateralPortfolio can write in
+pub const MAX_PORTFOLIO_MARKETS: usize = 32;
+
+#[account]
+/// Underlying collateral held once for every OptionMarket `owner` writes on one
+/// underlying, quote and expiration. Minting in a member market moves the underlying
+/// for the options from the `underlying_vault` into the market's pool, so they are
+/// backed like any other mint.
+///
+/// Written calls don't net against each other: each one can be exercised for its
+/// underlying as the price rises, so the worst-case payout of the portfolio is the sum
+/// of its markets' and every mint is fully backed. The portfolio saves the owner from
+/// funding each market separately, not collateral.
+pub struct CollateralPortfolio {
+    pub owner: Pubkey,
+    pub underlying_asset_mint: Pubkey,
+    pub quote_asset_mint: Pubkey,
+    pub expiration_unix_timestamp: i64,
+    /// Holds the shared underlying collateral
+    pub underlying_vault: Pubkey,
+    /// The OptionMarkets the portfolio can mint in
+    pub markets: Vec<Pubkey>,
+    /// Bump seed for the CollateralPortfolio PDA
+    pub bump_seed: u8,
+}
+
+impl CollateralPortfolio {
+    pub const SPACE: usize = 8 + 32 * 3 + 8 + 32 + (4 + MAX_PORTFOLIO_MARKETS * 32) + 1;
+
+    pub fn has_market(&self, option_market: &Pubkey) -> bool {
+        self.markets.contains(option_market)
+    }
+
+    /// The underlying the vault has to hold to mint `size` options in `option_market`,
+    /// including the mint fee
+    pub fn mint_cost(option_market: &OptionMarket, size: u64) -> Result<u64> {
+        option_market.underlying_amount_per_contract
+            .checked_add(fees::fee_amount(option_market.underlying_amount_per_contract))
+            .and_then(|per_contract| per_contract.checked_mul(size))
+            .ok_or_else(|| errors::ErrorCode::NumberOverflow.into())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(expiration_unix_timestamp: i64)]
+pub struct InitCollateralPortfolio<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [
+            &owner.key().to_bytes()[..],
+            &underlying_asset_mint.key().to_bytes()[..],
+            &quote_asset_mint.key().to_bytes()[..],
+            &expiration_unix_timestamp.to_le_bytes(),
+            b"collateralPortfolio"
+        ],
+        bump,
+        payer = owner,
+        space = CollateralPortfolio::SPACE
+    )]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    #[account(init,
+        seeds = [&collateral_portfolio.key().to_bytes()[..], b"underlyingVault"],
+        bump,
+        payer = owner,
+        token::mint = underlying_asset_mint,
+        token::authority = collateral_portfolio,
+    )]
+    pub underlying_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddPortfolioMarket<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, has_one = owner @ errors::ErrorCode::PortfolioOwnerDoesNotMatch)]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+}
+impl<'info> AddPortfolioMarket<'info> {
+    pub fn accounts(ctx: &Context<AddPortfolioMarket>) -> Result<()> {
+        let portfolio = &ctx.accounts.collateral_portfolio;
+        let option_market = &ctx.accounts.option_market;
+        if option_market.underlying_asset_mint != portfolio.underlying_asset_mint
+            || option_market.quote_asset_mint != portfolio.quote_asset_mint
+            || option_market.expiration_unix_timestamp != portfolio.expiration_unix_timestamp {
+            return Err(errors::ErrorCode::PortfolioMarketDoesNotMatch.into())
+        }
+        if portfolio.has_market(&option_market.key()) {
+            return Err(errors::ErrorCode::PortfolioMarketAlreadyAdded.into())
+        }
+        if portfolio.markets.len() >= MAX_PORTFOLIO_MARKETS {
+            return Err(errors::ErrorCode::PortfolioIsFull.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DepositPortfolioCollateral<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        has_one = owner @ errors::ErrorCode::PortfolioOwnerDoesNotMatch,
+        has_one = underlying_vault,
+    )]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    #[account(mut)]
+    pub underlying_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPortfolioCollateral<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        has_one = owner @ errors::ErrorCode::PortfolioOwnerDoesNotMatch,
+        has_one = underlying_vault,
+    )]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    #[account(mut)]
+    pub underlying_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+#[derive(Accounts)]
+pub struct MintFromPortfolio<'info> {
+    /// Pays the SOL mint fee of markets too small to take an underlying fee from
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut,
+        has_one = owner @ errors::ErrorCode::PortfolioOwnerDoesNotMatch,
+        has_one = underlying_vault,
+    )]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    #[account(mut)]
+    pub underlying_vault: Box<Account<'info, TokenAccount>>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for markets too small to take an underlying fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> MintFromPortfolio<'info> {
+    pub fn accounts(ctx: &Context<MintFromPortfolio>, size: u64) -> Result<()> {
+        if !ctx.accounts.collateral_portfolio.has_market(&ctx.accounts.option_market.key()) {
+            return Err(errors::ErrorCode::PortfolioMarketNotFound.into())
+        }
+        let required = CollateralPortfolio::mint_cost(&ctx.accounts.option_market, size)?;
+        if ctx.accounts.underlying_vault.amount < required {
+            return Err(errors::ErrorCode::PortfolioUndercollateralized.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<MintFromPortfolio>) -> Result<()> {
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseCollateralPortfolio<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut,
+        has_one = owner @ errors::ErrorCode::PortfolioOwnerDoesNotMatch,
+        has_one = underlying_vault,
+        close = owner
+    )]
+    pub collateral_portfolio: Box<Account<'info, CollateralPortfolio>>,
+    #[account(mut)]
+    pub underlying_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
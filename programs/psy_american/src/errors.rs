@@ -58,4 +58,168 @@ pub enum ErrorCode {
   CannotPruneActiveMarket,
   #[msg("Numberical overflow")]
   NumberOverflow,
+  #[msg("Signer is not the PsyOptions admin")]
+  InvalidAdmin,
+  #[msg("Referral account is not an allowed recipient for this market")]
+  InvalidReferral,
+  #[msg("Referral allowlist must have between 1 and 10 recipients")]
+  InvalidReferralAllowlist,
+  #[msg("ReferralConfig does not match the Serum market")]
+  ReferralConfigDoesNotMatchMarket,
+  #[msg("Underlying asset mint does not match the value on the OptionMarket")]
+  UnderlyingAssetMintDoesNotMatchMarket,
+  #[msg("Quote asset mint does not match the value on the OptionMarket")]
+  QuoteAssetMintDoesNotMatchMarket,
+  #[msg("Oracle account is not a valid price account")]
+  InvalidOracleAccount,
+  #[msg("Oracle price is not available")]
+  InvalidOraclePrice,
+  #[msg("Oracle account does not match the OracleConfig")]
+  OracleDoesNotMatchConfig,
+  #[msg("OracleConfig does not match the OptionMarket")]
+  OracleConfigDoesNotMatchMarket,
+  #[msg("Oracle price is stale")]
+  OraclePriceIsStale,
+  #[msg("Tolerance must be <= 10,000 bps and max price must be > 0")]
+  InvalidPriceBand,
+  #[msg("PriceBand does not match the OptionMarket")]
+  PriceBandDoesNotMatchMarket,
+  #[msg("Serum market's pc mint must be the OptionMarket's quote asset")]
+  PcMintIsNotQuoteAsset,
+  #[msg("Order price is above the market's maximum")]
+  OrderPriceAboveMaximum,
+  #[msg("Order price is below the option's intrinsic value")]
+  OrderPriceBelowIntrinsicValue,
+  #[msg("Position limit must be > 0")]
+  InvalidPositionLimit,
+  #[msg("PositionLimit does not match the OptionMarket")]
+  PositionLimitDoesNotMatchMarket,
+  #[msg("Order would exceed the owner's position limit")]
+  PositionLimitExceeded,
+  #[msg("TradingAllowlist does not match the Serum market")]
+  TradingAllowlistDoesNotMatchMarket,
+  #[msg("TradingAllowlist is full")]
+  TradingAllowlistFull,
+  #[msg("Trader is already on the TradingAllowlist")]
+  TraderAlreadyAllowlisted,
+  #[msg("Trader is not on the market's TradingAllowlist")]
+  TraderNotAllowlisted,
+  #[msg("RFQ leg does not reference this OptionMarket")]
+  RfqLegDoesNotMatchOptionMarket,
+  #[msg("RFQ leg's OptionMarket is expired")]
+  RfqLegOptionMarketExpired,
+  #[msg("Escrow mint does not match the RFQ leg's asset")]
+  RfqEscrowMintDoesNotMatchLeg,
+  #[msg("Signer is not the escrow's settlement authority")]
+  RfqSettlementAuthorityDoesNotMatch,
+  #[msg("Depositor does not match the escrow")]
+  RfqDepositorDoesNotMatch,
+  #[msg("Destination is not owned by the escrow's party")]
+  RfqDestinationOwnerDoesNotMatch,
+  #[msg("Auto exercise is only allowed in the window after expiration")]
+  AutoExerciseWindowNotOpen,
+  #[msg("Auto exercise accounts must be owned by the option holder")]
+  AutoExerciseAccountNotOwnedByHolder,
+  #[msg("Option is not in the money")]
+  OptionNotInTheMoney,
+  #[msg("Settlement price has already been recorded")]
+  SettlementPriceAlreadyRecorded,
+  #[msg("Settlement price can only be recorded shortly after expiration")]
+  OutsideSettlementWindow,
+  #[msg("Settlement price has not been recorded")]
+  SettlementPriceNotRecorded,
+  #[msg("TWAP window must be between 1 minute and 1 day")]
+  InvalidTwapWindow,
+  #[msg("Serum market does not trade the OptionMarket's underlying against its quote asset")]
+  TwapSerumMarketDoesNotMatchAssets,
+  #[msg("Event queue does not match the TwapAccumulator's Serum market")]
+  TwapEventQueueDoesNotMatch,
+  #[msg("The TWAP can only be cranked in its window before expiration")]
+  OutsideTwapWindow,
+  #[msg("No TWAP samples were taken in the window")]
+  TwapHasNoSamples,
+  #[msg("Not enough net exercised contracts to burn the WriterTokens against")]
+  NotEnoughNetExercisedContracts,
+  #[msg("Not enough underlying assets in the pool")]
+  NotEnoughUnderlyingAssetsInPool,
+  #[msg("Flash exercise callback can't be this program")]
+  InvalidFlashExerciseCallback,
+  #[msg("Flash exercise callback did not pay the strike and exercise fee into the quote asset pool")]
+  FlashExerciseNotRepaid,
+  #[msg("Exercise windows must be sorted, not overlap and end by expiration")]
+  InvalidExerciseSchedule,
+  #[msg("OptionMarket has an exercise schedule, it must be the last remaining account")]
+  ExerciseScheduleMissing,
+  #[msg("ExerciseSchedule does not match the OptionMarket")]
+  ExerciseScheduleDoesNotMatchMarket,
+  #[msg("OptionMarket can't be exercised outside its exercise windows")]
+  OutsideExerciseWindow,
+  #[msg("Claimed tokens are neither the BinaryMarket's OptionTokens nor its WriterTokens")]
+  BinaryClaimMintDoesNotMatchMarket,
+  #[msg("Claimed tokens pay nothing at the settlement price")]
+  BinaryClaimNotInTheMoney,
+  #[msg("OptionMarket was knocked out")]
+  OptionMarketKnockedOut,
+  #[msg("A barrier can't be added after options are minted")]
+  BarrierMarketAlreadyMinted,
+  #[msg("OptionMarket is expired, can't knock out")]
+  OptionMarketExpiredCantKnockOut,
+  #[msg("Oracle price does not breach the barrier")]
+  BarrierNotBreached,
+  #[msg("Spread markets must share the underlying, quote, contract size and expiration")]
+  SpreadMarketsDoNotMatch,
+  #[msg("The long leg of a spread must have the lower strike")]
+  SpreadLongStrikeMustBeLower,
+  #[msg("The long leg of a spread must be exercisable until expiration")]
+  SpreadLongMarketNotExercisable,
+  #[msg("Owner does not match the SpreadPosition")]
+  SpreadOwnerDoesNotMatch,
+  #[msg("Size exceeds the short options written by the SpreadPosition")]
+  SpreadSizeExceeded,
+  #[msg("Owner does not match the CollateralPortfolio")]
+  PortfolioOwnerDoesNotMatch,
+  #[msg("OptionMarket must share the portfolio's underlying, quote and expiration")]
+  PortfolioMarketDoesNotMatch,
+  #[msg("OptionMarket is already in the portfolio")]
+  PortfolioMarketAlreadyAdded,
+  #[msg("CollateralPortfolio can't hold any more markets")]
+  PortfolioIsFull,
+  #[msg("OptionMarket is not in the portfolio")]
+  PortfolioMarketNotFound,
+  #[msg("Portfolio collateral does not cover the underlying and mint fee")]
+  PortfolioUndercollateralized,
+  #[msg("Size exceeds the portfolio's unfunded options in the market")]
+  PortfolioWrittenSizeExceeded,
+  #[msg("Put fee recipient must be a token account of the quote asset")]
+  PutFeeTokenMustMatchQuoteAsset,
+  #[msg("Positions can only roll between markets with the same underlying and contract size")]
+  RollMarketsDoNotMatch,
+  #[msg("Remaining accounts do not match the batch mint legs")]
+  BatchMintAccountsDoNotMatchLegs,
+  #[msg("Chain strikes must be ascending, non zero and fit in the OptionChain")]
+  InvalidChainStrikes,
+  #[msg("Remaining accounts do not match the chain strikes")]
+  ChainAccountsDoNotMatchStrikes,
+  #[msg("The MarketRegistryPage can't hold any more markets")]
+  RegistryPageIsFull,
+  #[msg("Asset symbols must be 1 to 10 uppercase letters or digits")]
+  InvalidAssetSymbol,
+  #[msg("The generated mint name is too long")]
+  MintNameTooLong,
+  #[msg("The OptionMarket's underlying asset must be native SOL")]
+  UnderlyingAssetMustBeNativeMint,
+  #[msg("The OptionMarket's quote asset must be native SOL")]
+  QuoteAssetMustBeNativeMint,
+  #[msg("Writers can't close until the options settle out of the money or the auto exercise window closes")]
+  AutoExerciseWindowOpen,
+  #[msg("The oracle price must be published within seconds after expiration")]
+  SettlementPriceNotPublishedAtExpiration,
+  #[msg("The settlement price must be recorded from the OptionMarket's settlement source")]
+  WrongSettlementSource,
+  #[msg("BinaryMarket has not expired, payouts can't be claimed")]
+  BinaryMarketNotExpired,
+  #[msg("A new MarketRegistryPage can only follow a full page")]
+  RegistryPageIsNotFull,
+  #[msg("Metadata account is not the mint's Metaplex metadata address")]
+  MetadataAccountDoesNotMatchMint,
 }
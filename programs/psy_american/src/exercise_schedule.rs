@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{NewOptionMarket, OptionMarket, errors};
+
+/// The most exercise windows an ExerciseSchedule can hold
+pub const MAX_EXERCISE_WINDOWS: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ExerciseWindow {
+    /// Unix timestamp the window opens at
+    pub start: i64,
+    /// Unix timestamp the window closes at, inclusive
+    pub end: i64,
+}
+
+impl ExerciseWindow {
+    pub fn contains(&self, timestamp: i64) -> bool {
+        timestamp >= self.start && timestamp <= self.end
+    }
+}
+
+#[account]
+/// The windows a Bermudan OptionMarket can be exercised in. Written once when the market
+/// is initialized, the OptionMarket stores its hash.
+pub struct ExerciseSchedule {
+    pub option_market: Pubkey,
+    /// Sorted windows that don't overlap
+    pub windows: Vec<ExerciseWindow>,
+    /// Bump seed for the ExerciseSchedule PDA
+    pub bump_seed: u8,
+}
+
+impl ExerciseSchedule {
+    pub const SPACE: usize = 8 + 32 + (4 + MAX_EXERCISE_WINDOWS * 16) + 1;
+}
+
+/// The hash of the Borsh serialized windows, stored on the OptionMarket and part of its seeds
+pub fn exercise_schedule_hash(windows: &[ExerciseWindow]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(4 + windows.len() * 16);
+    data.extend_from_slice(&(windows.len() as u32).to_le_bytes());
+    for window in windows {
+        data.extend_from_slice(&window.start.to_le_bytes());
+        data.extend_from_slice(&window.end.to_le_bytes());
+    }
+    hash::hash(&data).to_bytes()
+}
+
+pub fn has_exercise_schedule(option_market: &OptionMarket) -> bool {
+    option_market.exercise_schedule_hash != [0u8; 32]
+}
+
+pub fn validate_exercise_windows(windows: &[ExerciseWindow], expiration_unix_timestamp: i64) -> Result<()> {
+    if windows.is_empty() || windows.len() > MAX_EXERCISE_WINDOWS {
+        return Err(errors::ErrorCode::InvalidExerciseSchedule.into())
+    }
+    for (i, window) in windows.iter().enumerate() {
+        if window.start >= window.end || window.end > expiration_unix_timestamp {
+            return Err(errors::ErrorCode::InvalidExerciseSchedule.into())
+        }
+        if let Some(next) = windows.get(i + 1) {
+            if window.end >= next.start {
+                return Err(errors::ErrorCode::InvalidExerciseSchedule.into())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate the OptionMarket can be exercised now. Markets without a schedule can be
+/// exercised any time before expiration. Scheduled markets take their ExerciseSchedule
+/// as the last of the remaining accounts.
+pub fn validate_exercise_window(option_market: &Account<OptionMarket>, remaining_accounts: &[AccountInfo]) -> Result<()> {
+    if !has_exercise_schedule(option_market) {
+        return Ok(())
+    }
+    let schedule_account = remaining_accounts.last().ok_or(errors::ErrorCode::ExerciseScheduleMissing)?;
+    let schedule = Account::<ExerciseSchedule>::try_from(schedule_account)?;
+    if schedule.option_market != option_market.key()
+        || exercise_schedule_hash(&schedule.windows) != option_market.exercise_schedule_hash {
+        return Err(errors::ErrorCode::ExerciseScheduleDoesNotMatchMarket.into())
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if !schedule.windows.iter().any(|window| window.contains(now)) {
+        return Err(errors::ErrorCode::OutsideExerciseWindow.into())
+    }
+    Ok(())
+}
+
+/// The accounts of `InitializeMarket`, with the schedule hash added to the OptionMarket
+/// seeds so a scheduled market never takes the address of the American market with the
+/// same terms.
+#[derive(Accounts)]
+#[instruction(
+    underlying_amount_per_contract: u64,
+    quote_amount_per_contract: u64,
+    expiration_unix_timestamp: i64,
+    bump_seed: u8,
+    windows: Vec<ExerciseWindow>
+)]
+pub struct InitializeMarketWithSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"optionToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = option_market
+    )]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"writerToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = option_market
+    )]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"quoteAssetPool"],
+        bump,
+        payer = authority,
+        token::mint = quote_asset_mint,
+        token::authority = option_market,
+    )]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"underlyingAssetPool"],
+        bump,
+        payer = authority,
+        token::mint = underlying_asset_mint,
+        token::authority = option_market,
+    )]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        seeds = [
+            underlying_asset_mint.key().as_ref(),
+            quote_asset_mint.key().as_ref(),
+            &underlying_amount_per_contract.to_le_bytes(),
+            &quote_amount_per_contract.to_le_bytes(),
+            &expiration_unix_timestamp.to_le_bytes(),
+            &exercise_schedule_hash(&windows)
+        ],
+        bump,
+        payer = authority,
+        space = 8 + std::mem::size_of::<OptionMarket>() + 300
+    )]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"exerciseSchedule"],
+        bump,
+        payer = authority,
+        space = ExerciseSchedule::SPACE
+    )]
+    pub exercise_schedule: Box<Account<'info, ExerciseSchedule>>,
+    /// The V1 fee owner
+    /// CHECK: Handled
+    pub fee_owner: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Unnecesary account, but left for backwards compatibility
+    pub associated_token_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+}
+impl<'info> InitializeMarketWithSchedule<'info> {
+    pub(crate) fn new_option_market(&mut self) -> NewOptionMarket<'_, 'info> {
+        NewOptionMarket {
+            underlying_asset_mint: self.underlying_asset_mint.key(),
+            quote_asset_mint: self.quote_asset_mint.key(),
+            option_mint: self.option_mint.key(),
+            writer_token_mint: self.writer_token_mint.key(),
+            underlying_asset_pool: self.underlying_asset_pool.key(),
+            quote_asset_pool: self.quote_asset_pool.key(),
+            clock: &self.clock,
+            option_market: &mut self.option_market,
+        }
+    }
+
+    /// The mints and pools are created with the OptionMarket as their authority, so only
+    /// the assets need checking
+    pub fn accounts(accounts: &InitializeMarketWithSchedule<'info>) -> Result<()> {
+        if accounts.underlying_asset_mint.key() == accounts.quote_asset_mint.key() {
+            return Err(errors::ErrorCode::QuoteAndUnderlyingAssetMustDiffer.into())
+        }
+        Ok(())
+    }
+}
@@ -48,3 +48,9 @@ pub fn fee_amount(asset_quantity: u64) -> u64 {
   let rate = fee_rate();
   rate.mul_u64(asset_quantity).floor()
 }
+
+/// Keepers that auto exercise options on behalf of holders earn 5bps of the
+/// underlying assets delivered.
+pub fn keeper_fee_amount(underlying_quantity: u64) -> u64 {
+  fee_bps(5).mul_u64(underlying_quantity).floor()
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, fees};
+
+#[derive(Accounts)]
+pub struct FlashExercise<'info> {
+    /// The owner of the `exerciser_option_token_src` account, pays the SOL exercise fee
+    /// of markets too small to take a quote fee from
+    #[account(mut)]
+    pub option_authority: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Any program but this one, it must pay the strike and exercise fee into the
+    /// quote pool
+    #[account(executable)]
+    pub callback_program: AccountInfo<'info>,
+    /// CHECK: Receives the SOL fee for markets too small to take a quote fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> FlashExercise<'info> {
+    pub fn accounts(ctx: &Context<FlashExercise>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if ctx.accounts.quote_asset_pool.key() != option_market.quote_asset_pool {
+            return Err(errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.option_mint.key() != option_market.option_mint {
+            return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_dest.mint != option_market.underlying_asset_mint {
+            return Err(errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset.into())
+        }
+        // A callback into this program could count the quote paid by another exercise
+        // as the repayment
+        if ctx.accounts.callback_program.key() == crate::ID {
+            return Err(errors::ErrorCode::InvalidFlashExerciseCallback.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<FlashExercise>) -> Result<()> {
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantExercise.into())
+        }
+        Ok(())
+    }
+}
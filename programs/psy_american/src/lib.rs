@@ -1,13 +1,61 @@
+pub mod admin;
+pub mod auto_exercise;
+pub mod barrier;
+pub mod batch_mint;
+pub mod binary_option;
+pub mod collateral_portfolio;
 pub mod errors;
+pub mod exercise_schedule;
 pub mod fees;
+pub mod flash_exercise;
+pub mod market_registry;
+pub mod mint_metadata;
+pub mod native_sol;
+pub mod net_exercise;
+pub mod option_chain;
+pub mod oracle;
+pub mod position_limit;
+pub mod price_band;
+pub mod put_option;
+pub mod referral_config;
+pub mod rfq;
+pub mod roll;
 pub mod serum_proxy;
+pub mod settlement;
+pub mod spread;
+pub mod trading_allowlist;
+pub mod twap;
 
 use anchor_lang::{AccountsExit, Key, prelude::*};
-use anchor_spl::token::{self, Burn, Mint, MintTo, TokenAccount, Transfer, Token};
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, TokenAccount, Transfer, Token};
 use anchor_spl::dex::{initialize_market as init_serum_market_instruction, InitializeMarket as SerumInitMarket};
 use spl_token::state::Account as SPLTokenAccount;
 use solana_program::{program::invoke, program_error::ProgramError, program_pack::Pack, system_instruction, system_program};
-use psyfi_serum_dex_permissioned::{MarketProxy, OpenOrdersPda, ReferralFees};
+use psyfi_serum_dex_permissioned::{MarketProxy, OpenOrdersPda};
+use std::convert::TryFrom;
+pub use auto_exercise::*;
+pub use barrier::*;
+pub use batch_mint::*;
+pub use binary_option::*;
+pub use collateral_portfolio::*;
+pub use exercise_schedule::*;
+pub use flash_exercise::*;
+pub use market_registry::*;
+pub use mint_metadata::*;
+pub use native_sol::*;
+pub use net_exercise::*;
+pub use option_chain::*;
+pub use oracle::*;
+pub use position_limit::*;
+pub use price_band::*;
+pub use put_option::*;
+pub use referral_config::*;
+pub use rfq::*;
+pub use roll::*;
+pub use settlement::*;
+pub use spread::*;
+pub use trading_allowlist::*;
+pub use twap::*;
 
 declare_id!("R2y9ip6mxmWUj4pt54jP2hz2dgvMozy9VTSwMWE7evs");
 
@@ -15,7 +63,7 @@ declare_id!("R2y9ip6mxmWUj4pt54jP2hz2dgvMozy9VTSwMWE7evs");
 pub mod psy_american {
     use super::*;
 
-    #[access_control(InitializeMarket::accounts(&ctx))]
+    #[access_control(InitializeMarket::accounts(&ctx.accounts))]
     /// Initialize a new PsyOptions market
     pub fn initialize_market(
         ctx: Context<InitializeMarket>, 
@@ -24,88 +72,259 @@ pub mod psy_american {
         expiration_unix_timestamp: i64,
         bump_seed: u8
     ) -> Result<()> {
-        // (nice to have) Validate the expiration is in the future
-        if expiration_unix_timestamp < ctx.accounts.clock.unix_timestamp {
-            return Err(errors::ErrorCode::ExpirationIsInThePast.into())
-        }
-        // check that underlying_amount_per_contract and quote_amount_per_contract are not 0
-        if underlying_amount_per_contract <= 0 || quote_amount_per_contract <= 0 {
-            return Err(errors::ErrorCode::QuoteOrUnderlyingAmountCannotBe0.into())
-        }
+        write_option_market(
+            ctx.accounts.new_option_market(),
+            ctx.remaining_accounts,
+            &ctx.bumps,
+            underlying_amount_per_contract,
+            quote_amount_per_contract,
+            expiration_unix_timestamp
+        )
+    }
 
-        let fee_accounts = validate_fee_accounts(
-            &ctx.remaining_accounts, 
-            &ctx.accounts.underlying_asset_mint.key(),
-            &ctx.accounts.quote_asset_mint.key(),
+    #[access_control(
+        InitializeMarketWithSchedule::accounts(&ctx.accounts)
+        validate_exercise_windows(&windows, expiration_unix_timestamp)
+    )]
+    /// Initialize a new PsyOptions market that can only be exercised in the given windows.
+    /// The schedule can't be changed afterwards.
+    pub fn initialize_market_with_schedule(
+        ctx: Context<InitializeMarketWithSchedule>,
+        underlying_amount_per_contract: u64,
+        quote_amount_per_contract: u64,
+        expiration_unix_timestamp: i64,
+        bump_seed: u8,
+        windows: Vec<ExerciseWindow>
+    ) -> Result<()> {
+        write_option_market(
+            ctx.accounts.new_option_market(),
+            ctx.remaining_accounts,
+            &ctx.bumps,
             underlying_amount_per_contract,
-            quote_amount_per_contract
+            quote_amount_per_contract,
+            expiration_unix_timestamp
         )?;
+        ctx.accounts.option_market.exercise_schedule_hash = exercise_schedule_hash(&windows);
 
-        // write the data to the OptionMarket account
-        let option_market = &mut ctx.accounts.option_market;
-        option_market.option_mint = *ctx.accounts.option_mint.to_account_info().key;
-        option_market.writer_token_mint = *ctx.accounts.writer_token_mint.to_account_info().key;
-        option_market.underlying_asset_mint = *ctx.accounts.underlying_asset_mint.to_account_info().key;
-        option_market.quote_asset_mint = *ctx.accounts.quote_asset_mint.to_account_info().key;
-        option_market.underlying_amount_per_contract = underlying_amount_per_contract;
-        option_market.quote_amount_per_contract = quote_amount_per_contract;
-        option_market.expiration_unix_timestamp = expiration_unix_timestamp;
-        option_market.underlying_asset_pool = *ctx.accounts.underlying_asset_pool.to_account_info().key;
-        option_market.quote_asset_pool = *ctx.accounts.quote_asset_pool.to_account_info().key;
-        option_market.mint_fee_account = fee_accounts.mint_fee_key;
-        option_market.exercise_fee_account = fee_accounts.exercise_fee_key;
-        option_market.expired = false;
-
-        match ctx.bumps.get("option_market") {
-            Some(bump) => {
-                option_market.bump_seed = *bump;
-            }
-            None => {
-                msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
-                panic!("Wrong bump key")
-            }
+        let exercise_schedule = &mut ctx.accounts.exercise_schedule;
+        exercise_schedule.option_market = ctx.accounts.option_market.key();
+        exercise_schedule.windows = windows;
+        exercise_schedule.bump_seed = *ctx.bumps.get("exercise_schedule").unwrap();
+        Ok(())
+    }
+
+    #[access_control(InitializeChain::accounts(&ctx, underlying_amount_per_contract, expiration_unix_timestamp, &strikes))]
+    /// Initialize an OptionMarket for each strike and index them in an OptionChain. The fee
+    /// accounts come first in the remaining accounts, as for `initialize_market`, followed
+    /// by each strike's accounts, see `CHAIN_ACCOUNTS_PER_STRIKE`.
+    pub fn initialize_chain<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, InitializeChain<'info>>,
+        underlying_amount_per_contract: u64,
+        expiration_unix_timestamp: i64,
+        strikes: Vec<u64>
+    ) -> Result<()> {
+        // The same fee accounts serve every strike
+        let mint_fee_accounts = (fees::fee_amount(underlying_amount_per_contract) > 0) as usize;
+        let exercise_fee_accounts = strikes.iter().any(|strike| fees::fee_amount(*strike) > 0) as usize;
+        let (fee_accounts, strike_accounts) = ctx.remaining_accounts
+            .split_at(std::cmp::min(mint_fee_accounts + exercise_fee_accounts, ctx.remaining_accounts.len()));
+        if strike_accounts.len() != strikes.len() * CHAIN_ACCOUNTS_PER_STRIKE {
+            return Err(errors::ErrorCode::ChainAccountsDoNotMatchStrikes.into())
+        }
+
+        let authority = ctx.accounts.authority.to_account_info();
+        let underlying_asset_mint = ctx.accounts.underlying_asset_mint.to_account_info();
+        let quote_asset_mint = ctx.accounts.quote_asset_mint.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let rent = ctx.accounts.rent.to_account_info();
+        let chain_market_accounts = ChainMarketAccounts {
+            authority: &authority,
+            underlying_asset_mint: &underlying_asset_mint,
+            quote_asset_mint: &quote_asset_mint,
+            token_program: &token_program,
+            system_program: &system_program,
+            rent: &rent,
+        };
+        let mut option_markets = Vec::with_capacity(strikes.len());
+        for (strike, strike_accounts) in strikes.iter().zip(strike_accounts.chunks(CHAIN_ACCOUNTS_PER_STRIKE)) {
+            let fee_accounts = validate_fee_accounts(
+                fee_accounts,
+                underlying_asset_mint.key,
+                quote_asset_mint.key,
+                underlying_amount_per_contract,
+                *strike
+            )?;
+            let mut option_market = create_chain_market(
+                &chain_market_accounts,
+                strike_accounts,
+                underlying_amount_per_contract,
+                *strike,
+                expiration_unix_timestamp
+            )?;
+            option_market.mint_fee_account = fee_accounts.mint_fee_key;
+            option_market.exercise_fee_account = fee_accounts.exercise_fee_key;
+            option_market.try_serialize(&mut &mut strike_accounts[0].try_borrow_mut_data()?[..])?;
+            option_markets.push(*strike_accounts[0].key);
         }
 
+        let option_chain = &mut ctx.accounts.option_chain;
+        option_chain.underlying_asset_mint = *underlying_asset_mint.key;
+        option_chain.quote_asset_mint = *quote_asset_mint.key;
+        option_chain.underlying_amount_per_contract = underlying_amount_per_contract;
+        option_chain.expiration_unix_timestamp = expiration_unix_timestamp;
+        option_chain.strikes = strikes;
+        option_chain.option_markets = option_markets;
+        option_chain.bump_seed = *ctx.bumps.get("option_chain").unwrap();
         Ok(())
     }
 
-    #[access_control(MintOption::unexpired_market(&ctx) MintOption::accounts(&ctx) validate_size(size))]
-    pub fn mint_option<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintOption<'info>>, size: u64) -> Result<()> {
+    /// Create the registry of markets for an OptionMarket's underlying and quote asset
+    pub fn init_quote_registry(ctx: Context<InitQuoteRegistry>) -> Result<()> {
+        init_registry(
+            &mut ctx.accounts.registry,
+            *ctx.bumps.get("registry").unwrap(),
+            &mut ctx.accounts.first_page,
+            *ctx.bumps.get("first_page").unwrap()
+        );
+        Ok(())
+    }
+
+    /// Create the registry of markets for an OptionMarket's underlying asset and expiration
+    pub fn init_expiry_registry(ctx: Context<InitExpiryRegistry>) -> Result<()> {
+        init_registry(
+            &mut ctx.accounts.registry,
+            *ctx.bumps.get("registry").unwrap(),
+            &mut ctx.accounts.first_page,
+            *ctx.bumps.get("first_page").unwrap()
+        );
+        Ok(())
+    }
+
+    #[access_control(OpenRegistryPage::current_page_is_full(&ctx))]
+    /// Open the page after the registry's full current page
+    pub fn open_registry_page(ctx: Context<OpenRegistryPage>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.current_page = registry.current_page
+            .checked_add(1)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+
+        let next_page = &mut ctx.accounts.next_page;
+        next_page.registry = registry.key();
+        next_page.page_index = registry.current_page;
+        next_page.bump_seed = *ctx.bumps.get("next_page").unwrap();
+        Ok(())
+    }
+
+    /// Append an OptionMarket to its quote and expiry registries. Anyone can register a
+    /// market, once. Markets aren't registered when they're initialized.
+    pub fn register_market(ctx: Context<RegisterMarket>) -> Result<()> {
+        ctx.accounts.market_registration.bump_seed = *ctx.bumps.get("market_registration").unwrap();
+
+        let option_market_key = ctx.accounts.option_market.key();
+        append_to_registry(
+            &mut ctx.accounts.quote_registry,
+            &mut ctx.accounts.quote_registry_page,
+            option_market_key
+        )?;
+        append_to_registry(
+            &mut ctx.accounts.expiry_registry,
+            &mut ctx.accounts.expiry_registry_page,
+            option_market_key
+        )
+    }
+
+    /// Name an OptionMarket's option and writer mints from the assets' Metaplex symbols,
+    /// e.g. `SOL-USDC 100C 2026-12-31`. Assets without Metaplex metadata are named by the
+    /// start of their mint address.
+    ///
+    /// Markets are named by this separate instruction so `initialize_market` keeps its
+    /// accounts and `initialize_chain` doesn't take four more accounts per strike, which
+    /// would leave fewer strikes per transaction. Clients send it in the same transaction
+    /// as the market's initialization.
+    pub fn init_mint_metadata(ctx: Context<InitMintMetadata>) -> Result<()> {
         let option_market = &ctx.accounts.option_market;
-        let mint_fee_account = validate_mint_fee_acct(
-            option_market,
-            ctx.remaining_accounts
+        let option_name = option_mint_name(
+            &asset_symbol(&option_market.underlying_asset_mint, &ctx.accounts.underlying_asset_metadata)?,
+            &asset_symbol(&option_market.quote_asset_mint, &ctx.accounts.quote_asset_metadata)?,
+            option_market.underlying_amount_per_contract,
+            ctx.accounts.underlying_asset_mint.decimals,
+            option_market.quote_amount_per_contract,
+            ctx.accounts.quote_asset_mint.decimals,
+            option_market.expiration_unix_timestamp,
+            OptionKind::Call
         )?;
+        let option_market_key = option_market.key();
+        let option_mint = option_market.option_mint;
+        let writer_token_mint = option_market.writer_token_mint;
+        write_mint_metadata(
+            &mut ctx.accounts.option_metadata,
+            *ctx.bumps.get("option_metadata").unwrap(),
+            &mut ctx.accounts.writer_metadata,
+            *ctx.bumps.get("writer_metadata").unwrap(),
+            option_market_key,
+            option_mint,
+            writer_token_mint,
+            option_name
+        );
+        Ok(())
+    }
 
-        // Take a mint fee
-        let mint_fee_amount_per_contract = fees::fee_amount(option_market.underlying_amount_per_contract);
-        if mint_fee_amount_per_contract > 0 {
-            match mint_fee_account {
-                Some(account) => {
-                    let cpi_accounts = Transfer {
-                        from: ctx.accounts.underlying_asset_src.to_account_info(),
-                        to: account.clone(),
-                        authority: ctx.accounts.user_authority.to_account_info().clone(),
-                    };
-                    let cpi_token_program = ctx.accounts.token_program.clone();
-                    let cpi_ctx = CpiContext::new(cpi_token_program.to_account_info(), cpi_accounts);
-                    let total_fee = mint_fee_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
-                    token::transfer(cpi_ctx, total_fee)?;
-                },
-                None => {}
-            }
-        } else {
-            // Handle NFT case with SOL fee
-            let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
-            invoke(
-                &system_instruction::transfer(&ctx.accounts.user_authority.key, &fees::fee_owner_key::ID, total_fee),
-            &[
-                ctx.accounts.user_authority.to_account_info().clone(),
-                ctx.accounts.fee_owner.clone(),
-                ctx.accounts.system_program.to_account_info().clone(),
-            ],
-            )?;
-        }
+    /// Name a PutMarket's option and writer mints, as `init_mint_metadata` does for calls
+    pub fn init_put_mint_metadata(ctx: Context<InitPutMintMetadata>) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
+        let option_name = option_mint_name(
+            &asset_symbol(&put_market.underlying_asset_mint, &ctx.accounts.underlying_asset_metadata)?,
+            &asset_symbol(&put_market.quote_asset_mint, &ctx.accounts.quote_asset_metadata)?,
+            put_market.underlying_amount_per_contract,
+            ctx.accounts.underlying_asset_mint.decimals,
+            put_market.quote_amount_per_contract,
+            ctx.accounts.quote_asset_mint.decimals,
+            put_market.expiration_unix_timestamp,
+            OptionKind::Put
+        )?;
+        let put_market_key = put_market.key();
+        let option_mint = put_market.option_mint;
+        let writer_token_mint = put_market.writer_token_mint;
+        write_mint_metadata(
+            &mut ctx.accounts.option_metadata,
+            *ctx.bumps.get("option_metadata").unwrap(),
+            &mut ctx.accounts.writer_metadata,
+            *ctx.bumps.get("writer_metadata").unwrap(),
+            put_market_key,
+            option_mint,
+            writer_token_mint,
+            option_name
+        );
+        Ok(())
+    }
+
+    #[access_control(MintOption::unexpired_market(&ctx) MintOption::accounts(&ctx) validate_size(size))]
+    pub fn mint_option<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintOption<'info>>, size: u64) -> Result<()> {
+        mint_with_fee(
+            MintWithFee {
+                option_market: &ctx.accounts.option_market,
+                user_authority: ctx.accounts.user_authority.to_account_info(),
+                underlying_asset_src: ctx.accounts.underlying_asset_src.to_account_info(),
+                underlying_asset_pool: ctx.accounts.underlying_asset_pool.to_account_info(),
+                option_mint: ctx.accounts.option_mint.to_account_info(),
+                minted_option_dest: ctx.accounts.minted_option_dest.to_account_info(),
+                writer_token_mint: ctx.accounts.writer_token_mint.to_account_info(),
+                minted_writer_token_dest: ctx.accounts.minted_writer_token_dest.to_account_info(),
+                fee_owner: ctx.accounts.fee_owner.clone(),
+                fee_payer: ctx.accounts.user_authority.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                authority_seeds: &[],
+            },
+            ctx.remaining_accounts,
+            size
+        )
+    }
+
+    #[access_control(MintOptionV2::unexpired_market(&ctx) MintOptionV2::accounts(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_size(size))]
+    pub fn mint_option_v2<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintOptionV2<'info>>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
 
         // Transfer the underlying assets to the underlying assets pool
         let cpi_accounts = Transfer {
@@ -118,14 +337,8 @@ pub mod psy_american {
         let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
         token::transfer(cpi_ctx, underlying_transfer_amount)?;
 
-        let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
-        ];
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
         let signer = &[&seeds[..]];
 
         // Mint a new OptionToken(s)
@@ -151,65 +364,112 @@ pub mod psy_american {
         Ok(())
     }
 
-    #[access_control(MintOptionV2::unexpired_market(&ctx) MintOptionV2::accounts(&ctx) validate_size(size))]
-    pub fn mint_option_v2<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintOptionV2<'info>>, size: u64) -> Result<()> {
+    #[access_control(MintOptionNative::unexpired_market(&ctx) MintOptionNative::accounts(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_size(size))]
+    /// Mint on a native SOL underlying market, taking the collateral and mint fee as
+    /// lamports from the signer and wrapping them into the pool and fee account.
+    pub fn mint_option_native<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintOptionNative<'info>>, size: u64) -> Result<()> {
         let option_market = &ctx.accounts.option_market;
+        let user_authority = ctx.accounts.user_authority.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
 
-        // Transfer the underlying assets to the underlying assets pool
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.underlying_asset_src.to_account_info(),
-            to: ctx.accounts.underlying_asset_pool.to_account_info(),
-            authority: ctx.accounts.user_authority.to_account_info().clone(),
-        };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new(cpi_token_program.to_account_info(), cpi_accounts);
-        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+        // Take a mint fee
+        let mint_fee_account = validate_mint_fee_acct(option_market, ctx.remaining_accounts)?;
+        let mint_fee_amount_per_contract = fees::fee_amount(option_market.underlying_amount_per_contract);
+        if mint_fee_amount_per_contract > 0 {
+            if let Some(account) = mint_fee_account {
+                let total_fee = mint_fee_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+                wrap_lamports(&user_authority, account, &system_program, &token_program, total_fee)?;
+            }
+        } else {
+            // Handle NFT case with SOL fee
+            let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+            invoke(
+                &system_instruction::transfer(user_authority.key, &fees::fee_owner_key::ID, total_fee),
+                &[user_authority.clone(), ctx.accounts.fee_owner.clone(), system_program.clone()],
+            )?;
+        }
 
-        let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
-        ];
+        // Wrap the underlying into the underlying assets pool
+        let underlying_amount = option_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        wrap_lamports(
+            &user_authority,
+            &ctx.accounts.underlying_asset_pool.to_account_info(),
+            &system_program,
+            &token_program,
+            underlying_amount
+        )?;
+
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
         let signer = &[&seeds[..]];
 
         // Mint a new OptionToken(s)
         let cpi_accounts = MintTo {
             mint: ctx.accounts.option_mint.to_account_info(),
             to: ctx.accounts.minted_option_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            authority: option_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
         token::mint_to(cpi_ctx, size)?;
 
         // Mint a new WriterToken(s)
         let cpi_accounts = MintTo {
             mint: ctx.accounts.writer_token_mint.to_account_info(),
             to: ctx.accounts.minted_writer_token_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            authority: option_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer);
         token::mint_to(cpi_ctx, size)?;
 
         Ok(())
     }
 
-    #[access_control(ExerciseOption::accounts(&ctx) ExerciseOption::unexpired_market(&ctx))]
+    #[access_control(BatchMint::accounts(&ctx, &legs))]
+    /// Mint in several OptionMarkets on the same underlying from one source account. Each
+    /// leg takes its accounts from the remaining accounts, see `BATCH_MINT_ACCOUNTS_PER_LEG`,
+    /// and pays its own market's mint fee. The collateral is transferred per leg, since each
+    /// market has its own underlying pool.
+    pub fn batch_mint<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, BatchMint<'info>>, legs: Vec<BatchMintLeg>) -> Result<()> {
+        let unix_timestamp = Clock::get()?.unix_timestamp;
+        let leg_accounts = ctx.remaining_accounts.chunks(BATCH_MINT_ACCOUNTS_PER_LEG);
+        for (leg, leg_accounts) in legs.iter().zip(leg_accounts) {
+            let option_market = Account::<OptionMarket>::try_from(&leg_accounts[0])?;
+            validate_batch_mint_leg(
+                &option_market,
+                leg,
+                leg_accounts,
+                &ctx.accounts.underlying_asset_src.mint,
+                unix_timestamp
+            )?;
+            mint_with_fee(
+                MintWithFee {
+                    option_market: &option_market,
+                    user_authority: ctx.accounts.user_authority.to_account_info(),
+                    underlying_asset_src: ctx.accounts.underlying_asset_src.to_account_info(),
+                    underlying_asset_pool: leg_accounts[5].clone(),
+                    option_mint: leg_accounts[1].clone(),
+                    minted_option_dest: leg_accounts[2].clone(),
+                    writer_token_mint: leg_accounts[3].clone(),
+                    minted_writer_token_dest: leg_accounts[4].clone(),
+                    fee_owner: ctx.accounts.fee_owner.clone(),
+                    fee_payer: ctx.accounts.user_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    authority_seeds: &[],
+                },
+                &leg_accounts[6..],
+                leg.size
+            )?;
+        }
+        Ok(())
+    }
+
+    #[access_control(ExerciseOption::accounts(&ctx) ExerciseOption::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_exercise_window(&ctx.accounts.option_market, ctx.remaining_accounts))]
     pub fn exercise_option<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ExerciseOption<'info>>, size: u64) -> Result<()> {
         let option_market = &ctx.accounts.option_market;
-        let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
-        ];
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
         let signer = &[&seeds[..]];
         // Burn the size of option tokens
         let cpi_ctx = CpiContext::new_with_signer(
@@ -264,195 +524,1522 @@ pub mod psy_american {
                 None => {}
             }
         } else {
-            // Handle NFT case with SOL fee
+            // Handle NFT case with SOL fee
+            let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+            invoke(
+                &system_instruction::transfer(&ctx.accounts.user_authority.key, &fees::fee_owner_key::ID, total_fee),
+            &[
+                ctx.accounts.user_authority.to_account_info().clone(),
+                ctx.accounts.fee_owner.clone(),
+                ctx.accounts.system_program.to_account_info().clone(),
+            ],
+            )?;
+        }
+        Ok(())
+    }
+
+    #[access_control(ExerciseOptionV2::accounts(&ctx) ExerciseOptionV2::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_exercise_window(&ctx.accounts.option_market, ctx.remaining_accounts))]
+    pub fn exercise_option_v2<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ExerciseOptionV2<'info>>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+        // Burn the size of option tokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.option_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the quote assets to the pool
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_src.to_account_info(),
+            to: ctx.accounts.quote_asset_pool.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new(cpi_token_program.to_account_info(), cpi_accounts);
+        let quote_transfer_amount = option_market.quote_amount_per_contract.checked_mul(size).unwrap();
+        token::transfer(cpi_ctx, quote_transfer_amount)?;
+
+        // Transfer the underlying assets from the pool to the exerciser
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+
+        Ok(())
+    }
+
+    #[access_control(ExerciseOptionNative::accounts(&ctx) ExerciseOptionNative::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_exercise_window(&ctx.accounts.option_market, ctx.remaining_accounts) validate_size(size))]
+    /// Exercise on a native SOL quote market, taking the quote and exercise fee as
+    /// lamports from the signer and wrapping them into the pool and fee account.
+    pub fn exercise_option_native<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ExerciseOptionNative<'info>>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let user_authority = ctx.accounts.user_authority.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of option tokens
+        let cpi_ctx = CpiContext::new(
+            token_program.clone(),
+            Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.option_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Wrap the quote assets into the pool
+        let quote_amount = option_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        wrap_lamports(
+            &user_authority,
+            &ctx.accounts.quote_asset_pool.to_account_info(),
+            &system_program,
+            &token_program,
+            quote_amount
+        )?;
+
+        // Transfer the underlying assets from the pool to the exerciser
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+        let underlying_amount = option_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_amount)?;
+
+        // Transfer an exercise fee
+        let exercise_fee_account = validate_exercise_fee_acct(option_market, ctx.remaining_accounts)?;
+        let exercise_fee_amount_per_contract = fees::fee_amount(option_market.quote_amount_per_contract);
+        if exercise_fee_amount_per_contract > 0 {
+            if let Some(account) = exercise_fee_account {
+                let total_fee = exercise_fee_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+                wrap_lamports(&user_authority, account, &system_program, &token_program, total_fee)?;
+            }
+        } else {
+            // Handle NFT case with SOL fee
+            let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+            invoke(
+                &system_instruction::transfer(user_authority.key, &fees::fee_owner_key::ID, total_fee),
+                &[user_authority.clone(), ctx.accounts.fee_owner.clone(), system_program],
+            )?;
+        }
+        Ok(())
+    }
+
+    #[access_control(FlashExercise::accounts(&ctx) FlashExercise::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_exercise_window(&ctx.accounts.option_market, ctx.remaining_accounts) validate_size(size))]
+    /// Exercise before paying the strike. The underlying is delivered first, then the
+    /// `callback_program` is invoked with `callback_data` and the remaining accounts. The
+    /// instruction fails unless the callback paid the strike and the exercise fee into the
+    /// quote asset pool. The exercise fee account, when the market takes a quote fee, is
+    /// the first remaining account and is not passed to the callback.
+    pub fn flash_exercise<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, FlashExercise<'info>>, size: u64, callback_data: Vec<u8>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let exercise_fee_account = validate_exercise_fee_acct(option_market, ctx.remaining_accounts)?;
+        let exercise_fee = fees::fee_amount(option_market.quote_amount_per_contract)
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        let quote_repayment = option_market.quote_amount_per_contract
+            .checked_mul(size)
+            .and_then(|strike| strike.checked_add(exercise_fee))
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        let quote_pool_before = ctx.accounts.quote_asset_pool.amount;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+        // Burn the size of option tokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.option_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the underlying assets from the pool to the exerciser
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_transfer_amount = option_market.underlying_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+
+        // Hand control to the callback, signer and writable privileges are passed through.
+        // The exercise fee account and a scheduled market's ExerciseSchedule, the last
+        // remaining account, are not passed on.
+        let callback_start = if exercise_fee_account.is_some() { 1 } else { 0 };
+        let callback_end = if has_exercise_schedule(&ctx.accounts.option_market) {
+            ctx.remaining_accounts.len() - 1
+        } else {
+            ctx.remaining_accounts.len()
+        };
+        let callback_accounts = ctx.remaining_accounts
+            .get(callback_start..callback_end)
+            .ok_or(errors::ErrorCode::ExerciseScheduleMissing)?;
+        let callback_ix = solana_program::instruction::Instruction {
+            program_id: ctx.accounts.callback_program.key(),
+            accounts: callback_accounts.iter().map(|account| {
+                if account.is_writable {
+                    AccountMeta::new(*account.key, account.is_signer)
+                } else {
+                    AccountMeta::new_readonly(*account.key, account.is_signer)
+                }
+            }).collect(),
+            data: callback_data,
+        };
+        let mut callback_accounts = callback_accounts.to_vec();
+        callback_accounts.push(ctx.accounts.callback_program.clone());
+        invoke(&callback_ix, &callback_accounts)?;
+
+        // Validate the strike was paid
+        ctx.accounts.quote_asset_pool.reload()?;
+        let quote_pool_after = ctx.accounts.quote_asset_pool.amount;
+        if quote_pool_after < quote_pool_before.checked_add(quote_repayment).ok_or(errors::ErrorCode::NumberOverflow)? {
+            return Err(errors::ErrorCode::FlashExerciseNotRepaid.into())
+        }
+
+        // Transfer the exercise fee out of the repayment
+        match exercise_fee_account {
+            Some(account) => {
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.quote_asset_pool.to_account_info(),
+                    to: account.clone(),
+                    authority: ctx.accounts.option_market.to_account_info(),
+                };
+                let cpi_token_program = ctx.accounts.token_program.clone();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+                token::transfer(cpi_ctx, exercise_fee)?;
+            },
+            None => {
+                // Handle NFT case with SOL fee
+                let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+                invoke(
+                    &system_instruction::transfer(ctx.accounts.option_authority.key, &fees::fee_owner_key::ID, total_fee),
+                &[
+                    ctx.accounts.option_authority.to_account_info(),
+                    ctx.accounts.fee_owner.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[access_control(ExerciseOptionNet::accounts(&ctx) ExerciseOptionNet::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_exercise_window(&ctx.accounts.option_market, ctx.remaining_accounts) validate_size(size))]
+    /// Exercise without paying the strike. The holder receives only the underlying worth
+    /// more than the strike at the oracle price, the pool keeps the rest for the writers
+    /// in place of the quote assets.
+    pub fn exercise_option_net(ctx: Context<ExerciseOptionNet>, size: u64) -> Result<()> {
+        let oracle_config = &ctx.accounts.oracle_config;
+        let oracle_price = oracle_config.load_price(&ctx.accounts.oracle, Clock::get()?.unix_timestamp)?;
+        let contract_value = oracle_price.contract_value(&ctx.accounts.option_market, oracle_config)?;
+        let (delivered, retained) = net_exercise_amounts(&ctx.accounts.option_market, contract_value, size)?;
+
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+        // Burn the size of option tokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.option_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the underlying in excess of the strike value to the exerciser
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, delivered)?;
+
+        let option_market = &mut ctx.accounts.option_market;
+        option_market.net_exercised_contracts = option_market.net_exercised_contracts
+            .checked_add(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        option_market.net_exercised_underlying = option_market.net_exercised_underlying
+            .checked_add(retained)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        Ok(())
+    }
+
+    #[access_control(RecordSettlementPrice::unrecorded(&ctx))]
+    /// Snapshot the oracle price at expiration on the OptionMarket. Anyone can call it,
+    /// but only once per market.
+    pub fn record_settlement_price(ctx: Context<RecordSettlementPrice>) -> Result<()> {
+        let oracle_config = &ctx.accounts.oracle_config;
+        let clock = Clock::get()?;
+        let oracle_price = oracle_config.load_price(&ctx.accounts.oracle, clock.unix_timestamp)?;
+        if !is_settlement_publish_time(ctx.accounts.option_market.expiration_unix_timestamp, oracle_price.publish_time) {
+            return Err(errors::ErrorCode::SettlementPriceNotPublishedAtExpiration.into())
+        }
+        let settlement_price = oracle_price.contract_value(&ctx.accounts.option_market, oracle_config)?;
+
+        let option_market = &mut ctx.accounts.option_market;
+        option_market.settlement_price = settlement_price;
+        option_market.settlement_oracle = oracle_config.oracle;
+        option_market.settlement_oracle_source = oracle_config.oracle_source;
+        option_market.settlement_slot = clock.slot;
+        Ok(())
+    }
+
+    #[access_control(InitTwapAccumulator::accounts(&ctx, window_minutes))]
+    /// Configure the Serum market whose trades are sampled for an OptionMarket's TWAP
+    /// settlement price
+    pub fn init_twap_accumulator(ctx: Context<InitTwapAccumulator>, window_minutes: u16) -> Result<()> {
+        let twap_accumulator = &mut ctx.accounts.twap_accumulator;
+        twap_accumulator.option_market = ctx.accounts.option_market.key();
+        twap_accumulator.serum_market = ctx.accounts.serum_market.key();
+        twap_accumulator.event_queue = ctx.accounts.event_queue.key();
+        twap_accumulator.window_minutes = window_minutes;
+        // Fills from before the accumulator existed are never sampled
+        twap_accumulator.last_seq_num = event_queue_seq_num(&ctx.accounts.event_queue)?;
+        twap_accumulator.bump_seed = *ctx.bumps.get("twap_accumulator").unwrap();
+        ctx.accounts.option_market.settlement_source = OracleSource::SerumTwap;
+        Ok(())
+    }
+
+    #[access_control(CrankTwap::in_window(&ctx))]
+    /// Sample the latest trade price from the Serum market's event queue. Anyone can
+    /// call it during the TWAP window. Nothing is sampled when there were no new fills or
+    /// the current bucket already has a sample, the previous sample holds until the
+    /// price changes.
+    pub fn crank_twap(ctx: Context<CrankTwap>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let twap_accumulator = &mut ctx.accounts.twap_accumulator;
+        if !twap_accumulator.bucket_is_open(&ctx.accounts.option_market, now) {
+            return Ok(())
+        }
+        let (fill, seq_num) = latest_maker_fill(&ctx.accounts.event_queue, twap_accumulator.last_seq_num)?;
+        twap_accumulator.last_seq_num = seq_num;
+        if let Some((native_pc, native_coin)) = fill {
+            let price = (native_pc as u128)
+                .checked_mul(ctx.accounts.option_market.underlying_amount_per_contract as u128)
+                .ok_or(errors::ErrorCode::NumberOverflow)?
+                / native_coin as u128;
+            twap_accumulator.push(TwapSample {
+                timestamp: now,
+                price: u64::try_from(price).map_err(|_| errors::ErrorCode::NumberOverflow)?,
+            });
+        }
+        Ok(())
+    }
+
+    #[access_control(RecordTwapSettlementPrice::unrecorded(&ctx))]
+    /// Record the TWAP of the underlying's Serum market over the accumulator's window as
+    /// the settlement price. Like `record_settlement_price` it can only be recorded once.
+    pub fn record_twap_settlement_price(ctx: Context<RecordTwapSettlementPrice>) -> Result<()> {
+        let twap_accumulator = &ctx.accounts.twap_accumulator;
+        let expiration = ctx.accounts.option_market.expiration_unix_timestamp;
+        let settlement_price = twap_accumulator
+            .twap(twap_accumulator.window_start(&ctx.accounts.option_market), expiration)
+            .ok_or(errors::ErrorCode::TwapHasNoSamples)?;
+
+        let option_market = &mut ctx.accounts.option_market;
+        option_market.settlement_price = settlement_price;
+        option_market.settlement_oracle = twap_accumulator.key();
+        option_market.settlement_oracle_source = OracleSource::SerumTwap;
+        option_market.settlement_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    #[access_control(AutoExercise::accounts(&ctx) AutoExercise::in_window(&ctx) not_knocked_out(&ctx.accounts.option_market) validate_size(size))]
+    /// Exercise options that are in the money at the recorded settlement price on behalf
+    /// of a holder that delegated their OptionTokens and quote assets to the market's
+    /// exercise authority. Anyone can call it, the caller
+    /// earns a keeper fee out of the underlying assets delivered. Scheduled markets are
+    /// auto exercised after expiration like any other, outside of their ExerciseSchedule.
+    pub fn auto_exercise(ctx: Context<AutoExercise>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if option_market.settlement_price <= option_market.quote_amount_per_contract {
+            return Err(errors::ErrorCode::OptionNotInTheMoney.into())
+        }
+
+        let option_market_key = option_market.key();
+        let exercise_authority_seeds = &[
+            option_market_key.as_ref(),
+            b"exerciseAuthority",
+            &[*ctx.bumps.get("exercise_authority").unwrap()]
+        ];
+        let exercise_authority_signer = &[&exercise_authority_seeds[..]];
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of option tokens as the holder's delegate
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.exercise_authority.to_account_info(),
+            },
+            exercise_authority_signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the holder's quote assets to the pool as their delegate
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_src.to_account_info(),
+            to: ctx.accounts.quote_asset_pool.to_account_info(),
+            authority: ctx.accounts.exercise_authority.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, exercise_authority_signer);
+        let quote_transfer_amount = option_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, quote_transfer_amount)?;
+
+        // Transfer the underlying assets from the pool to the holder, less the keeper fee
+        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        let keeper_fee = fees::keeper_fee_amount(underlying_transfer_amount);
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, underlying_transfer_amount - keeper_fee)?;
+
+        if keeper_fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.underlying_asset_pool.to_account_info(),
+                to: ctx.accounts.keeper_underlying_dest.to_account_info(),
+                authority: ctx.accounts.option_market.to_account_info(),
+            };
+            let cpi_token_program = ctx.accounts.token_program.clone();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, keeper_fee)?;
+        }
+        Ok(())
+    }
+
+    #[access_control(ClosePostExp::accounts(&ctx) ClosePostExp::expired_market(&ctx) ClosePostExp::underlying_in_pool(&ctx, size))]
+    pub fn close_post_expiration(ctx: Context<ClosePostExp>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the underlying from the pool to the user
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+        Ok(())
+    }
+
+    #[access_control(ClosePostExpNative::accounts(&ctx) ClosePostExpNative::expired_market(&ctx) ClosePostExpNative::underlying_in_pool(&ctx, size) validate_size(size))]
+    /// Close post expiration on a native SOL underlying market, unwrapping the underlying
+    /// to the signer as lamports.
+    pub fn close_post_expiration_native(ctx: Context<ClosePostExpNative>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the underlying from the pool to the unwrap account
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.unwrap_account.to_account_info(),
+            authority: option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_amount = option_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_amount)?;
+
+        // Close the unwrap account, returning the underlying and its rent as lamports
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.unwrap_account.to_account_info(),
+            destination: ctx.accounts.user_authority.to_account_info(),
+            authority: option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::close_account(cpi_ctx)
+    }
+
+    #[access_control(CloseOptionPosition::accounts(&ctx))]
+    pub fn close_option_position(ctx: Context<CloseOptionPosition>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Burn the Optiontokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.option_token_mint.to_account_info(),
+                from: ctx.accounts.option_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+
+        // Transfer the underlying assets from the pool to the destination
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+        Ok(())
+    }
+
+    #[access_control(RollPosition::accounts(&ctx, size) RollPosition::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.new_option_market) validate_size(size))]
+    /// Close `size` of a written position on one market and write it on another with the
+    /// same underlying. The underlying moves pool to pool. Like `close_post_expiration`,
+    /// only WriterTokens are burned when the old market is expired or knocked out.
+    pub fn roll_position(ctx: Context<RollPosition>, size: u64) -> Result<()> {
+        let old_market = &ctx.accounts.old_option_market;
+        let old_market_seeds = old_market.signer_seeds();
+        let old_seeds = old_market_seeds.as_slices();
+        let old_signer = &[&old_seeds[..]];
+        let new_market = &ctx.accounts.new_option_market;
+        let new_market_seeds = new_market.signer_seeds();
+        let new_seeds = new_market_seeds.as_slices();
+        let new_signer = &[&new_seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.old_writer_token_mint.to_account_info(),
+                from: ctx.accounts.old_writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Burn the size of OptionTokens while they are still live
+        if !roll_from_closed_market(old_market, Clock::get()?.unix_timestamp) {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.old_option_mint.to_account_info(),
+                    from: ctx.accounts.old_option_token_src.to_account_info(),
+                    authority: ctx.accounts.user_authority.to_account_info(),
+                },
+            );
+            token::burn(cpi_ctx, size)?;
+        }
+
+        // Transfer the underlying from the old pool to the new pool
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.old_underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.new_underlying_asset_pool.to_account_info(),
+            authority: ctx.accounts.old_option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, old_signer);
+        let underlying_transfer_amount = old_market.underlying_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+
+        // Mint a new OptionToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.new_option_mint.to_account_info(),
+            to: ctx.accounts.minted_option_dest.to_account_info(),
+            authority: ctx.accounts.new_option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, new_signer);
+        token::mint_to(cpi_ctx, size)?;
+
+        // Mint a new WriterToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.new_writer_token_mint.to_account_info(),
+            to: ctx.accounts.minted_writer_token_dest.to_account_info(),
+            authority: ctx.accounts.new_option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, new_signer);
+        token::mint_to(cpi_ctx, size)?;
+        Ok(())
+    }
+
+    #[access_control(BurnWriterForQuote::accounts(&ctx) BurnWriterForQuote::quotes_in_pool(&ctx, size))]
+    pub fn burn_writer_for_quote(ctx: Context<BurnWriterForQuote>, size: u64)  -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the quote assets to the writer's account
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.writer_quote_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        let quote_transfer_amount = option_market.quote_amount_per_contract.checked_mul(size).unwrap();
+        token::transfer(cpi_ctx, quote_transfer_amount)?;
+        
+
+        Ok(())
+    }
+
+    #[access_control(BurnWriterForQuoteNative::accounts(&ctx) BurnWriterForQuoteNative::quotes_in_pool(&ctx, size) validate_size(size))]
+    /// Burn WriterTokens on a native SOL quote market, unwrapping the quote to the signer
+    /// as lamports.
+    pub fn burn_writer_for_quote_native(ctx: Context<BurnWriterForQuoteNative>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the quote from the pool to the unwrap account
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.unwrap_account.to_account_info(),
+            authority: option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let quote_amount = option_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, quote_amount)?;
+
+        // Close the unwrap account, returning the quote and its rent as lamports
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.unwrap_account.to_account_info(),
+            destination: ctx.accounts.user_authority.to_account_info(),
+            authority: option_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::close_account(cpi_ctx)
+    }
+
+    #[access_control(BurnWriterForNetExercise::accounts(&ctx) BurnWriterForNetExercise::net_exercised(&ctx, size) validate_size(size))]
+    /// Burn WriterTokens for a pro-rata share of the underlying kept by net exercises.
+    /// The counterpart of `burn_writer_for_quote` for contracts exercised without quote.
+    pub fn burn_writer_for_net_exercise(ctx: Context<BurnWriterForNetExercise>, size: u64) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        let underlying_transfer_amount = (option_market.net_exercised_underlying as u128)
+            .checked_mul(size as u128)
+            .ok_or(errors::ErrorCode::NumberOverflow)?
+            / option_market.net_exercised_contracts as u128;
+        let underlying_transfer_amount = underlying_transfer_amount as u64;
+        let market_seeds = option_market.signer_seeds();
+        let seeds = market_seeds.as_slices();
+        let signer = &[&seeds[..]];
+
+        // Burn the size of WriterTokens
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info().clone(),
+            Burn {
+                mint: ctx.accounts.writer_token_mint.to_account_info(),
+                from: ctx.accounts.writer_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the share of the kept underlying to the writer
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.option_market.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+
+        let option_market = &mut ctx.accounts.option_market;
+        option_market.net_exercised_contracts -= size;
+        option_market.net_exercised_underlying -= underlying_transfer_amount;
+        Ok(())
+    }
+
+    /// Set the barrier that knocks out an OptionMarket. Only allowed before any options
+    /// are minted.
+    pub fn init_knock_out_barrier(ctx: Context<InitKnockOutBarrier>, barrier_price: u64, direction: BarrierDirection) -> Result<()> {
+        let knock_out_barrier = &mut ctx.accounts.knock_out_barrier;
+        knock_out_barrier.option_market = ctx.accounts.option_market.key();
+        knock_out_barrier.barrier_price = barrier_price;
+        knock_out_barrier.direction = direction;
+        knock_out_barrier.bump_seed = *ctx.bumps.get("knock_out_barrier").unwrap();
+        Ok(())
+    }
+
+    #[access_control(TriggerKnockOut::unexpired_market(&ctx))]
+    /// Knock out the OptionMarket with an oracle price that breaches its barrier. Anyone
+    /// can call it.
+    pub fn trigger_knock_out(ctx: Context<TriggerKnockOut>) -> Result<()> {
+        let oracle_config = &ctx.accounts.oracle_config;
+        let oracle_price = oracle_config.load_price(&ctx.accounts.oracle, Clock::get()?.unix_timestamp)?;
+        // Prices published after expiration don't knock out the options
+        if oracle_price.publish_time > ctx.accounts.option_market.expiration_unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantKnockOut.into())
+        }
+        let contract_value = oracle_price.contract_value(&ctx.accounts.option_market, oracle_config)?;
+        if !ctx.accounts.knock_out_barrier.is_breached(contract_value) {
+            return Err(errors::ErrorCode::BarrierNotBreached.into())
+        }
+
+        ctx.accounts.option_market.knocked_out = true;
+        emit!(KnockedOut {
+            option_market: ctx.accounts.option_market.key(),
+            oracle: oracle_config.oracle,
+            contract_value,
+            publish_time: oracle_price.publish_time,
+        });
+        Ok(())
+    }
+
+    #[access_control(InitializeBinaryMarket::accounts(
+        &ctx,
+        underlying_amount_per_contract,
+        strike,
+        payout_per_contract,
+        expiration_unix_timestamp,
+        oracle_source
+    ))]
+    /// Initialize a cash-or-nothing market settled by the given oracle
+    pub fn initialize_binary_market(
+        ctx: Context<InitializeBinaryMarket>,
+        underlying_amount_per_contract: u64,
+        strike: u64,
+        payout_per_contract: u64,
+        expiration_unix_timestamp: i64,
+        payout_condition: BinaryPayoutCondition,
+        oracle_source: OracleSource
+    ) -> Result<()> {
+        let binary_market = &mut ctx.accounts.binary_market;
+        binary_market.option_mint = ctx.accounts.option_mint.key();
+        binary_market.writer_token_mint = ctx.accounts.writer_token_mint.key();
+        binary_market.underlying_asset_mint = ctx.accounts.underlying_asset_mint.key();
+        binary_market.quote_asset_mint = ctx.accounts.quote_asset_mint.key();
+        binary_market.underlying_amount_per_contract = underlying_amount_per_contract;
+        binary_market.strike = strike;
+        binary_market.payout_per_contract = payout_per_contract;
+        binary_market.payout_condition = payout_condition;
+        binary_market.expiration_unix_timestamp = expiration_unix_timestamp;
+        binary_market.quote_asset_pool = ctx.accounts.quote_asset_pool.key();
+        binary_market.oracle_source = oracle_source;
+        binary_market.oracle = ctx.accounts.oracle.key();
+        binary_market.underlying_decimals = ctx.accounts.underlying_asset_mint.decimals;
+        binary_market.quote_decimals = ctx.accounts.quote_asset_mint.decimals;
+        binary_market.bump_seed = *ctx.bumps.get("binary_market").unwrap();
+        Ok(())
+    }
+
+    #[access_control(MintBinaryOption::unexpired_market(&ctx) validate_size(size))]
+    /// Lock the payout of `size` contracts in quote and mint the OptionTokens and
+    /// WriterTokens
+    pub fn mint_binary_option(ctx: Context<MintBinaryOption>, size: u64) -> Result<()> {
+        let binary_market = &ctx.accounts.binary_market;
+        let seeds = &[
+            binary_market.underlying_asset_mint.as_ref(),
+            binary_market.quote_asset_mint.as_ref(),
+            &binary_market.underlying_amount_per_contract.to_le_bytes(),
+            &binary_market.strike.to_le_bytes(),
+            &binary_market.payout_per_contract.to_le_bytes(),
+            &binary_market.expiration_unix_timestamp.to_le_bytes(),
+            &[binary_market.payout_condition as u8],
+            binary_market.oracle.as_ref(),
+            b"binaryMarket",
+            &[binary_market.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+
+        // Transfer the payout to the pool
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_src.to_account_info(),
+            to: ctx.accounts.quote_asset_pool.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        let payout_amount = binary_market.payout_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, payout_amount)?;
+
+        // Mint a new OptionToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.option_mint.to_account_info(),
+            to: ctx.accounts.minted_option_dest.to_account_info(),
+            authority: ctx.accounts.binary_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, size)?;
+
+        // Mint a new WriterToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.writer_token_mint.to_account_info(),
+            to: ctx.accounts.minted_writer_token_dest.to_account_info(),
+            authority: ctx.accounts.binary_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, size)?;
+        Ok(())
+    }
+
+    #[access_control(RecordBinarySettlementPrice::unrecorded(&ctx))]
+    /// Snapshot the oracle price at expiration on the BinaryMarket. Like
+    /// `record_settlement_price` it can only be recorded once.
+    pub fn record_binary_settlement_price(ctx: Context<RecordBinarySettlementPrice>) -> Result<()> {
+        let binary_market = &ctx.accounts.binary_market;
+        let oracle_price = oracle::load_price(binary_market.oracle_source, &ctx.accounts.oracle)?;
+        if !is_settlement_publish_time(binary_market.expiration_unix_timestamp, oracle_price.publish_time) {
+            return Err(errors::ErrorCode::SettlementPriceNotPublishedAtExpiration.into())
+        }
+        let settlement_price = oracle_price.value_of(
+            binary_market.underlying_amount_per_contract,
+            binary_market.underlying_decimals,
+            binary_market.quote_decimals,
+        )?;
+
+        let binary_market = &mut ctx.accounts.binary_market;
+        binary_market.settlement_price = settlement_price;
+        binary_market.settlement_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    #[access_control(ClaimBinaryPayout::accounts(&ctx) validate_size(size))]
+    /// Burn OptionTokens or WriterTokens for the payout once the settlement price is
+    /// recorded. Only the side the settlement price pays out to can claim. When the
+    /// settlement window closes without a price, writers can claim their payouts back.
+    pub fn claim_binary_payout(ctx: Context<ClaimBinaryPayout>, size: u64) -> Result<()> {
+        let binary_market = &ctx.accounts.binary_market;
+        let seeds = &[
+            binary_market.underlying_asset_mint.as_ref(),
+            binary_market.quote_asset_mint.as_ref(),
+            &binary_market.underlying_amount_per_contract.to_le_bytes(),
+            &binary_market.strike.to_le_bytes(),
+            &binary_market.payout_per_contract.to_le_bytes(),
+            &binary_market.expiration_unix_timestamp.to_le_bytes(),
+            &[binary_market.payout_condition as u8],
+            binary_market.oracle.as_ref(),
+            b"binaryMarket",
+            &[binary_market.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+
+        // Burn the claimed tokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.claim_token_mint.to_account_info(),
+                from: ctx.accounts.claim_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the payout to the claimant
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.quote_asset_dest.to_account_info(),
+            authority: ctx.accounts.binary_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let payout_amount = binary_market.payout_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, payout_amount)?;
+        Ok(())
+    }
+
+    #[access_control(validate_spread_markets(&ctx.accounts.long_market, &ctx.accounts.short_market))]
+    /// Create a SpreadPosition and its vaults for the owner's spread between two markets
+    pub fn init_spread_position(ctx: Context<InitSpreadPosition>) -> Result<()> {
+        let spread_position = &mut ctx.accounts.spread_position;
+        spread_position.owner = ctx.accounts.owner.key();
+        spread_position.long_market = ctx.accounts.long_market.key();
+        spread_position.short_market = ctx.accounts.short_market.key();
+        spread_position.long_option_vault = ctx.accounts.long_option_vault.key();
+        spread_position.quote_vault = ctx.accounts.quote_vault.key();
+        spread_position.written = 0;
+        spread_position.bump_seed = *ctx.bumps.get("spread_position").unwrap();
+        Ok(())
+    }
+
+    #[access_control(OpenSpread::accounts(&ctx) OpenSpread::unexpired_market(&ctx) validate_spread_markets(&ctx.accounts.long_market, &ctx.accounts.short_market) validate_size(size))]
+    /// Escrow `size` long OptionTokens and the spread's quote collateral, and mint `size`
+    /// short OptionTokens against them. No underlying is locked and no WriterTokens are
+    /// minted, the SpreadPosition is the short options' writer.
+    pub fn open_spread<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, OpenSpread<'info>>, size: u64) -> Result<()> {
+        not_knocked_out(&ctx.accounts.short_market)?;
+        take_mint_fee(
+            MintFee {
+                option_market: &ctx.accounts.short_market,
+                user_authority: ctx.accounts.owner.to_account_info(),
+                underlying_asset_src: ctx.accounts.underlying_asset_src.to_account_info(),
+                fee_owner: ctx.accounts.fee_owner.to_account_info(),
+                fee_payer: ctx.accounts.owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                authority_seeds: &[],
+            },
+            ctx.remaining_accounts,
+            size
+        )?;
+
+        // Escrow the long OptionTokens
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.long_option_src.to_account_info(),
+            to: ctx.accounts.long_option_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, size)?;
+
+        // Lock the quote for the difference in strikes
+        let collateral_amount = spread_collateral_per_contract(&ctx.accounts.long_market, &ctx.accounts.short_market)
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if collateral_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.quote_asset_src.to_account_info(),
+                to: ctx.accounts.quote_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, collateral_amount)?;
+        }
+
+        // Mint the short OptionTokens
+        let short_market_seeds = ctx.accounts.short_market.signer_seeds();
+        let short_seeds = short_market_seeds.as_slices();
+        let short_signer = &[&short_seeds[..]];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.short_option_mint.to_account_info(),
+            to: ctx.accounts.minted_option_dest.to_account_info(),
+            authority: ctx.accounts.short_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, short_signer);
+        token::mint_to(cpi_ctx, size)?;
+
+        let spread_position = &mut ctx.accounts.spread_position;
+        spread_position.written = spread_position.written.checked_add(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        Ok(())
+    }
+
+    #[access_control(ExerciseSpread::accounts(&ctx, size) ExerciseSpread::unexpired_market(&ctx) not_knocked_out(&ctx.accounts.short_market) not_knocked_out(&ctx.accounts.long_market) validate_exercise_window(&ctx.accounts.short_market, ctx.remaining_accounts) validate_size(size))]
+    /// Exercise `size` short options of a SpreadPosition by exercising as many of its
+    /// escrowed long options. The exerciser pays the short strike and receives the
+    /// underlying delivered by the long market. Short options the short market's pool
+    /// can't cover are exercised this way.
+    pub fn exercise_spread(ctx: Context<ExerciseSpread>, size: u64) -> Result<()> {
+        let spread_position = &ctx.accounts.spread_position;
+        let spread_seeds = &[
+            spread_position.owner.as_ref(),
+            spread_position.long_market.as_ref(),
+            spread_position.short_market.as_ref(),
+            b"spreadPosition",
+            &[spread_position.bump_seed]
+        ];
+        let spread_signer = &[&spread_seeds[..]];
+        let long_market_seeds = ctx.accounts.long_market.signer_seeds();
+        let long_seeds = long_market_seeds.as_slices();
+        let long_signer = &[&long_seeds[..]];
+
+        // Burn the exerciser's short OptionTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.short_option_mint.to_account_info(),
+                from: ctx.accounts.exerciser_option_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Take the short strike into the quote vault
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_src.to_account_info(),
+            to: ctx.accounts.quote_vault.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        let short_quote_amount = ctx.accounts.short_market.quote_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, short_quote_amount)?;
+
+        // Exercise the escrowed long OptionTokens with the vault's quote
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.long_option_mint.to_account_info(),
+                from: ctx.accounts.long_option_vault.to_account_info(),
+                authority: ctx.accounts.spread_position.to_account_info(),
+            },
+            spread_signer,
+        );
+        token::burn(cpi_ctx, size)?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_vault.to_account_info(),
+            to: ctx.accounts.long_quote_asset_pool.to_account_info(),
+            authority: ctx.accounts.spread_position.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, spread_signer);
+        let long_quote_amount = ctx.accounts.long_market.quote_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, long_quote_amount)?;
+
+        // Deliver the long market's underlying to the exerciser
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.long_underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.long_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, long_signer);
+        let underlying_amount = ctx.accounts.long_market.underlying_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_amount)?;
+
+        let spread_position = &mut ctx.accounts.spread_position;
+        spread_position.written -= size;
+        Ok(())
+    }
+
+    #[access_control(CloseSpread::expired_market(&ctx))]
+    /// Return the escrowed long OptionTokens and the quote left in the vault to the owner
+    /// and close the SpreadPosition
+    pub fn close_spread(ctx: Context<CloseSpread>) -> Result<()> {
+        let spread_position = &ctx.accounts.spread_position;
+        let spread_seeds = &[
+            spread_position.owner.as_ref(),
+            spread_position.long_market.as_ref(),
+            spread_position.short_market.as_ref(),
+            b"spreadPosition",
+            &[spread_position.bump_seed]
+        ];
+        let spread_signer = &[&spread_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.long_option_vault.to_account_info(),
+            to: ctx.accounts.long_option_dest.to_account_info(),
+            authority: ctx.accounts.spread_position.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, spread_signer);
+        token::transfer(cpi_ctx, ctx.accounts.long_option_vault.amount)?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_vault.to_account_info(),
+            to: ctx.accounts.quote_asset_dest.to_account_info(),
+            authority: ctx.accounts.spread_position.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, spread_signer);
+        token::transfer(cpi_ctx, ctx.accounts.quote_vault.amount)?;
+
+        // Close the vaults and return their rent to the owner
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.long_option_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.spread_position.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, spread_signer);
+        token::close_account(cpi_ctx)?;
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.quote_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.spread_position.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, spread_signer);
+        token::close_account(cpi_ctx)
+    }
+
+    /// Create a CollateralPortfolio and its vault for the owner's markets on one
+    /// underlying, quote and expiration
+    pub fn init_collateral_portfolio(ctx: Context<InitCollateralPortfolio>, expiration_unix_timestamp: i64) -> Result<()> {
+        let collateral_portfolio = &mut ctx.accounts.collateral_portfolio;
+        collateral_portfolio.owner = ctx.accounts.owner.key();
+        collateral_portfolio.underlying_asset_mint = ctx.accounts.underlying_asset_mint.key();
+        collateral_portfolio.quote_asset_mint = ctx.accounts.quote_asset_mint.key();
+        collateral_portfolio.expiration_unix_timestamp = expiration_unix_timestamp;
+        collateral_portfolio.underlying_vault = ctx.accounts.underlying_vault.key();
+        collateral_portfolio.bump_seed = *ctx.bumps.get("collateral_portfolio").unwrap();
+        Ok(())
+    }
+
+    #[access_control(AddPortfolioMarket::accounts(&ctx))]
+    pub fn add_portfolio_market(ctx: Context<AddPortfolioMarket>) -> Result<()> {
+        let option_market_key = ctx.accounts.option_market.key();
+        ctx.accounts.collateral_portfolio.markets.push(option_market_key);
+        Ok(())
+    }
+
+    pub fn deposit_portfolio_collateral(ctx: Context<DepositPortfolioCollateral>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_asset_src.to_account_info(),
+            to: ctx.accounts.underlying_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)
+    }
+
+    /// Withdraw collateral that hasn't been used to mint
+    pub fn withdraw_portfolio_collateral(ctx: Context<WithdrawPortfolioCollateral>, amount: u64) -> Result<()> {
+        let collateral_portfolio = &ctx.accounts.collateral_portfolio;
+        let expiration = collateral_portfolio.expiration_unix_timestamp.to_le_bytes();
+        let seeds = &[
+            collateral_portfolio.owner.as_ref(),
+            collateral_portfolio.underlying_asset_mint.as_ref(),
+            collateral_portfolio.quote_asset_mint.as_ref(),
+            &expiration,
+            b"collateralPortfolio",
+            &[collateral_portfolio.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_vault.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.collateral_portfolio.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)
+    }
+
+    #[access_control(MintFromPortfolio::unexpired_market(&ctx) MintFromPortfolio::accounts(&ctx, size) validate_size(size))]
+    /// Mint OptionTokens and WriterTokens in a member market with the portfolio's shared
+    /// collateral. The underlying and mint fee are taken from the vault.
+    pub fn mint_from_portfolio<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MintFromPortfolio<'info>>, size: u64) -> Result<()> {
+        let collateral_portfolio = &ctx.accounts.collateral_portfolio;
+        let expiration = collateral_portfolio.expiration_unix_timestamp.to_le_bytes();
+        let seeds = &[
+            collateral_portfolio.owner.as_ref(),
+            collateral_portfolio.underlying_asset_mint.as_ref(),
+            collateral_portfolio.quote_asset_mint.as_ref(),
+            &expiration,
+            b"collateralPortfolio",
+            &[collateral_portfolio.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+        mint_with_fee(
+            MintWithFee {
+                option_market: &ctx.accounts.option_market,
+                user_authority: ctx.accounts.collateral_portfolio.to_account_info(),
+                underlying_asset_src: ctx.accounts.underlying_vault.to_account_info(),
+                underlying_asset_pool: ctx.accounts.underlying_asset_pool.to_account_info(),
+                option_mint: ctx.accounts.option_mint.to_account_info(),
+                minted_option_dest: ctx.accounts.minted_option_dest.to_account_info(),
+                writer_token_mint: ctx.accounts.writer_token_mint.to_account_info(),
+                minted_writer_token_dest: ctx.accounts.minted_writer_token_dest.to_account_info(),
+                fee_owner: ctx.accounts.fee_owner.to_account_info(),
+                fee_payer: ctx.accounts.owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                authority_seeds: signer,
+            },
+            ctx.remaining_accounts,
+            size
+        )
+    }
+
+    /// Return the remaining collateral to the owner and close the portfolio
+    pub fn close_collateral_portfolio(ctx: Context<CloseCollateralPortfolio>) -> Result<()> {
+        let collateral_portfolio = &ctx.accounts.collateral_portfolio;
+        let expiration = collateral_portfolio.expiration_unix_timestamp.to_le_bytes();
+        let seeds = &[
+            collateral_portfolio.owner.as_ref(),
+            collateral_portfolio.underlying_asset_mint.as_ref(),
+            collateral_portfolio.quote_asset_mint.as_ref(),
+            &expiration,
+            b"collateralPortfolio",
+            &[collateral_portfolio.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.underlying_vault.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.collateral_portfolio.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, ctx.accounts.underlying_vault.amount)?;
+
+        // Close the vault and return its rent to the owner
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.underlying_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.collateral_portfolio.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::close_account(cpi_ctx)
+    }
+
+    #[access_control(InitializePutMarket::accounts(
+        &ctx,
+        underlying_amount_per_contract,
+        quote_amount_per_contract,
+        expiration_unix_timestamp
+    ))]
+    /// Initialize a cash-secured put market. `quote_amount_per_contract` is the strike.
+    pub fn initialize_put_market(
+        ctx: Context<InitializePutMarket>,
+        underlying_amount_per_contract: u64,
+        quote_amount_per_contract: u64,
+        expiration_unix_timestamp: i64
+    ) -> Result<()> {
+        let put_market = &mut ctx.accounts.put_market;
+        put_market.option_mint = ctx.accounts.option_mint.key();
+        put_market.writer_token_mint = ctx.accounts.writer_token_mint.key();
+        put_market.underlying_asset_mint = ctx.accounts.underlying_asset_mint.key();
+        put_market.quote_asset_mint = ctx.accounts.quote_asset_mint.key();
+        put_market.underlying_amount_per_contract = underlying_amount_per_contract;
+        put_market.quote_amount_per_contract = quote_amount_per_contract;
+        put_market.expiration_unix_timestamp = expiration_unix_timestamp;
+        put_market.quote_asset_pool = ctx.accounts.quote_asset_pool.key();
+        put_market.underlying_asset_pool = ctx.accounts.underlying_asset_pool.key();
+        put_market.fee_account = ctx.accounts.fee_account.key();
+        put_market.bump_seed = *ctx.bumps.get("put_market").unwrap();
+        Ok(())
+    }
+
+    #[access_control(MintPut::unexpired_market(&ctx) validate_size(size))]
+    /// Lock the strike of `size` puts in quote and mint the OptionTokens and WriterTokens.
+    /// The mint fee is taken on the strike.
+    pub fn mint_put(ctx: Context<MintPut>, size: u64) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
+        let mint_fee_amount_per_contract = fees::fee_amount(put_market.quote_amount_per_contract);
+        if mint_fee_amount_per_contract > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.quote_asset_src.to_account_info(),
+                to: ctx.accounts.fee_account.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            let total_fee = mint_fee_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+            token::transfer(cpi_ctx, total_fee)?;
+        } else {
+            // Strikes too small to take a fee from are charged in SOL
             let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
             invoke(
-                &system_instruction::transfer(&ctx.accounts.user_authority.key, &fees::fee_owner_key::ID, total_fee),
-            &[
-                ctx.accounts.user_authority.to_account_info().clone(),
-                ctx.accounts.fee_owner.clone(),
-                ctx.accounts.system_program.to_account_info().clone(),
-            ],
+                &system_instruction::transfer(&ctx.accounts.user_authority.key(), &fees::fee_owner_key::ID, total_fee),
+                &[
+                    ctx.accounts.user_authority.to_account_info(),
+                    ctx.accounts.fee_account.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
             )?;
         }
+
+        // Transfer the strike to the pool
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.quote_asset_src.to_account_info(),
+            to: ctx.accounts.quote_asset_pool.to_account_info(),
+            authority: ctx.accounts.user_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        let quote_amount = put_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, quote_amount)?;
+
+        let seeds = &[
+            put_market.underlying_asset_mint.as_ref(),
+            put_market.quote_asset_mint.as_ref(),
+            &put_market.underlying_amount_per_contract.to_le_bytes(),
+            &put_market.quote_amount_per_contract.to_le_bytes(),
+            &put_market.expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket",
+            &[put_market.bump_seed]
+        ];
+        let signer = &[&seeds[..]];
+
+        // Mint a new OptionToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.option_mint.to_account_info(),
+            to: ctx.accounts.minted_option_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, size)?;
+
+        // Mint a new WriterToken(s)
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.writer_token_mint.to_account_info(),
+            to: ctx.accounts.minted_writer_token_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, size)?;
         Ok(())
     }
 
-    #[access_control(ExerciseOptionV2::accounts(&ctx) ExerciseOptionV2::unexpired_market(&ctx))]
-    pub fn exercise_option_v2<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ExerciseOptionV2<'info>>, size: u64) -> Result<()> {
-        let option_market = &ctx.accounts.option_market;
+    #[access_control(ExercisePut::unexpired_market(&ctx) validate_size(size))]
+    /// Deliver the underlying for `size` puts and receive the strike, less the exercise
+    /// fee taken on it
+    pub fn exercise_put(ctx: Context<ExercisePut>, size: u64) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
         let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
+            put_market.underlying_asset_mint.as_ref(),
+            put_market.quote_asset_mint.as_ref(),
+            &put_market.underlying_amount_per_contract.to_le_bytes(),
+            &put_market.quote_amount_per_contract.to_le_bytes(),
+            &put_market.expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket",
+            &[put_market.bump_seed]
         ];
         let signer = &[&seeds[..]];
+
         // Burn the size of option tokens
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info().clone(),
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
             Burn {
                 mint: ctx.accounts.option_mint.to_account_info(),
                 from: ctx.accounts.exerciser_option_token_src.to_account_info(),
-                authority: ctx.accounts.option_authority.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
             },
-            signer,
         );
         token::burn(cpi_ctx, size)?;
 
-        // Transfer the quote assets to the pool
+        // Transfer the underlying from the exerciser to the pool
         let cpi_accounts = Transfer {
-            from: ctx.accounts.quote_asset_src.to_account_info(),
-            to: ctx.accounts.quote_asset_pool.to_account_info(),
+            from: ctx.accounts.underlying_asset_src.to_account_info(),
+            to: ctx.accounts.underlying_asset_pool.to_account_info(),
             authority: ctx.accounts.user_authority.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new(cpi_token_program.to_account_info(), cpi_accounts);
-        let quote_transfer_amount = option_market.quote_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, quote_transfer_amount)?;
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        let underlying_amount = put_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_amount)?;
+
+        let quote_amount = put_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        let exercise_fee = fees::fee_amount(put_market.quote_amount_per_contract)
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if exercise_fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.quote_asset_pool.to_account_info(),
+                to: ctx.accounts.fee_account.to_account_info(),
+                authority: ctx.accounts.put_market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, exercise_fee)?;
+        }
 
-        // Transfer the underlying assets from the pool to the exerciser
+        // Transfer the rest of the strike to the exerciser
         let cpi_accounts = Transfer {
-            from: ctx.accounts.underlying_asset_pool.to_account_info(),
-            to: ctx.accounts.underlying_asset_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.quote_asset_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
-        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, underlying_transfer_amount)?;
-
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, quote_amount - exercise_fee)?;
         Ok(())
     }
 
-    #[access_control(ClosePostExp::accounts(&ctx) ClosePostExp::expired_market(&ctx))]
-    pub fn close_post_expiration(ctx: Context<ClosePostExp>, size: u64) -> Result<()> {
-        let option_market = &ctx.accounts.option_market;
+    #[access_control(validate_size(size))]
+    /// Burn OptionTokens and WriterTokens together to unlock the strike
+    pub fn close_put_position(ctx: Context<ClosePutPosition>, size: u64) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
         let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
+            put_market.underlying_asset_mint.as_ref(),
+            put_market.quote_asset_mint.as_ref(),
+            &put_market.underlying_amount_per_contract.to_le_bytes(),
+            &put_market.quote_amount_per_contract.to_le_bytes(),
+            &put_market.expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket",
+            &[put_market.bump_seed]
         ];
         let signer = &[&seeds[..]];
 
-        // Burn the size of WriterTokens
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info().clone(),
+        // Burn WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
             Burn {
                 mint: ctx.accounts.writer_token_mint.to_account_info(),
                 from: ctx.accounts.writer_token_src.to_account_info(),
                 authority: ctx.accounts.user_authority.to_account_info(),
             },
-            signer,
         );
         token::burn(cpi_ctx, size)?;
 
-        // Transfer the underlying from the pool to the user
+        // Burn OptionTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.option_token_mint.to_account_info(),
+                from: ctx.accounts.option_token_src.to_account_info(),
+                authority: ctx.accounts.user_authority.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, size)?;
+
+        // Transfer the strike back to the writer
         let cpi_accounts = Transfer {
-            from: ctx.accounts.underlying_asset_pool.to_account_info(),
-            to: ctx.accounts.underlying_asset_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.quote_asset_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
-        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let quote_amount = put_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, quote_amount)?;
         Ok(())
     }
 
-    #[access_control(CloseOptionPosition::accounts(&ctx))]
-    pub fn close_option_position(ctx: Context<CloseOptionPosition>, size: u64) -> Result<()> {
-        let option_market = &ctx.accounts.option_market;
+    #[access_control(ClosePutPostExpiration::expired_market(&ctx) ClosePutPostExpiration::quotes_in_pool(&ctx, size) validate_size(size))]
+    /// Burn WriterTokens for the strike left unexercised once the market has expired
+    pub fn close_put_post_expiration(ctx: Context<ClosePutPostExpiration>, size: u64) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
         let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
+            put_market.underlying_asset_mint.as_ref(),
+            put_market.quote_asset_mint.as_ref(),
+            &put_market.underlying_amount_per_contract.to_le_bytes(),
+            &put_market.quote_amount_per_contract.to_le_bytes(),
+            &put_market.expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket",
+            &[put_market.bump_seed]
         ];
         let signer = &[&seeds[..]];
 
-        // Burn the size of WriterTokens
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info().clone(),
+        // Burn WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
             Burn {
                 mint: ctx.accounts.writer_token_mint.to_account_info(),
                 from: ctx.accounts.writer_token_src.to_account_info(),
                 authority: ctx.accounts.user_authority.to_account_info(),
             },
-            signer,
-        );
-        token::burn(cpi_ctx, size)?;
-
-        // Burn the Optiontokens
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info().clone(),
-            Burn {
-                mint: ctx.accounts.option_token_mint.to_account_info(),
-                from: ctx.accounts.option_token_src.to_account_info(),
-                authority: ctx.accounts.user_authority.to_account_info(),
-            },
-            signer,
         );
         token::burn(cpi_ctx, size)?;
 
-
-        // Transfer the underlying assets from the pool to the destination
+        // Transfer the strike back to the writer
         let cpi_accounts = Transfer {
-            from: ctx.accounts.underlying_asset_pool.to_account_info(),
-            to: ctx.accounts.underlying_asset_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            from: ctx.accounts.quote_asset_pool.to_account_info(),
+            to: ctx.accounts.quote_asset_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
-        let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, underlying_transfer_amount)?;
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let quote_amount = put_market.quote_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, quote_amount)?;
         Ok(())
     }
 
-    #[access_control(BurnWriterForQuote::accounts(&ctx) BurnWriterForQuote::quotes_in_pool(&ctx, size))]
-    pub fn burn_writer_for_quote(ctx: Context<BurnWriterForQuote>, size: u64)  -> Result<()> {
-        let option_market = &ctx.accounts.option_market;
+    #[access_control(BurnPutWriterForUnderlying::underlying_in_pool(&ctx, size) validate_size(size))]
+    /// Burn WriterTokens for the underlying delivered by exercised puts. The counterpart
+    /// of `burn_writer_for_quote`.
+    pub fn burn_put_writer_for_underlying(ctx: Context<BurnPutWriterForUnderlying>, size: u64) -> Result<()> {
+        let put_market = &ctx.accounts.put_market;
         let seeds = &[
-            option_market.underlying_asset_mint.as_ref(),
-            option_market.quote_asset_mint.as_ref(),
-            &option_market.underlying_amount_per_contract.to_le_bytes(),
-            &option_market.quote_amount_per_contract.to_le_bytes(),
-            &option_market.expiration_unix_timestamp.to_le_bytes(),
-            &[option_market.bump_seed]
+            put_market.underlying_asset_mint.as_ref(),
+            put_market.quote_asset_mint.as_ref(),
+            &put_market.underlying_amount_per_contract.to_le_bytes(),
+            &put_market.quote_amount_per_contract.to_le_bytes(),
+            &put_market.expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket",
+            &[put_market.bump_seed]
         ];
         let signer = &[&seeds[..]];
 
-        // Burn the size of WriterTokens
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info().clone(),
+        // Burn WriterTokens
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
             Burn {
                 mint: ctx.accounts.writer_token_mint.to_account_info(),
                 from: ctx.accounts.writer_token_src.to_account_info(),
                 authority: ctx.accounts.user_authority.to_account_info(),
             },
-            signer,
         );
         token::burn(cpi_ctx, size)?;
 
-        // Transfer the quote assets to the writer's account
+        // Transfer the delivered underlying to the writer
         let cpi_accounts = Transfer {
-            from: ctx.accounts.quote_asset_pool.to_account_info(),
-            to: ctx.accounts.writer_quote_dest.to_account_info(),
-            authority: ctx.accounts.option_market.to_account_info(),
+            from: ctx.accounts.underlying_asset_pool.to_account_info(),
+            to: ctx.accounts.underlying_asset_dest.to_account_info(),
+            authority: ctx.accounts.put_market.to_account_info(),
         };
-        let cpi_token_program = ctx.accounts.token_program.clone();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, signer);
-        let quote_transfer_amount = option_market.quote_amount_per_contract.checked_mul(size).unwrap();
-        token::transfer(cpi_ctx, quote_transfer_amount)?;
-        
-
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        let underlying_amount = put_market.underlying_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        token::transfer(cpi_ctx, underlying_amount)?;
         Ok(())
     }
 
@@ -486,20 +2073,489 @@ pub mod psy_american {
         )
     }
 
+    #[access_control(validate_referral_policy(&policy))]
+    /// Set who receives the referral rebates of a PsyOptions Serum market
+    pub fn init_referral_config(ctx: Context<InitReferralConfig>, policy: ReferralPolicy) -> Result<()> {
+        let referral_config = &mut ctx.accounts.referral_config;
+        referral_config.serum_market = ctx.accounts.serum_market.key();
+        referral_config.policy = policy;
+        referral_config.bump_seed = *ctx.bumps.get("referral_config").unwrap();
+        Ok(())
+    }
+
+    #[access_control(validate_referral_policy(&policy))]
+    pub fn update_referral_config(ctx: Context<UpdateReferralConfig>, policy: ReferralPolicy) -> Result<()> {
+        ctx.accounts.referral_config.policy = policy;
+        Ok(())
+    }
+
+    #[access_control(validate_oracle(oracle_source, &ctx.accounts.oracle))]
+    /// Set the oracle that prices an OptionMarket's underlying asset
+    pub fn init_oracle_config(ctx: Context<InitOracleConfig>, oracle_source: OracleSource, max_staleness: i64) -> Result<()> {
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.option_market = ctx.accounts.option_market.key();
+        oracle_config.oracle_source = oracle_source;
+        oracle_config.oracle = ctx.accounts.oracle.key();
+        oracle_config.max_staleness = max_staleness;
+        oracle_config.underlying_decimals = ctx.accounts.underlying_asset_mint.decimals;
+        oracle_config.quote_decimals = ctx.accounts.quote_asset_mint.decimals;
+        oracle_config.bump_seed = *ctx.bumps.get("oracle_config").unwrap();
+        Ok(())
+    }
+
+    #[access_control(validate_oracle(oracle_source, &ctx.accounts.oracle))]
+    pub fn update_oracle_config(ctx: Context<UpdateOracleConfig>, oracle_source: OracleSource, max_staleness: i64) -> Result<()> {
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.oracle_source = oracle_source;
+        oracle_config.oracle = ctx.accounts.oracle.key();
+        oracle_config.max_staleness = max_staleness;
+        Ok(())
+    }
+
+    #[access_control(validate_price_band(tolerance_bps, max_price))]
+    /// Set the prices orders on the OptionMarket's Serum markets must fall within
+    pub fn init_price_band(ctx: Context<InitPriceBand>, tolerance_bps: u16, max_price: u64) -> Result<()> {
+        let price_band = &mut ctx.accounts.price_band;
+        price_band.option_market = ctx.accounts.option_market.key();
+        price_band.tolerance_bps = tolerance_bps;
+        price_band.max_price = max_price;
+        price_band.bump_seed = *ctx.bumps.get("price_band").unwrap();
+        Ok(())
+    }
+
+    #[access_control(validate_price_band(tolerance_bps, max_price))]
+    pub fn update_price_band(ctx: Context<UpdatePriceBand>, tolerance_bps: u16, max_price: u64) -> Result<()> {
+        let price_band = &mut ctx.accounts.price_band;
+        price_band.tolerance_bps = tolerance_bps;
+        price_band.max_price = max_price;
+        Ok(())
+    }
+
+    #[access_control(validate_position_limit(max_contracts))]
+    /// Cap the contracts a single owner can have on order on the OptionMarket's Serum markets
+    pub fn init_position_limit(ctx: Context<InitPositionLimit>, max_contracts: u64) -> Result<()> {
+        let position_limit = &mut ctx.accounts.position_limit;
+        position_limit.option_market = ctx.accounts.option_market.key();
+        position_limit.max_contracts = max_contracts;
+        position_limit.bump_seed = *ctx.bumps.get("position_limit").unwrap();
+        Ok(())
+    }
+
+    #[access_control(validate_position_limit(max_contracts))]
+    pub fn update_position_limit(ctx: Context<UpdatePositionLimit>, max_contracts: u64) -> Result<()> {
+        ctx.accounts.position_limit.max_contracts = max_contracts;
+        Ok(())
+    }
+
+    /// Restrict a PsyOptions Serum market to the traders the admin adds to its allowlist
+    pub fn init_trading_allowlist(ctx: Context<InitTradingAllowlist>) -> Result<()> {
+        let trading_allowlist = &mut ctx.accounts.trading_allowlist;
+        trading_allowlist.serum_market = ctx.accounts.serum_market.key();
+        trading_allowlist.bump_seed = *ctx.bumps.get("trading_allowlist").unwrap();
+        Ok(())
+    }
+
+    pub fn add_allowlisted_trader(ctx: Context<UpdateTradingAllowlist>, trader: Pubkey) -> Result<()> {
+        ctx.accounts.trading_allowlist.add(trader)
+    }
+
+    pub fn remove_allowlisted_trader(ctx: Context<UpdateTradingAllowlist>, trader: Pubkey) -> Result<()> {
+        ctx.accounts.trading_allowlist.remove(&trader)
+    }
+
+    #[access_control(leg.validate(&ctx.accounts.option_market))]
+    /// Validate RFQ leg data references a real OptionMarket that can be settled
+    pub fn validate_rfq_leg(ctx: Context<ValidateRfqLeg>, leg: RfqLeg) -> Result<()> {
+        Ok(())
+    }
+
+    #[access_control(PrepareRfqSettlement::accounts(&ctx, &leg))]
+    /// Lock the OptionTokens or collateral one party delivers for an RFQ leg in escrow
+    pub fn prepare_rfq_settlement(
+        ctx: Context<PrepareRfqSettlement>,
+        leg_index: u8,
+        leg: RfqLeg,
+        counterparty: Pubkey
+    ) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.depositor_src.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new(cpi_token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, leg.escrow_amount(&ctx.accounts.option_market)?)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.trade = ctx.accounts.trade.key();
+        escrow.leg_index = leg_index;
+        escrow.leg = leg;
+        escrow.depositor = ctx.accounts.depositor.key();
+        escrow.counterparty = counterparty;
+        escrow.settlement_authority = ctx.accounts.settlement_authority.key();
+        escrow.vault = ctx.accounts.escrow_vault.key();
+        escrow.bump_seed = *ctx.bumps.get("escrow").unwrap();
+        Ok(())
+    }
+
+    #[access_control(PrepareRfqSettlementWithMint::accounts(&ctx, &leg))]
+    /// Mint the OptionTokens of an RFQ leg straight into escrow from the seller's
+    /// underlying assets, with the WriterTokens going to the seller
+    pub fn prepare_rfq_settlement_with_mint<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PrepareRfqSettlementWithMint<'info>>,
+        leg_index: u8,
+        leg: RfqLeg,
+        counterparty: Pubkey
+    ) -> Result<()> {
+        mint_with_fee(
+            MintWithFee {
+                option_market: &ctx.accounts.option_market,
+                user_authority: ctx.accounts.depositor.to_account_info(),
+                underlying_asset_src: ctx.accounts.underlying_asset_src.to_account_info(),
+                underlying_asset_pool: ctx.accounts.underlying_asset_pool.to_account_info(),
+                option_mint: ctx.accounts.option_mint.to_account_info(),
+                minted_option_dest: ctx.accounts.escrow_vault.to_account_info(),
+                writer_token_mint: ctx.accounts.writer_token_mint.to_account_info(),
+                minted_writer_token_dest: ctx.accounts.minted_writer_token_dest.to_account_info(),
+                fee_owner: ctx.accounts.fee_owner.clone(),
+                fee_payer: ctx.accounts.depositor.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                authority_seeds: &[],
+            },
+            ctx.remaining_accounts,
+            leg.amount
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.trade = ctx.accounts.trade.key();
+        escrow.leg_index = leg_index;
+        escrow.leg = leg;
+        escrow.depositor = ctx.accounts.depositor.key();
+        escrow.counterparty = counterparty;
+        escrow.settlement_authority = ctx.accounts.settlement_authority.key();
+        escrow.vault = ctx.accounts.escrow_vault.key();
+        escrow.bump_seed = *ctx.bumps.get("escrow").unwrap();
+        Ok(())
+    }
+
+    #[access_control(SettleRfqLeg::accounts(&ctx))]
+    /// Deliver an RFQ leg's escrow to the counterparty
+    pub fn settle_rfq_leg<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, SettleRfqLeg<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let escrow_seeds = &[
+            escrow.trade.as_ref(),
+            &[escrow.leg_index],
+            escrow.depositor.as_ref(),
+            b"rfqEscrow",
+            &[escrow.bump_seed]
+        ];
+        let escrow_signer = &[&escrow_seeds[..]];
+
+        match escrow.leg.asset {
+            RfqLegAsset::OptionToken => {
+                // Transfer the OptionTokens to the counterparty
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.escrow_vault.to_account_info(),
+                    to: ctx.accounts.counterparty_option_dest.to_account_info(),
+                    authority: ctx.accounts.escrow.to_account_info(),
+                };
+                let cpi_token_program = ctx.accounts.token_program.clone();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, escrow_signer);
+                token::transfer(cpi_ctx, escrow.leg.amount)?;
+            },
+            RfqLegAsset::Collateral => {
+                let option_market = &ctx.accounts.option_market;
+                if option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+                    return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+                }
+                // Mint the OptionTokens to the counterparty and the WriterTokens to the
+                // depositor, the escrow pays the mint fee and the collateral
+                mint_with_fee(
+                    MintWithFee {
+                        option_market,
+                        user_authority: ctx.accounts.escrow.to_account_info(),
+                        underlying_asset_src: ctx.accounts.escrow_vault.to_account_info(),
+                        underlying_asset_pool: ctx.accounts.underlying_asset_pool.to_account_info(),
+                        option_mint: ctx.accounts.option_mint.to_account_info(),
+                        minted_option_dest: ctx.accounts.counterparty_option_dest.to_account_info(),
+                        writer_token_mint: ctx.accounts.writer_token_mint.to_account_info(),
+                        minted_writer_token_dest: ctx.accounts.depositor_writer_token_dest.to_account_info(),
+                        fee_owner: ctx.accounts.fee_owner.clone(),
+                        fee_payer: ctx.accounts.fee_payer.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        authority_seeds: escrow_signer,
+                    },
+                    ctx.remaining_accounts,
+                    escrow.leg.amount
+                )?;
+            },
+        }
+
+        // Close the empty vault, the escrow is closed by Anchor
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.escrow_vault.to_account_info(),
+            destination: ctx.accounts.depositor.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, escrow_signer);
+        token::close_account(cpi_ctx)
+    }
+
+    /// Return an RFQ leg's escrow to the depositor when the trade defaults
+    pub fn revert_rfq_leg(ctx: Context<RevertRfqLeg>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let escrow_seeds = &[
+            escrow.trade.as_ref(),
+            &[escrow.leg_index],
+            escrow.depositor.as_ref(),
+            b"rfqEscrow",
+            &[escrow.bump_seed]
+        ];
+        let escrow_signer = &[&escrow_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow_vault.to_account_info(),
+            to: ctx.accounts.depositor_dest.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, escrow_signer);
+        token::transfer(cpi_ctx, ctx.accounts.escrow_vault.amount)?;
+
+        // Close the empty vault, the escrow is closed by Anchor
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.escrow_vault.to_account_info(),
+            destination: ctx.accounts.depositor.to_account_info(),
+            authority: ctx.accounts.escrow.to_account_info(),
+        };
+        let cpi_token_program = ctx.accounts.token_program.clone();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_token_program.to_account_info(), cpi_accounts, escrow_signer);
+        token::close_account(cpi_ctx)
+    }
+
     pub fn entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Result<()> {
         MarketProxy::new()
             .middleware(&mut serum_proxy::Validation::new())
-            .middleware(&mut ReferralFees::new(serum_proxy::referral::ID))
+            .middleware(&mut serum_proxy::PriceBands::new())
+            .middleware(&mut serum_proxy::PositionLimits::new())
+            .middleware(&mut serum_proxy::TradingAllowlists::new())
+            .middleware(&mut serum_proxy::Referral::new())
             .middleware(&mut OpenOrdersPda::new())
             .run(program_id, accounts, data)
     }
 }
 
+/// The accounts `take_mint_fee` moves the fee between
+struct MintFee<'a, 'info> {
+    option_market: &'a Account<'info, OptionMarket>,
+    user_authority: AccountInfo<'info>,
+    underlying_asset_src: AccountInfo<'info>,
+    fee_owner: AccountInfo<'info>,
+    /// Pays the SOL fee of markets too small to take an underlying fee from
+    fee_payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    /// Signs for `user_authority` when it's one of the program's PDAs, empty otherwise
+    authority_seeds: &'a [&'a [&'a [u8]]],
+}
+
+/// Take the mint fee of `size` options in the underlying asset, or in SOL for markets
+/// too small to take an underlying fee from. Shared by `mint_with_fee` and `open_spread`.
+fn take_mint_fee<'info>(
+    accounts: MintFee<'_, 'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    size: u64
+) -> Result<()> {
+    let option_market = accounts.option_market;
+    let mint_fee_account = validate_mint_fee_acct(
+        option_market,
+        remaining_accounts
+    )?;
+
+    // Take a mint fee
+    let mint_fee_amount_per_contract = fees::fee_amount(option_market.underlying_amount_per_contract);
+    if mint_fee_amount_per_contract > 0 {
+        match mint_fee_account {
+            Some(account) => {
+                let cpi_accounts = Transfer {
+                    from: accounts.underlying_asset_src.clone(),
+                    to: account.clone(),
+                    authority: accounts.user_authority.clone(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, accounts.authority_seeds);
+                let total_fee = mint_fee_amount_per_contract.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+                token::transfer(cpi_ctx, total_fee)?;
+            },
+            None => {}
+        }
+    } else {
+        // Handle NFT case with SOL fee
+        let total_fee = fees::NFT_MINT_LAMPORTS.checked_mul(size).ok_or(errors::ErrorCode::NumberOverflow)?;
+        invoke(
+            &system_instruction::transfer(accounts.fee_payer.key, &fees::fee_owner_key::ID, total_fee),
+        &[
+            accounts.fee_payer.clone(),
+            accounts.fee_owner.clone(),
+            accounts.system_program.clone(),
+        ],
+        )?;
+    }
+    Ok(())
+}
+
+/// The accounts `mint_with_fee` moves tokens between
+struct MintWithFee<'a, 'info> {
+    option_market: &'a Account<'info, OptionMarket>,
+    user_authority: AccountInfo<'info>,
+    underlying_asset_src: AccountInfo<'info>,
+    underlying_asset_pool: AccountInfo<'info>,
+    option_mint: AccountInfo<'info>,
+    minted_option_dest: AccountInfo<'info>,
+    writer_token_mint: AccountInfo<'info>,
+    minted_writer_token_dest: AccountInfo<'info>,
+    fee_owner: AccountInfo<'info>,
+    /// Pays the SOL fee of markets too small to take an underlying fee from
+    fee_payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    /// Signs for `user_authority` when it's one of the program's PDAs, empty otherwise
+    authority_seeds: &'a [&'a [&'a [u8]]],
+}
+
+/// Take the mint fee, lock the underlying assets in the pool and mint `size` OptionTokens
+/// and WriterTokens. Shared by `mint_option`, `batch_mint`, `mint_from_portfolio` and
+/// the RFQ mint on settlement.
+fn mint_with_fee<'info>(
+    accounts: MintWithFee<'_, 'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    size: u64
+) -> Result<()> {
+    let option_market = accounts.option_market;
+    not_knocked_out(option_market)?;
+    take_mint_fee(
+        MintFee {
+            option_market,
+            user_authority: accounts.user_authority.clone(),
+            underlying_asset_src: accounts.underlying_asset_src.clone(),
+            fee_owner: accounts.fee_owner.clone(),
+            fee_payer: accounts.fee_payer.clone(),
+            token_program: accounts.token_program.clone(),
+            system_program: accounts.system_program.clone(),
+            authority_seeds: accounts.authority_seeds,
+        },
+        remaining_accounts,
+        size
+    )?;
+
+    // Transfer the underlying assets to the underlying assets pool
+    let cpi_accounts = Transfer {
+        from: accounts.underlying_asset_src.clone(),
+        to: accounts.underlying_asset_pool.clone(),
+        authority: accounts.user_authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, accounts.authority_seeds);
+    let underlying_transfer_amount = option_market.underlying_amount_per_contract.checked_mul(size).unwrap();
+    token::transfer(cpi_ctx, underlying_transfer_amount)?;
+
+    let market_seeds = option_market.signer_seeds();
+    let seeds = market_seeds.as_slices();
+    let signer = &[&seeds[..]];
+
+    // Mint a new OptionToken(s)
+    let cpi_accounts = MintTo {
+        mint: accounts.option_mint.clone(),
+        to: accounts.minted_option_dest.clone(),
+        authority: option_market.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, signer);
+    token::mint_to(cpi_ctx, size)?;
+
+    // Mint a new WriterToken(s)
+    let cpi_accounts = MintTo {
+        mint: accounts.writer_token_mint.clone(),
+        to: accounts.minted_writer_token_dest.clone(),
+        authority: option_market.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, signer);
+    token::mint_to(cpi_ctx, size)?;
+
+    Ok(())
+}
+
 struct FeeAccounts {
     mint_fee_key: Pubkey,
     exercise_fee_key: Pubkey
 }
 
+/// The accounts `write_option_market` fills a new OptionMarket from
+struct NewOptionMarket<'a, 'info> {
+    option_market: &'a mut Account<'info, OptionMarket>,
+    underlying_asset_mint: Pubkey,
+    quote_asset_mint: Pubkey,
+    option_mint: Pubkey,
+    writer_token_mint: Pubkey,
+    underlying_asset_pool: Pubkey,
+    quote_asset_pool: Pubkey,
+    clock: &'a Clock,
+}
+
+/// Validate the market parameters and write them to the new OptionMarket
+fn write_option_market<'info>(
+    accounts: NewOptionMarket<'_, 'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    bumps: &std::collections::BTreeMap<String, u8>,
+    underlying_amount_per_contract: u64,
+    quote_amount_per_contract: u64,
+    expiration_unix_timestamp: i64
+) -> Result<()> {
+    // (nice to have) Validate the expiration is in the future
+    if expiration_unix_timestamp < accounts.clock.unix_timestamp {
+        return Err(errors::ErrorCode::ExpirationIsInThePast.into())
+    }
+    // check that underlying_amount_per_contract and quote_amount_per_contract are not 0
+    if underlying_amount_per_contract <= 0 || quote_amount_per_contract <= 0 {
+        return Err(errors::ErrorCode::QuoteOrUnderlyingAmountCannotBe0.into())
+    }
+
+    let fee_accounts = validate_fee_accounts(
+        remaining_accounts, 
+        &accounts.underlying_asset_mint,
+        &accounts.quote_asset_mint,
+        underlying_amount_per_contract,
+        quote_amount_per_contract
+    )?;
+
+    // write the data to the OptionMarket account
+    let option_market = accounts.option_market;
+    option_market.option_mint = accounts.option_mint;
+    option_market.writer_token_mint = accounts.writer_token_mint;
+    option_market.underlying_asset_mint = accounts.underlying_asset_mint;
+    option_market.quote_asset_mint = accounts.quote_asset_mint;
+    option_market.underlying_amount_per_contract = underlying_amount_per_contract;
+    option_market.quote_amount_per_contract = quote_amount_per_contract;
+    option_market.expiration_unix_timestamp = expiration_unix_timestamp;
+    option_market.underlying_asset_pool = accounts.underlying_asset_pool;
+    option_market.quote_asset_pool = accounts.quote_asset_pool;
+    option_market.mint_fee_account = fee_accounts.mint_fee_key;
+    option_market.exercise_fee_account = fee_accounts.exercise_fee_key;
+    option_market.expired = false;
+
+    match bumps.get("option_market") {
+        Some(bump) => {
+            option_market.bump_seed = *bump;
+        }
+        None => {
+            msg!("Wrong bump key. Available keys are {:?}", bumps.keys());
+            panic!("Wrong bump key")
+        }
+    }
+
+    Ok(())
+}
+
 /// Validate that the size is greater than 0
 fn validate_size(size: u64) -> Result<()> {
     if size <= 0 {
@@ -687,21 +2743,34 @@ pub struct InitializeMarket<'info> {
     pub clock: Sysvar<'info, Clock>,
 }
 impl<'info> InitializeMarket<'info> {
-    fn accounts(ctx: &Context<InitializeMarket<'info>>) -> Result<()> {
-        if ctx.accounts.option_mint.mint_authority.unwrap() != *ctx.accounts.option_market.to_account_info().key {
+    fn new_option_market(&mut self) -> NewOptionMarket<'_, 'info> {
+        NewOptionMarket {
+            underlying_asset_mint: self.underlying_asset_mint.key(),
+            quote_asset_mint: self.quote_asset_mint.key(),
+            option_mint: self.option_mint.key(),
+            writer_token_mint: self.writer_token_mint.key(),
+            underlying_asset_pool: self.underlying_asset_pool.key(),
+            quote_asset_pool: self.quote_asset_pool.key(),
+            clock: &self.clock,
+            option_market: &mut self.option_market,
+        }
+    }
+
+    fn accounts(accounts: &InitializeMarket<'info>) -> Result<()> {
+        if accounts.option_mint.mint_authority.unwrap() != *accounts.option_market.to_account_info().key {
             return Err(errors::ErrorCode::OptionMarketMustBeMintAuthority.into());
         }
-        if ctx.accounts.writer_token_mint.mint_authority.unwrap() != *ctx.accounts.option_market.to_account_info().key {
+        if accounts.writer_token_mint.mint_authority.unwrap() != *accounts.option_market.to_account_info().key {
             return Err(errors::ErrorCode::OptionMarketMustBeMintAuthority.into());
         }
-        if ctx.accounts.underlying_asset_pool.owner != *ctx.accounts.option_market.to_account_info().key {
+        if accounts.underlying_asset_pool.owner != *accounts.option_market.to_account_info().key {
             return Err(errors::ErrorCode::OptionMarketMustOwnUnderlyingAssetPool.into());
         }
-        if ctx.accounts.quote_asset_pool.owner != *ctx.accounts.option_market.to_account_info().key {
+        if accounts.quote_asset_pool.owner != *accounts.option_market.to_account_info().key {
             return Err(errors::ErrorCode::OptionMarketMustOwnQuoteAssetPool.into());
         }
         // check that underlying and quote are not the same asset
-        if ctx.accounts.underlying_asset_mint.to_account_info().key == ctx.accounts.quote_asset_mint.to_account_info().key {
+        if accounts.underlying_asset_mint.to_account_info().key == accounts.quote_asset_mint.to_account_info().key {
             return Err(errors::ErrorCode::QuoteAndUnderlyingAssetMustDiffer.into())
         }
         Ok(())
@@ -991,10 +3060,23 @@ impl<'info> ClosePostExp<'info> {
         Ok(())
     }
     fn expired_market(ctx: &Context<ClosePostExp>) -> Result<()> {
+        // Knocked out options are worthless, so writers don't have to wait for expiration
+        if ctx.accounts.option_market.knocked_out {
+            return Ok(())
+        }
         // Validate the market is expired
         if ctx.accounts.option_market.expiration_unix_timestamp >= ctx.accounts.clock.unix_timestamp {
             return Err(errors::ErrorCode::OptionMarketNotExpiredCantClose.into())
         }
+        auto_exercise_closed(&ctx.accounts.option_market, ctx.accounts.clock.unix_timestamp)
+    }
+    // Validate the underlying kept by net exercises stays in the pool for its writers
+    fn underlying_in_pool(ctx: &Context<ClosePostExp>, size: u64) -> Result<()> {
+        let available = ctx.accounts.underlying_asset_pool.amount
+            .saturating_sub(ctx.accounts.option_market.net_exercised_underlying);
+        if available < size.checked_mul(ctx.accounts.option_market.underlying_amount_per_contract).unwrap() {
+            return Err(errors::ErrorCode::NotEnoughUnderlyingAssetsInPool.into())
+        }
         Ok(())
     }
 }
@@ -1189,5 +3271,74 @@ pub struct OptionMarket {
     /// This will be set when Serum markets are closed and expiration is validated
     pub expired: bool,
     /// Bump seed for the market PDA
-    pub bump_seed: u8
+    pub bump_seed: u8,
+    /// The value of `underlying_amount_per_contract` at expiration, in the quote asset's
+    /// native units. Written once by `record_settlement_price`
+    pub settlement_price: u64,
+    /// The oracle the settlement price was read from
+    pub settlement_oracle: Pubkey,
+    /// How the settlement oracle was parsed
+    pub settlement_oracle_source: OracleSource,
+    /// The slot the settlement price was recorded in, 0 until it is recorded
+    pub settlement_slot: u64,
+    /// The number of contracts exercised through `exercise_option_net` whose WriterTokens
+    /// have not claimed their share of `net_exercised_underlying`
+    pub net_exercised_contracts: u64,
+    /// The underlying kept in the pool in place of the strike of net exercised contracts.
+    /// Reserved for `burn_writer_for_net_exercise`.
+    pub net_exercised_underlying: u64,
+    /// The hash of the market's ExerciseSchedule windows, all zero for markets that can be
+    /// exercised any time before expiration
+    pub exercise_schedule_hash: [u8; 32],
+    /// Set by `trigger_knock_out` when the market's KnockOutBarrier is breached. The
+    /// options can no longer be minted or exercised and writers can close immediately.
+    pub knocked_out: bool,
+    /// Where the settlement price must be recorded from. Pyth through the OracleConfig,
+    /// unless `init_twap_accumulator` switched the market to its Serum TWAP.
+    pub settlement_source: OracleSource,
+}
+
+impl OptionMarket {
+    /// The seeds the OptionMarket PDA signs with. Scheduled markets add their
+    /// `exercise_schedule_hash`, so they never take the address of the American market
+    /// with the same terms.
+    pub fn signer_seeds(&self) -> OptionMarketSeeds {
+        OptionMarketSeeds {
+            underlying_asset_mint: self.underlying_asset_mint,
+            quote_asset_mint: self.quote_asset_mint,
+            underlying_amount_per_contract: self.underlying_amount_per_contract.to_le_bytes(),
+            quote_amount_per_contract: self.quote_amount_per_contract.to_le_bytes(),
+            expiration_unix_timestamp: self.expiration_unix_timestamp.to_le_bytes(),
+            exercise_schedule_hash: if has_exercise_schedule(self) { Some(self.exercise_schedule_hash) } else { None },
+            bump_seed: [self.bump_seed],
+        }
+    }
+}
+
+/// The OptionMarket PDA seeds, owned so they can outlive the borrow of the market
+pub struct OptionMarketSeeds {
+    underlying_asset_mint: Pubkey,
+    quote_asset_mint: Pubkey,
+    underlying_amount_per_contract: [u8; 8],
+    quote_amount_per_contract: [u8; 8],
+    expiration_unix_timestamp: [u8; 8],
+    exercise_schedule_hash: Option<[u8; 32]>,
+    bump_seed: [u8; 1],
+}
+
+impl OptionMarketSeeds {
+    pub fn as_slices(&self) -> Vec<&[u8]> {
+        let mut seeds: Vec<&[u8]> = vec![
+            self.underlying_asset_mint.as_ref(),
+            self.quote_asset_mint.as_ref(),
+            &self.underlying_amount_per_contract,
+            &self.quote_amount_per_contract,
+            &self.expiration_unix_timestamp,
+        ];
+        if let Some(exercise_schedule_hash) = &self.exercise_schedule_hash {
+            seeds.push(exercise_schedule_hash);
+        }
+        seeds.push(&self.bump_seed);
+        seeds
+    }
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+
+use crate::{OptionMarket, errors};
+
+/// Markets per MarketRegistryPage. Once the current page holds this many, anyone can open
+/// the next page with `open_registry_page`.
+pub const MARKETS_PER_REGISTRY_PAGE: usize = 64;
+
+#[account]
+#[derive(Default)]
+/// Registry of the OptionMarkets registered with `register_market` for an underlying and
+/// quote asset, or for an underlying asset and expiration. The markets are stored in
+/// MarketRegistryPages, in the order they were registered.
+///
+/// Registration is opt-in, so a registry doesn't list markets nobody registered. Appending
+/// in `initialize_market` would add required accounts to every existing initialize caller
+/// and write lock the same pages for every market on a pair or expiry.
+pub struct MarketRegistry {
+    /// The number of markets across all pages
+    pub market_count: u64,
+    /// The page new markets are appended to. Pages `0..=current_page` exist.
+    pub current_page: u32,
+    /// Bump seed for the MarketRegistry PDA
+    pub bump_seed: u8,
+}
+
+impl MarketRegistry {
+    pub const SPACE: usize = 8 + 8 + 4 + 1;
+}
+
+#[account]
+#[derive(Default)]
+pub struct MarketRegistryPage {
+    pub registry: Pubkey,
+    pub page_index: u32,
+    pub markets: Vec<Pubkey>,
+    /// Bump seed for the MarketRegistryPage PDA
+    pub bump_seed: u8,
+}
+
+impl MarketRegistryPage {
+    pub const SPACE: usize = 8 + 32 + 4 + (4 + MARKETS_PER_REGISTRY_PAGE * 32) + 1;
+}
+
+#[account]
+#[derive(Default)]
+/// Marks an OptionMarket as registered, so it is only appended to its registries once
+pub struct MarketRegistration {
+    /// Bump seed for the MarketRegistration PDA
+    pub bump_seed: u8,
+}
+
+impl MarketRegistration {
+    pub const SPACE: usize = 8 + 1;
+}
+
+/// Write a newly created registry and its first page
+pub fn init_registry(
+    registry: &mut Account<MarketRegistry>,
+    registry_bump: u8,
+    page: &mut Account<MarketRegistryPage>,
+    page_bump: u8,
+) {
+    registry.bump_seed = registry_bump;
+    page.registry = registry.key();
+    page.page_index = 0;
+    page.bump_seed = page_bump;
+}
+
+/// Append the market to the registry's current page
+pub fn append_to_registry(
+    registry: &mut Account<MarketRegistry>,
+    page: &mut Account<MarketRegistryPage>,
+    market: Pubkey,
+) -> Result<()> {
+    if page.markets.len() >= MARKETS_PER_REGISTRY_PAGE {
+        return Err(errors::ErrorCode::RegistryPageIsFull.into())
+    }
+    page.markets.push(market);
+    registry.market_count = registry.market_count
+        .checked_add(1)
+        .ok_or(errors::ErrorCode::NumberOverflow)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitQuoteRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Registries are only created for assets with an OptionMarket
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(init,
+        seeds = [option_market.underlying_asset_mint.as_ref(), option_market.quote_asset_mint.as_ref(), b"quoteRegistry"],
+        bump,
+        payer = payer,
+        space = MarketRegistry::SPACE
+    )]
+    pub registry: Box<Account<'info, MarketRegistry>>,
+    #[account(init,
+        seeds = [registry.key().as_ref(), &0u32.to_le_bytes(), b"registryPage"],
+        bump,
+        payer = payer,
+        space = MarketRegistryPage::SPACE
+    )]
+    pub first_page: Box<Account<'info, MarketRegistryPage>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitExpiryRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Registries are only created for expirations with an OptionMarket
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(init,
+        seeds = [option_market.underlying_asset_mint.as_ref(), &option_market.expiration_unix_timestamp.to_le_bytes(), b"expiryRegistry"],
+        bump,
+        payer = payer,
+        space = MarketRegistry::SPACE
+    )]
+    pub registry: Box<Account<'info, MarketRegistry>>,
+    #[account(init,
+        seeds = [registry.key().as_ref(), &0u32.to_le_bytes(), b"registryPage"],
+        bump,
+        payer = payer,
+        space = MarketRegistryPage::SPACE
+    )]
+    pub first_page: Box<Account<'info, MarketRegistryPage>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenRegistryPage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub registry: Box<Account<'info, MarketRegistry>>,
+    #[account(
+        seeds = [registry.key().as_ref(), &registry.current_page.to_le_bytes(), b"registryPage"],
+        bump = current_page.bump_seed,
+    )]
+    pub current_page: Box<Account<'info, MarketRegistryPage>>,
+    #[account(init,
+        seeds = [registry.key().as_ref(), &(registry.current_page + 1).to_le_bytes(), b"registryPage"],
+        bump,
+        payer = payer,
+        space = MarketRegistryPage::SPACE
+    )]
+    pub next_page: Box<Account<'info, MarketRegistryPage>>,
+
+    pub system_program: Program<'info, System>,
+}
+impl<'info> OpenRegistryPage<'info> {
+    /// Only a full page can be followed, so pages can't be opened ahead of real markets
+    pub fn current_page_is_full(ctx: &Context<OpenRegistryPage>) -> Result<()> {
+        if ctx.accounts.current_page.markets.len() < MARKETS_PER_REGISTRY_PAGE {
+            return Err(errors::ErrorCode::RegistryPageIsNotFull.into())
+        }
+        Ok(())
+    }
+}
+
+/// Registration only write locks the registries, so initializing markets never contends
+/// on them.
+#[derive(Accounts)]
+pub struct RegisterMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(init,
+        seeds = [option_market.key().as_ref(), b"marketRegistration"],
+        bump,
+        payer = payer,
+        space = MarketRegistration::SPACE
+    )]
+    pub market_registration: Box<Account<'info, MarketRegistration>>,
+    #[account(mut,
+        seeds = [option_market.underlying_asset_mint.as_ref(), option_market.quote_asset_mint.as_ref(), b"quoteRegistry"],
+        bump = quote_registry.bump_seed,
+    )]
+    pub quote_registry: Box<Account<'info, MarketRegistry>>,
+    #[account(mut,
+        seeds = [quote_registry.key().as_ref(), &quote_registry.current_page.to_le_bytes(), b"registryPage"],
+        bump = quote_registry_page.bump_seed,
+    )]
+    pub quote_registry_page: Box<Account<'info, MarketRegistryPage>>,
+    #[account(mut,
+        seeds = [option_market.underlying_asset_mint.as_ref(), &option_market.expiration_unix_timestamp.to_le_bytes(), b"expiryRegistry"],
+        bump = expiry_registry.bump_seed,
+    )]
+    pub expiry_registry: Box<Account<'info, MarketRegistry>>,
+    #[account(mut,
+        seeds = [expiry_registry.key().as_ref(), &expiry_registry.current_page.to_le_bytes(), b"registryPage"],
+        bump = expiry_registry_page.bump_seed,
+    )]
+    pub expiry_registry_page: Box<Account<'info, MarketRegistryPage>>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use std::convert::TryInto;
+
+use crate::{OptionMarket, PutMarket, errors};
+
+pub mod token_metadata_program {
+  use solana_program::declare_id;
+  declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+/// The longest underlying or quote symbol a market can be named with
+pub const MAX_ASSET_SYMBOL_LEN: usize = 10;
+
+/// Characters of the mint address that name an asset without a usable Metaplex symbol
+pub const MINT_ADDRESS_SYMBOL_LEN: usize = 4;
+
+/// Long enough for two symbols, a strike with `STRIKE_DECIMALS` and the writer suffix
+pub const MAX_MINT_NAME_LEN: usize = 80;
+
+/// Decimals the strike in a mint name is rounded down to, trailing zeros are trimmed
+pub const STRIKE_DECIMALS: u32 = 6;
+
+/// Appended to the OptionToken name to name the WriterToken
+pub const WRITER_TOKEN_NAME_SUFFIX: &str = "-WRT";
+
+/// The Metaplex `Key::MetadataV1` discriminator
+const METAPLEX_METADATA_V1: u8 = 4;
+
+#[account]
+#[derive(Default)]
+/// Program owned metadata naming an option or writer mint, e.g. `SOL-USDC 100C 2026-12-31`
+/// and `SOL-USDC 100C 2026-12-31-WRT` for a call, `SOL-USDC 100P 2026-12-31` for a put
+pub struct MintMetadata {
+    pub mint: Pubkey,
+    /// The OptionMarket or PutMarket the mint belongs to
+    pub market: Pubkey,
+    pub name: String,
+    /// Bump seed for the MintMetadata PDA
+    pub bump_seed: u8,
+}
+
+impl MintMetadata {
+    pub const SPACE: usize = 8 + 32 + 32 + (4 + MAX_MINT_NAME_LEN) + 1;
+}
+
+#[derive(Clone, Copy)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+impl OptionKind {
+    /// The letter after the strike in a mint name
+    pub fn suffix(self) -> char {
+        match self {
+            OptionKind::Call => 'C',
+            OptionKind::Put => 'P',
+        }
+    }
+}
+
+/// The symbols must be short ASCII letters or digits
+pub fn is_valid_asset_symbol(symbol: &str) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= MAX_ASSET_SYMBOL_LEN
+        && symbol.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// The borsh string at the offset and the offset after it
+fn read_borsh_string(data: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+    let end = offset.checked_add(4)?.checked_add(len)?;
+    Some((data.get(offset + 4..end)?, end))
+}
+
+/// The symbol in a Metaplex Metadata account's data, if it is one
+fn read_metaplex_symbol(data: &[u8]) -> Option<String> {
+    if data.first() != Some(&METAPLEX_METADATA_V1) {
+        return None
+    }
+    // The key, update authority and mint come before the name and symbol
+    let (_name, symbol_offset) = read_borsh_string(data, 1 + 32 + 32)?;
+    let (symbol, _) = read_borsh_string(data, symbol_offset)?;
+    // Metaplex pads the symbol with null bytes
+    Some(std::str::from_utf8(symbol).ok()?.trim_end_matches('\0').to_string())
+}
+
+/// The mint's Metaplex symbol, or the start of its address when it has no metadata or the
+/// symbol can't be used in a name. Only the mint's Metaplex metadata address is accepted.
+pub fn asset_symbol(mint: &Pubkey, metadata: &AccountInfo) -> Result<String> {
+    let (metadata_key, _) = Pubkey::find_program_address(
+        &[b"metadata", token_metadata_program::ID.as_ref(), mint.as_ref()],
+        &token_metadata_program::ID
+    );
+    if metadata_key != *metadata.key {
+        return Err(errors::ErrorCode::MetadataAccountDoesNotMatchMint.into())
+    }
+    if *metadata.owner == token_metadata_program::ID {
+        if let Some(symbol) = read_metaplex_symbol(&metadata.try_borrow_data()?) {
+            if is_valid_asset_symbol(&symbol) {
+                return Ok(symbol)
+            }
+        }
+    }
+    Ok(mint.to_string()[..MINT_ADDRESS_SYMBOL_LEN].to_string())
+}
+
+/// The strike as quote asset per whole underlying asset, taking both mints' decimals into
+/// account. Rounded down to `STRIKE_DECIMALS` with trailing zeros trimmed.
+pub fn format_strike(
+    underlying_amount_per_contract: u64,
+    underlying_decimals: u8,
+    quote_amount_per_contract: u64,
+    quote_decimals: u8,
+) -> Result<String> {
+    let scale = |decimals: u32| 10u128.checked_pow(decimals).ok_or(errors::ErrorCode::NumberOverflow);
+    let numerator = (quote_amount_per_contract as u128)
+        .checked_mul(scale(underlying_decimals as u32)?)
+        .and_then(|n| n.checked_mul(10u128.pow(STRIKE_DECIMALS)))
+        .ok_or(errors::ErrorCode::NumberOverflow)?;
+    let denominator = (underlying_amount_per_contract as u128)
+        .checked_mul(scale(quote_decimals as u32)?)
+        .ok_or(errors::ErrorCode::NumberOverflow)?;
+    let strike = numerator / denominator;
+    let whole = strike / 10u128.pow(STRIKE_DECIMALS);
+    let fraction = strike % 10u128.pow(STRIKE_DECIMALS);
+    if fraction == 0 {
+        return Ok(whole.to_string())
+    }
+    let fraction = format!("{:0width$}", fraction, width = STRIKE_DECIMALS as usize);
+    Ok(format!("{}.{}", whole, fraction.trim_end_matches('0')))
+}
+
+/// The UTC date of the timestamp as `YYYY-MM-DD`
+pub fn format_date(unix_timestamp: i64) -> String {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = unix_timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The OptionToken name, e.g. `SOL-USDC 100C 2026-12-31`
+#[allow(clippy::too_many_arguments)]
+pub fn option_mint_name(
+    underlying_symbol: &str,
+    quote_symbol: &str,
+    underlying_amount_per_contract: u64,
+    underlying_decimals: u8,
+    quote_amount_per_contract: u64,
+    quote_decimals: u8,
+    expiration_unix_timestamp: i64,
+    kind: OptionKind,
+) -> Result<String> {
+    let strike = format_strike(
+        underlying_amount_per_contract,
+        underlying_decimals,
+        quote_amount_per_contract,
+        quote_decimals,
+    )?;
+    let name = format!(
+        "{}-{} {}{} {}",
+        underlying_symbol,
+        quote_symbol,
+        strike,
+        kind.suffix(),
+        format_date(expiration_unix_timestamp)
+    );
+    if name.len() + WRITER_TOKEN_NAME_SUFFIX.len() > MAX_MINT_NAME_LEN {
+        return Err(errors::ErrorCode::MintNameTooLong.into())
+    }
+    Ok(name)
+}
+
+/// Write the option and writer mint metadata for a market
+#[allow(clippy::too_many_arguments)]
+pub fn write_mint_metadata(
+    option_metadata: &mut MintMetadata,
+    option_metadata_bump: u8,
+    writer_metadata: &mut MintMetadata,
+    writer_metadata_bump: u8,
+    market_key: Pubkey,
+    option_mint: Pubkey,
+    writer_token_mint: Pubkey,
+    option_name: String,
+) {
+    option_metadata.mint = option_mint;
+    option_metadata.market = market_key;
+    option_metadata.bump_seed = option_metadata_bump;
+    writer_metadata.mint = writer_token_mint;
+    writer_metadata.market = market_key;
+    writer_metadata.name = format!("{}{}", option_name, WRITER_TOKEN_NAME_SUFFIX);
+    writer_metadata.bump_seed = writer_metadata_bump;
+    option_metadata.name = option_name;
+}
+
+#[derive(Accounts)]
+pub struct InitMintMetadata<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(address = option_market.underlying_asset_mint @ errors::ErrorCode::UnderlyingAssetMintDoesNotMatchMarket)]
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    #[account(address = option_market.quote_asset_mint @ errors::ErrorCode::QuoteAssetMintDoesNotMatchMarket)]
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: The underlying asset's Metaplex metadata address, checked by `asset_symbol`.
+    /// It doesn't need to exist.
+    pub underlying_asset_metadata: AccountInfo<'info>,
+    /// CHECK: The quote asset's Metaplex metadata address, checked by `asset_symbol`
+    pub quote_asset_metadata: AccountInfo<'info>,
+    #[account(init,
+        seeds = [option_market.option_mint.as_ref(), b"mintMetadata"],
+        bump,
+        payer = payer,
+        space = MintMetadata::SPACE
+    )]
+    pub option_metadata: Box<Account<'info, MintMetadata>>,
+    #[account(init,
+        seeds = [option_market.writer_token_mint.as_ref(), b"mintMetadata"],
+        bump,
+        payer = payer,
+        space = MintMetadata::SPACE
+    )]
+    pub writer_metadata: Box<Account<'info, MintMetadata>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitPutMintMetadata<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(address = put_market.underlying_asset_mint @ errors::ErrorCode::UnderlyingAssetMintDoesNotMatchMarket)]
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    #[account(address = put_market.quote_asset_mint @ errors::ErrorCode::QuoteAssetMintDoesNotMatchMarket)]
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: The underlying asset's Metaplex metadata address, checked by `asset_symbol`.
+    /// It doesn't need to exist.
+    pub underlying_asset_metadata: AccountInfo<'info>,
+    /// CHECK: The quote asset's Metaplex metadata address, checked by `asset_symbol`
+    pub quote_asset_metadata: AccountInfo<'info>,
+    #[account(init,
+        seeds = [put_market.option_mint.as_ref(), b"mintMetadata"],
+        bump,
+        payer = payer,
+        space = MintMetadata::SPACE
+    )]
+    pub option_metadata: Box<Account<'info, MintMetadata>>,
+    #[account(init,
+        seeds = [put_market.writer_token_mint.as_ref(), b"mintMetadata"],
+        bump,
+        payer = payer,
+        space = MintMetadata::SPACE
+    )]
+    pub writer_metadata: Box<Account<'info, MintMetadata>>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use solana_program::{program::invoke, system_instruction};
+
+use crate::{OptionMarket, auto_exercise::auto_exercise_closed, errors, fees};
+
+/// Move lamports from the payer into a wrapped SOL token account and sync its balance
+pub fn wrap_lamports<'info>(
+    payer: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    invoke(
+        &system_instruction::transfer(payer.key, token_account.key, lamports),
+        &[payer.clone(), token_account.clone(), system_program.clone()],
+    )?;
+    invoke(
+        &spl_token::instruction::sync_native(&spl_token::ID, token_account.key)?,
+        &[token_account.clone(), token_program.clone()],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MintOptionNative<'info> {
+    /// Pays the underlying and mint fee in lamports
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for markets too small to take an underlying fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> MintOptionNative<'info> {
+    pub fn accounts(ctx: &Context<MintOptionNative>) -> Result<()> {
+        if ctx.accounts.option_market.underlying_asset_mint != spl_token::native_mint::ID {
+            return Err(errors::ErrorCode::UnderlyingAssetMustBeNativeMint.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<MintOptionNative>) -> Result<()> {
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExerciseOptionNative<'info> {
+    /// Pays the quote asset and exercise fee in lamports
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    /// The owner of the `exerciser_option_token_src` account
+    pub option_authority: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = underlying_asset_dest.mint == option_market.underlying_asset_mint @ errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for markets too small to take a quote fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> ExerciseOptionNative<'info> {
+    pub fn accounts(ctx: &Context<ExerciseOptionNative>) -> Result<()> {
+        if ctx.accounts.option_market.quote_asset_mint != spl_token::native_mint::ID {
+            return Err(errors::ErrorCode::QuoteAssetMustBeNativeMint.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<ExerciseOptionNative>) -> Result<()> {
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantExercise.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClosePostExpNative<'info> {
+    /// Receives the underlying as lamports
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(address = spl_token::native_mint::ID)]
+    pub native_mint: Box<Account<'info, Mint>>,
+    /// Holds the underlying for the instruction, closing it unwraps the lamports to the user
+    #[account(init,
+        seeds = [option_market.key().as_ref(), user_authority.key().as_ref(), b"nativeUnwrap"],
+        bump,
+        payer = user_authority,
+        token::mint = native_mint,
+        token::authority = option_market,
+    )]
+    pub unwrap_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+impl<'info> ClosePostExpNative<'info> {
+    pub fn accounts(ctx: &Context<ClosePostExpNative>) -> Result<()> {
+        if ctx.accounts.option_market.underlying_asset_mint != spl_token::native_mint::ID {
+            return Err(errors::ErrorCode::UnderlyingAssetMustBeNativeMint.into())
+        }
+        Ok(())
+    }
+    pub fn expired_market(ctx: &Context<ClosePostExpNative>) -> Result<()> {
+        // Knocked out options are worthless, so writers don't have to wait for expiration
+        if ctx.accounts.option_market.knocked_out {
+            return Ok(())
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if ctx.accounts.option_market.expiration_unix_timestamp >= now {
+            return Err(errors::ErrorCode::OptionMarketNotExpiredCantClose.into())
+        }
+        auto_exercise_closed(&ctx.accounts.option_market, now)
+    }
+    // Validate the underlying kept by net exercises stays in the pool for its writers
+    pub fn underlying_in_pool(ctx: &Context<ClosePostExpNative>, size: u64) -> Result<()> {
+        let available = ctx.accounts.underlying_asset_pool.amount
+            .saturating_sub(ctx.accounts.option_market.net_exercised_underlying);
+        let underlying_amount = size
+            .checked_mul(ctx.accounts.option_market.underlying_amount_per_contract)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if available < underlying_amount {
+            return Err(errors::ErrorCode::NotEnoughUnderlyingAssetsInPool.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct BurnWriterForQuoteNative<'info> {
+    /// Receives the quote asset as lamports
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = option_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(address = spl_token::native_mint::ID)]
+    pub native_mint: Box<Account<'info, Mint>>,
+    /// Holds the quote for the instruction, closing it unwraps the lamports to the user
+    #[account(init,
+        seeds = [option_market.key().as_ref(), user_authority.key().as_ref(), b"nativeUnwrap"],
+        bump,
+        payer = user_authority,
+        token::mint = native_mint,
+        token::authority = option_market,
+    )]
+    pub unwrap_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+impl<'info> BurnWriterForQuoteNative<'info> {
+    pub fn accounts(ctx: &Context<BurnWriterForQuoteNative>) -> Result<()> {
+        if ctx.accounts.option_market.quote_asset_mint != spl_token::native_mint::ID {
+            return Err(errors::ErrorCode::QuoteAssetMustBeNativeMint.into())
+        }
+        Ok(())
+    }
+    // Validate there is enough quote assets in the pool
+    pub fn quotes_in_pool(ctx: &Context<BurnWriterForQuoteNative>, size: u64) -> Result<()> {
+        let quote_amount = size
+            .checked_mul(ctx.accounts.option_market.quote_amount_per_contract)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if ctx.accounts.quote_asset_pool.amount < quote_amount {
+            return Err(errors::ErrorCode::NotEnoughQuoteAssetsInPool.into())
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, oracle::OracleConfig};
+
+#[derive(Accounts)]
+pub struct ExerciseOptionNet<'info> {
+    /// The owner of the `exerciser_option_token_src` account
+    pub option_authority: Signer<'info>,
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump = oracle_config.bump_seed,
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+    /// CHECK: Validated against the OracleConfig when the price is loaded
+    pub oracle: AccountInfo<'info>,
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> ExerciseOptionNet<'info> {
+    pub fn accounts(ctx: &Context<ExerciseOptionNet>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.option_mint.key() != option_market.option_mint {
+            return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_dest.mint != option_market.underlying_asset_mint {
+            return Err(errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<ExerciseOptionNet>) -> Result<()> {
+        if ctx.accounts.option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantExercise.into())
+        }
+        Ok(())
+    }
+}
+
+/// Split the underlying of `size` contracts exercised at `contract_value` into the amount
+/// delivered to the holder and the amount the pool keeps in place of the strike.
+pub fn net_exercise_amounts(option_market: &OptionMarket, contract_value: u64, size: u64) -> Result<(u64, u64)> {
+    if contract_value <= option_market.quote_amount_per_contract {
+        return Err(errors::ErrorCode::OptionNotInTheMoney.into())
+    }
+    let underlying_amount = option_market.underlying_amount_per_contract
+        .checked_mul(size)
+        .ok_or(errors::ErrorCode::NumberOverflow)?;
+    // Rounds down so any dust stays with the writers
+    let delivered = (underlying_amount as u128)
+        .checked_mul((contract_value - option_market.quote_amount_per_contract) as u128)
+        .ok_or(errors::ErrorCode::NumberOverflow)?
+        / contract_value as u128;
+    let delivered = delivered as u64;
+    Ok((delivered, underlying_amount - delivered))
+}
+
+#[derive(Accounts)]
+pub struct BurnWriterForNetExercise<'info> {
+    pub user_authority: Signer<'info>,
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> BurnWriterForNetExercise<'info> {
+    pub fn accounts(ctx: &Context<BurnWriterForNetExercise>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.writer_token_mint.key() != option_market.writer_token_mint {
+            return Err(errors::ErrorCode::WriterTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_dest.mint != option_market.underlying_asset_mint {
+            return Err(errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset.into())
+        }
+        Ok(())
+    }
+
+    // Validate enough contracts were net exercised to back the claim
+    pub fn net_exercised(ctx: &Context<BurnWriterForNetExercise>, size: u64) -> Result<()> {
+        if size > ctx.accounts.option_market.net_exercised_contracts {
+            return Err(errors::ErrorCode::NotEnoughNetExercisedContracts.into())
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, InitializeAccount, InitializeMint, Mint, Token};
+use solana_program::{program::invoke_signed, program_pack::Pack, system_instruction};
+use spl_token::state::{Account as SPLTokenAccount, Mint as SPLMint};
+
+use crate::{OptionMarket, errors};
+
+/// The most strikes an OptionChain can index
+pub const MAX_CHAIN_STRIKES: usize = 32;
+
+/// The remaining accounts each strike of `initialize_chain` takes after the fee accounts,
+/// in order: the OptionMarket, its option mint, its writer token mint, its quote asset pool
+/// and its underlying asset pool. All are derived exactly as `InitializeMarket` does.
+pub const CHAIN_ACCOUNTS_PER_STRIKE: usize = 5;
+
+#[account]
+/// Index of the OptionMarkets listed together for one underlying, quote and expiration
+pub struct OptionChain {
+    pub underlying_asset_mint: Pubkey,
+    pub quote_asset_mint: Pubkey,
+    pub underlying_amount_per_contract: u64,
+    pub expiration_unix_timestamp: i64,
+    /// The `quote_amount_per_contract` of each market, ascending
+    pub strikes: Vec<u64>,
+    /// The OptionMarket for each strike, in the same order
+    pub option_markets: Vec<Pubkey>,
+    /// Bump seed for the OptionChain PDA
+    pub bump_seed: u8,
+}
+
+impl OptionChain {
+    pub const SPACE: usize = 8 + 32 * 2 + 8 * 2 + (4 + MAX_CHAIN_STRIKES * 8) + (4 + MAX_CHAIN_STRIKES * 32) + 1;
+}
+
+/// The space allocated for every OptionMarket
+pub const OPTION_MARKET_SPACE: usize = 8 + std::mem::size_of::<OptionMarket>() + 300;
+
+pub fn validate_chain_strikes(strikes: &[u64]) -> Result<()> {
+    if strikes.is_empty() || strikes.len() > MAX_CHAIN_STRIKES {
+        return Err(errors::ErrorCode::InvalidChainStrikes.into())
+    }
+    if strikes[0] == 0 || strikes.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(errors::ErrorCode::InvalidChainStrikes.into())
+    }
+    Ok(())
+}
+
+/// Create a PDA owned by `owner`. Like Anchor's `init`, accounts that were already sent
+/// lamports are topped up, allocated and assigned instead.
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let signer = &[seeds];
+    if account.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, rent, space as u64, owner),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer,
+        )?;
+        return Ok(())
+    }
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke_signed(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer,
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        signer,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        signer,
+    )?;
+    Ok(())
+}
+
+/// The accounts shared by every market created by `initialize_chain`
+pub struct ChainMarketAccounts<'a, 'info> {
+    pub authority: &'a AccountInfo<'info>,
+    pub underlying_asset_mint: &'a AccountInfo<'info>,
+    pub quote_asset_mint: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub rent: &'a AccountInfo<'info>,
+}
+
+/// Create the OptionMarket for one strike of a chain along with its mints and pools. The
+/// OptionMarket is returned with its addresses filled in for the caller to finish.
+pub fn create_chain_market<'info>(
+    accounts: &ChainMarketAccounts<'_, 'info>,
+    strike_accounts: &[AccountInfo<'info>],
+    underlying_amount_per_contract: u64,
+    quote_amount_per_contract: u64,
+    expiration_unix_timestamp: i64,
+) -> Result<OptionMarket> {
+    let option_market = &strike_accounts[0];
+    let underlying_amount_bytes = underlying_amount_per_contract.to_le_bytes();
+    let quote_amount_bytes = quote_amount_per_contract.to_le_bytes();
+    let expiration_bytes = expiration_unix_timestamp.to_le_bytes();
+    let market_seeds: &[&[u8]] = &[
+        accounts.underlying_asset_mint.key.as_ref(),
+        accounts.quote_asset_mint.key.as_ref(),
+        &underlying_amount_bytes,
+        &quote_amount_bytes,
+        &expiration_bytes,
+    ];
+    let (market_key, bump_seed) = Pubkey::find_program_address(market_seeds, &crate::ID);
+    if market_key != *option_market.key {
+        return Err(errors::ErrorCode::ChainAccountsDoNotMatchStrikes.into())
+    }
+    let bump = [bump_seed];
+    let market_signer_seeds = &[
+        accounts.underlying_asset_mint.key.as_ref(),
+        accounts.quote_asset_mint.key.as_ref(),
+        &underlying_amount_bytes,
+        &quote_amount_bytes,
+        &expiration_bytes,
+        &bump,
+    ];
+    create_pda_account(
+        accounts.authority,
+        option_market,
+        accounts.system_program,
+        OPTION_MARKET_SPACE,
+        &crate::ID,
+        market_signer_seeds,
+    )?;
+
+    // Create the mints and pools with the same seeds as InitializeMarket
+    let pda_seeds: [&[u8]; 4] = [b"optionToken", b"writerToken", b"quoteAssetPool", b"underlyingAssetPool"];
+    for (i, seed) in pda_seeds.iter().enumerate() {
+        let account = &strike_accounts[i + 1];
+        let (key, pda_bump) = Pubkey::find_program_address(&[&option_market.key.to_bytes()[..], *seed], &crate::ID);
+        if key != *account.key {
+            return Err(errors::ErrorCode::ChainAccountsDoNotMatchStrikes.into())
+        }
+        let space = if i < 2 { SPLMint::LEN } else { SPLTokenAccount::LEN };
+        create_pda_account(
+            accounts.authority,
+            account,
+            accounts.system_program,
+            space,
+            &spl_token::ID,
+            &[&option_market.key.to_bytes()[..], *seed, &[pda_bump]],
+        )?;
+        if i < 2 {
+            let cpi_accounts = InitializeMint {
+                mint: account.clone(),
+                rent: accounts.rent.clone(),
+            };
+            let cpi_ctx = CpiContext::new(accounts.token_program.clone(), cpi_accounts);
+            token::initialize_mint(cpi_ctx, 0, option_market.key, None)?;
+        } else {
+            let mint = if i == 2 { accounts.quote_asset_mint } else { accounts.underlying_asset_mint };
+            let cpi_accounts = InitializeAccount {
+                account: account.clone(),
+                mint: mint.clone(),
+                authority: option_market.clone(),
+                rent: accounts.rent.clone(),
+            };
+            let cpi_ctx = CpiContext::new(accounts.token_program.clone(), cpi_accounts);
+            token::initialize_account(cpi_ctx)?;
+        }
+    }
+
+    Ok(OptionMarket {
+        option_mint: *strike_accounts[1].key,
+        writer_token_mint: *strike_accounts[2].key,
+        underlying_asset_mint: *accounts.underlying_asset_mint.key,
+        quote_asset_mint: *accounts.quote_asset_mint.key,
+        underlying_amount_per_contract,
+        quote_amount_per_contract,
+        expiration_unix_timestamp,
+        quote_asset_pool: *strike_accounts[3].key,
+        underlying_asset_pool: *strike_accounts[4].key,
+        bump_seed,
+        ..Default::default()
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(underlying_amount_per_contract: u64, expiration_unix_timestamp: i64)]
+pub struct InitializeChain<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [
+            underlying_asset_mint.key().as_ref(),
+            quote_asset_mint.key().as_ref(),
+            &underlying_amount_per_contract.to_le_bytes(),
+            &expiration_unix_timestamp.to_le_bytes(),
+            b"optionChain"
+        ],
+        bump,
+        payer = authority,
+        space = OptionChain::SPACE
+    )]
+    pub option_chain: Box<Account<'info, OptionChain>>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> InitializeChain<'info> {
+    pub fn accounts(
+        ctx: &Context<InitializeChain>,
+        underlying_amount_per_contract: u64,
+        expiration_unix_timestamp: i64,
+        strikes: &[u64],
+    ) -> Result<()> {
+        if expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::ExpirationIsInThePast.into())
+        }
+        if underlying_amount_per_contract == 0 {
+            return Err(errors::ErrorCode::QuoteOrUnderlyingAmountCannotBe0.into())
+        }
+        if ctx.accounts.underlying_asset_mint.key() == ctx.accounts.quote_asset_mint.key() {
+            return Err(errors::ErrorCode::QuoteAndUnderlyingAssetMustDiffer.into())
+        }
+        validate_chain_strikes(strikes)
+    }
+}
@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use std::convert::TryFrom;
+
+use crate::{OptionMarket, admin, errors};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OracleSource {
+    /// A Pyth V2 price account
+    Pyth,
+    /// A TwapAccumulator of the underlying's Serum market. Only used as a settlement
+    /// price source, it can't be read as a live price.
+    SerumTwap,
+}
+
+impl Default for OracleSource {
+    fn default() -> Self {
+        OracleSource::Pyth
+    }
+}
+
+/// A price read from an oracle. The value of one whole underlying token is
+/// `price * 10^expo` units of the quote asset.
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Convert the price into the value of `underlying_amount_per_contract`, denominated
+    /// in the quote asset's native units. This is the same unit as the strike
+    /// (`quote_amount_per_contract`), so the two can be compared directly.
+    pub fn contract_value(&self, option_market: &OptionMarket, oracle_config: &OracleConfig) -> Result<u64> {
+        self.value_of(
+            option_market.underlying_amount_per_contract,
+            oracle_config.underlying_decimals,
+            oracle_config.quote_decimals,
+        )
+    }
+
+    /// The value of `underlying_amount` native units of the underlying asset in the quote
+    /// asset's native units
+    pub fn value_of(&self, underlying_amount: u64, underlying_decimals: u8, quote_decimals: u8) -> Result<u64> {
+        if self.price <= 0 {
+            return Err(errors::ErrorCode::InvalidOraclePrice.into())
+        }
+        let value = (self.price as u128)
+            .checked_mul(underlying_amount as u128)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        let exponent = quote_decimals as i32 + self.expo - underlying_decimals as i32;
+        let scale = 10u128.checked_pow(exponent.unsigned_abs()).ok_or(errors::ErrorCode::NumberOverflow)?;
+        let value = if exponent >= 0 {
+            value.checked_mul(scale).ok_or(errors::ErrorCode::NumberOverflow)?
+        } else {
+            value / scale
+        };
+        u64::try_from(value).map_err(|_| errors::ErrorCode::NumberOverflow.into())
+    }
+}
+
+#[account]
+/// Which oracle prices an OptionMarket's underlying asset. Shared by every feature that
+/// needs a price for the market.
+pub struct OracleConfig {
+    /// The OptionMarket the oracle prices
+    pub option_market: Pubkey,
+    /// How the oracle account is parsed
+    pub oracle_source: OracleSource,
+    /// The oracle's price account
+    pub oracle: Pubkey,
+    /// Prices older than this many seconds are rejected
+    pub max_staleness: i64,
+    /// Decimals of the underlying asset mint
+    pub underlying_decimals: u8,
+    /// Decimals of the quote asset mint
+    pub quote_decimals: u8,
+    /// Bump seed for the OracleConfig PDA
+    pub bump_seed: u8,
+}
+
+impl OracleConfig {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 8 + 1 + 1 + 1;
+
+    /// Read the current price from the configured oracle account
+    pub fn load_price(&self, oracle: &AccountInfo, now: i64) -> Result<OraclePrice> {
+        if *oracle.key != self.oracle {
+            return Err(errors::ErrorCode::OracleDoesNotMatchConfig.into())
+        }
+        let price = load_price(self.oracle_source, oracle)?;
+        if now.saturating_sub(price.publish_time) > self.max_staleness {
+            return Err(errors::ErrorCode::OraclePriceIsStale.into())
+        }
+        Ok(price)
+    }
+}
+
+/// Load the OracleConfig of an OptionMarket from an AccountInfo that was not validated
+/// by Anchor (i.e. inside the Serum proxy).
+pub fn load_oracle_config<'info>(
+    option_market: &Pubkey,
+    oracle_config: &AccountInfo<'info>,
+) -> Result<Account<'info, OracleConfig>> {
+    let oracle_config = Account::<OracleConfig>::try_from(oracle_config)?;
+    if oracle_config.option_market != *option_market {
+        return Err(errors::ErrorCode::OracleConfigDoesNotMatchMarket.into())
+    }
+    Ok(oracle_config)
+}
+
+/// Read the current price from an oracle account of the given source
+pub fn load_price(oracle_source: OracleSource, oracle: &AccountInfo) -> Result<OraclePrice> {
+    match oracle_source {
+        OracleSource::Pyth => pyth::load_price(oracle),
+        OracleSource::SerumTwap => Err(errors::ErrorCode::InvalidOracleAccount.into()),
+    }
+}
+
+/// Validate the oracle account can be read as the given source
+pub fn validate_oracle(oracle_source: OracleSource, oracle: &AccountInfo) -> Result<()> {
+    load_price(oracle_source, oracle).map(|_| ())
+}
+
+/// Minimal reader for Pyth V2 price accounts, so the program doesn't need to pull in
+/// the Pyth SDK and its Solana version requirements.
+mod pyth {
+    use super::*;
+    use std::convert::TryInto;
+
+    const MAGIC: u32 = 0xa1b2c3d4;
+    const VERSION_2: u32 = 2;
+    const ACCOUNT_TYPE_PRICE: u32 = 3;
+    const STATUS_TRADING: u32 = 1;
+    const PRICE_ACCOUNT_LEN: usize = 240;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_i32(data: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_i64(data: &[u8], offset: usize) -> i64 {
+        i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    pub fn load_price(oracle: &AccountInfo) -> Result<OraclePrice> {
+        let data = oracle.try_borrow_data()?;
+        if data.len() < PRICE_ACCOUNT_LEN
+            || read_u32(&data, 0) != MAGIC
+            || read_u32(&data, 4) != VERSION_2
+            || read_u32(&data, 8) != ACCOUNT_TYPE_PRICE {
+            return Err(errors::ErrorCode::InvalidOracleAccount.into())
+        }
+        // The aggregate price is only usable while the product is trading
+        if read_u32(&data, 224) != STATUS_TRADING {
+            return Err(errors::ErrorCode::InvalidOraclePrice.into())
+        }
+        Ok(OraclePrice {
+            price: read_i64(&data, 208),
+            expo: read_i32(&data, 20),
+            publish_time: read_i64(&data, 96),
+        })
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitOracleConfig<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(address = option_market.underlying_asset_mint @ errors::ErrorCode::UnderlyingAssetMintDoesNotMatchMarket)]
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    #[account(address = option_market.quote_asset_mint @ errors::ErrorCode::QuoteAssetMintDoesNotMatchMarket)]
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Parsed according to `oracle_source` before it is saved
+    pub oracle: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump,
+        payer = admin,
+        space = OracleConfig::SPACE
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracleConfig<'info> {
+    #[account(address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    /// CHECK: Parsed according to `oracle_source` before it is saved
+    pub oracle: AccountInfo<'info>,
+    #[account(mut)]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+}
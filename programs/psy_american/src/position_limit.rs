@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{OptionMarket, admin, errors};
+
+#[account]
+/// Caps the number of contracts a single owner can have resting on an OptionMarket's
+/// Serum markets. Every owner trades through their OpenOrders PDA, so the limit is
+/// enforced per OpenOrders account.
+pub struct PositionLimit {
+    /// The OptionMarket the limit applies to
+    pub option_market: Pubkey,
+    /// The maximum number of contracts an owner can have on order
+    pub max_contracts: u64,
+    /// Bump seed for the PositionLimit PDA
+    pub bump_seed: u8,
+}
+
+impl PositionLimit {
+    pub const SPACE: usize = 8 + 32 + 8 + 1;
+}
+
+/// Validate the limit allows at least one contract
+pub fn validate_position_limit(max_contracts: u64) -> Result<()> {
+    if max_contracts == 0 {
+        return Err(errors::ErrorCode::InvalidPositionLimit.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitPositionLimit<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"positionLimit"],
+        bump,
+        payer = admin,
+        space = PositionLimit::SPACE
+    )]
+    pub position_limit: Box<Account<'info, PositionLimit>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePositionLimit<'info> {
+    #[account(address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub position_limit: Box<Account<'info, PositionLimit>>,
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{OptionMarket, admin, errors, oracle::OracleConfig};
+
+#[account]
+/// Limits the prices orders can be placed at on an OptionMarket's Serum markets.
+/// Prices are in the quote asset's native units per contract.
+pub struct PriceBand {
+    /// The OptionMarket the band applies to
+    pub option_market: Pubkey,
+    /// How far below the intrinsic value, in basis points, an order may be priced
+    pub tolerance_bps: u16,
+    /// The highest price an order may be placed at
+    pub max_price: u64,
+    /// Bump seed for the PriceBand PDA
+    pub bump_seed: u8,
+}
+
+impl PriceBand {
+    pub const SPACE: usize = 8 + 32 + 2 + 8 + 1;
+
+    /// The lowest price an order may be placed at given the option's intrinsic value
+    pub fn min_price(&self, intrinsic_value: u64) -> u64 {
+        let floor = intrinsic_value as u128 * (10_000 - self.tolerance_bps as u128) / 10_000;
+        floor as u64
+    }
+}
+
+/// Validate the band's parameters
+pub fn validate_price_band(tolerance_bps: u16, max_price: u64) -> Result<()> {
+    if tolerance_bps > 10_000 || max_price == 0 {
+        return Err(errors::ErrorCode::InvalidPriceBand.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitPriceBand<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    /// The intrinsic value can't be computed without an oracle, so it must be configured first
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump = oracle_config.bump_seed,
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"priceBand"],
+        bump,
+        payer = admin,
+        space = PriceBand::SPACE
+    )]
+    pub price_band: Box<Account<'info, PriceBand>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceBand<'info> {
+    #[account(address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub price_band: Box<Account<'info, PriceBand>>,
+}
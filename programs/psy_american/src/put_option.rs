@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use solana_program::program_pack::Pack;
+use spl_token::state::Account as SPLTokenAccount;
+
+use crate::{errors, fees};
+
+#[account]
+/// A market for cash-secured puts. Writers lock the strike in the quote asset, holders
+/// deliver `underlying_amount_per_contract` of the underlying asset for it. Fees are
+/// charged on the strike in the quote asset.
+pub struct PutMarket {
+    /// The SPL Token mint address for the tokens that denote an option
+    pub option_mint: Pubkey,
+    /// The SPL Token mint address for Writer Tokens that denote a written option
+    pub writer_token_mint: Pubkey,
+    /// The asset holders deliver when exercising
+    pub underlying_asset_mint: Pubkey,
+    /// The asset the strike is locked and paid in
+    pub quote_asset_mint: Pubkey,
+    /// The amount of the underlying asset delivered per contract
+    pub underlying_amount_per_contract: u64,
+    /// The strike, the amount of quote asset paid out per contract
+    pub quote_amount_per_contract: u64,
+    /// The Unix timestamp at which the contracts in this market expire
+    pub expiration_unix_timestamp: i64,
+    /// Address for the pool that holds the locked strikes
+    pub quote_asset_pool: Pubkey,
+    /// Address for the pool that holds the underlying delivered by exercises
+    pub underlying_asset_pool: Pubkey,
+    /// The quote token account fees are paid to, or the fee owner when the strike is too
+    /// small to take a fee from and minting is charged in SOL
+    pub fee_account: Pubkey,
+    /// Bump seed for the market PDA
+    pub bump_seed: u8,
+}
+
+impl PutMarket {
+    pub const SPACE: usize = 8 + 32 * 4 + 8 * 3 + 32 * 3 + 1;
+}
+
+#[derive(Accounts)]
+#[instruction(
+    underlying_amount_per_contract: u64,
+    quote_amount_per_contract: u64,
+    expiration_unix_timestamp: i64,
+)]
+pub struct InitializePutMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub underlying_asset_mint: Box<Account<'info, Mint>>,
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Validated against the fee owner and quote asset when the market is created
+    pub fee_account: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&put_market.key().to_bytes()[..], b"optionToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = put_market
+    )]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&put_market.key().to_bytes()[..], b"writerToken"],
+        bump,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = put_market
+    )]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [&put_market.key().to_bytes()[..], b"quoteAssetPool"],
+        bump,
+        payer = authority,
+        token::mint = quote_asset_mint,
+        token::authority = put_market,
+    )]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(init,
+        seeds = [&put_market.key().to_bytes()[..], b"underlyingAssetPool"],
+        bump,
+        payer = authority,
+        token::mint = underlying_asset_mint,
+        token::authority = put_market,
+    )]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        seeds = [
+            underlying_asset_mint.key().as_ref(),
+            quote_asset_mint.key().as_ref(),
+            &underlying_amount_per_contract.to_le_bytes(),
+            &quote_amount_per_contract.to_le_bytes(),
+            &expiration_unix_timestamp.to_le_bytes(),
+            b"putMarket"
+        ],
+        bump,
+        payer = authority,
+        space = PutMarket::SPACE
+    )]
+    pub put_market: Box<Account<'info, PutMarket>>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> InitializePutMarket<'info> {
+    pub fn accounts(
+        ctx: &Context<InitializePutMarket>,
+        underlying_amount_per_contract: u64,
+        quote_amount_per_contract: u64,
+        expiration_unix_timestamp: i64,
+    ) -> Result<()> {
+        if expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::ExpirationIsInThePast.into())
+        }
+        if underlying_amount_per_contract == 0 || quote_amount_per_contract == 0 {
+            return Err(errors::ErrorCode::QuoteOrUnderlyingAmountCannotBe0.into())
+        }
+        if ctx.accounts.underlying_asset_mint.key() == ctx.accounts.quote_asset_mint.key() {
+            return Err(errors::ErrorCode::QuoteAndUnderlyingAssetMustDiffer.into())
+        }
+        let fee_account = &ctx.accounts.fee_account;
+        if fees::fee_amount(quote_amount_per_contract) > 0 {
+            if fee_account.owner != &spl_token::ID {
+                return Err(errors::ErrorCode::ExpectedSPLTokenProgramId.into())
+            }
+            let fee_token_account = SPLTokenAccount::unpack_from_slice(&fee_account.try_borrow_data()?)?;
+            if fee_token_account.owner != fees::fee_owner_key::ID {
+                return Err(errors::ErrorCode::MintFeeMustBeOwnedByFeeOwner.into())
+            }
+            if fee_token_account.mint != ctx.accounts.quote_asset_mint.key() {
+                return Err(errors::ErrorCode::PutFeeTokenMustMatchQuoteAsset.into())
+            }
+        } else if fee_account.key() != fees::fee_owner_key::ID {
+            return Err(errors::ErrorCode::FeeOwnerDoesNotMatchProgram.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct MintPut<'info> {
+    /// The owner of the `quote_asset_src`
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(mut)]
+    pub quote_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Checked against the PutMarket
+    #[account(mut, address = put_market.fee_account @ errors::ErrorCode::MintFeeKeyDoesNotMatchOptionMarket)]
+    pub fee_account: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> MintPut<'info> {
+    pub fn unexpired_market(ctx: &Context<MintPut>) -> Result<()> {
+        if ctx.accounts.put_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExercisePut<'info> {
+    /// The owner of the `exerciser_option_token_src` and `underlying_asset_src`
+    pub user_authority: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(mut, address = put_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Checked against the PutMarket
+    #[account(mut, address = put_market.fee_account @ errors::ErrorCode::ExerciseFeeKeyDoesNotMatchOptionMarket)]
+    pub fee_account: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> ExercisePut<'info> {
+    pub fn unexpired_market(ctx: &Context<ExercisePut>) -> Result<()> {
+        if ctx.accounts.put_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantExercise.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClosePutPosition<'info> {
+    /// The owner of the `writer_token_src` and `option_token_src`
+    pub user_authority: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(mut, address = put_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub option_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePutPostExpiration<'info> {
+    /// The owner of the `writer_token_src`
+    pub user_authority: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(mut, address = put_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> ClosePutPostExpiration<'info> {
+    pub fn expired_market(ctx: &Context<ClosePutPostExpiration>) -> Result<()> {
+        if ctx.accounts.put_market.expiration_unix_timestamp >= Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketNotExpiredCantClose.into())
+        }
+        Ok(())
+    }
+    pub fn quotes_in_pool(ctx: &Context<ClosePutPostExpiration>, size: u64) -> Result<()> {
+        let quote_amount = ctx.accounts.put_market.quote_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if quote_amount > ctx.accounts.quote_asset_pool.amount {
+            return Err(errors::ErrorCode::NotEnoughQuoteAssetsInPool.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct BurnPutWriterForUnderlying<'info> {
+    /// The owner of the `writer_token_src`
+    pub user_authority: Signer<'info>,
+    pub put_market: Box<Account<'info, PutMarket>>,
+    #[account(mut, address = put_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = put_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> BurnPutWriterForUnderlying<'info> {
+    pub fn underlying_in_pool(ctx: &Context<BurnPutWriterForUnderlying>, size: u64) -> Result<()> {
+        let underlying_amount = ctx.accounts.put_market.underlying_amount_per_contract
+            .checked_mul(size)
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if underlying_amount > ctx.accounts.underlying_asset_pool.amount {
+            return Err(errors::ErrorCode::NotEnoughUnderlyingAssetsInPool.into())
+        }
+        Ok(())
+    }
+}
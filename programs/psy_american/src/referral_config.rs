@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{OptionMarket, admin, errors};
+
+/// The maximum number of recipients a ReferralPolicy::Allowlist can hold
+pub const MAX_REFERRAL_RECIPIENTS: usize = 10;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum ReferralPolicy {
+    /// Every referral rebate is paid to a token account owned by `recipient`
+    Fixed { recipient: Pubkey },
+    /// The caller of `settle_funds` picks the referral account, as long as its
+    /// owner is in the list
+    Allowlist { recipients: Vec<Pubkey> },
+}
+
+impl ReferralPolicy {
+    pub fn allows(&self, referral_owner: &Pubkey) -> bool {
+        match self {
+            ReferralPolicy::Fixed { recipient } => recipient == referral_owner,
+            ReferralPolicy::Allowlist { recipients } => recipients.contains(referral_owner),
+        }
+    }
+}
+
+/// Validate an Allowlist policy is not empty and fits in the ReferralConfig account
+pub fn validate_referral_policy(policy: &ReferralPolicy) -> Result<()> {
+    if let ReferralPolicy::Allowlist { recipients } = policy {
+        if recipients.is_empty() || recipients.len() > MAX_REFERRAL_RECIPIENTS {
+            return Err(errors::ErrorCode::InvalidReferralAllowlist.into())
+        }
+    }
+    Ok(())
+}
+
+#[account]
+/// Stores who receives the referral rebates for a PsyOptions Serum market. Markets
+/// without a ReferralConfig pay the rebates to the `serum_proxy::referral` key.
+pub struct ReferralConfig {
+    /// The Serum market the policy applies to
+    pub serum_market: Pubkey,
+    /// The referral recipient policy enforced on `settle_funds`
+    pub policy: ReferralPolicy,
+    /// Bump seed for the ReferralConfig PDA
+    pub bump_seed: u8,
+}
+
+impl ReferralConfig {
+    pub const SPACE: usize = 8 + 32 + (1 + 4 + 32 * MAX_REFERRAL_RECIPIENTS) + 1;
+}
+
+#[derive(Accounts)]
+pub struct InitReferralConfig<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    pub pc_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Only used as a seed, the seeds constraint validates it belongs to the OptionMarket
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], &pc_mint.key().to_bytes()[..], b"serumMarket"],
+        bump,
+    )]
+    pub serum_market: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&serum_market.key().to_bytes()[..], b"referralConfig"],
+        bump,
+        payer = admin,
+        space = ReferralConfig::SPACE
+    )]
+    pub referral_config: Box<Account<'info, ReferralConfig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReferralConfig<'info> {
+    #[account(address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub referral_config: Box<Account<'info, ReferralConfig>>,
+}
@@ -0,0 +1,284 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, fees};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RfqLegAsset {
+    /// The deliverer already holds the OptionTokens
+    OptionToken,
+    /// The deliverer locks the underlying that backs the contracts, which are minted
+    /// to the counterparty on settlement
+    Collateral,
+}
+
+/// The instrument data of an RFQ leg that settles a PsyOptions American option
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RfqLeg {
+    pub option_market: Pubkey,
+    pub asset: RfqLegAsset,
+    /// The number of contracts delivered
+    pub amount: u64,
+}
+
+impl RfqLeg {
+    /// Validate the leg references the given OptionMarket and can still be settled
+    pub fn validate(&self, option_market: &Account<OptionMarket>) -> Result<()> {
+        if self.option_market != option_market.key() {
+            return Err(errors::ErrorCode::RfqLegDoesNotMatchOptionMarket.into())
+        }
+        if self.amount == 0 {
+            return Err(errors::ErrorCode::SizeCantBeLessThanEqZero.into())
+        }
+        if option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::RfqLegOptionMarketExpired.into())
+        }
+        Ok(())
+    }
+
+    /// The mint of the tokens locked in the escrow
+    pub fn escrow_mint(&self, option_market: &OptionMarket) -> Pubkey {
+        match self.asset {
+            RfqLegAsset::OptionToken => option_market.option_mint,
+            RfqLegAsset::Collateral => option_market.underlying_asset_mint,
+        }
+    }
+
+    /// The amount of the escrow mint locked for the leg. Collateral includes the mint fee
+    /// taken when the contracts are minted on settlement.
+    pub fn escrow_amount(&self, option_market: &OptionMarket) -> Result<u64> {
+        match self.asset {
+            RfqLegAsset::OptionToken => Ok(self.amount),
+            RfqLegAsset::Collateral => option_market.underlying_amount_per_contract
+                .checked_add(fees::fee_amount(option_market.underlying_amount_per_contract))
+                .and_then(|per_contract| per_contract.checked_mul(self.amount))
+                .ok_or(errors::ErrorCode::NumberOverflow.into()),
+        }
+    }
+}
+
+#[account]
+/// Holds the assets one party delivers for a leg of an RFQ trade until the trade
+/// settles or defaults
+pub struct RfqEscrow {
+    /// The RFQ trade the leg belongs to
+    pub trade: Pubkey,
+    /// The index of the leg within the trade
+    pub leg_index: u8,
+    pub leg: RfqLeg,
+    /// The party that locked the assets. Gets them back if the trade defaults
+    pub depositor: Pubkey,
+    /// The party that receives the leg when the trade settles
+    pub counterparty: Pubkey,
+    /// The signer allowed to settle or revert the leg, i.e. the RFQ program's PDA
+    pub settlement_authority: Pubkey,
+    /// The token account holding the locked assets
+    pub vault: Pubkey,
+    /// Bump seed for the RfqEscrow PDA
+    pub bump_seed: u8,
+}
+
+impl RfqEscrow {
+    pub const SPACE: usize = 8 + 32 + 1 + (32 + 1 + 8) + 32 + 32 + 32 + 32 + 1;
+}
+
+#[derive(Accounts)]
+pub struct ValidateRfqLeg<'info> {
+    pub option_market: Box<Account<'info, OptionMarket>>,
+}
+
+#[derive(Accounts)]
+#[instruction(leg_index: u8)]
+pub struct PrepareRfqSettlement<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub settlement_authority: Signer<'info>,
+    /// CHECK: Only used as a seed with the depositor, so a trade's escrows can't be
+    /// created by anyone else
+    pub trade: AccountInfo<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    pub escrow_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub depositor_src: Box<Account<'info, TokenAccount>>,
+    #[account(init,
+        seeds = [&trade.key().to_bytes()[..], &[leg_index], depositor.key().as_ref(), b"rfqEscrow"],
+        bump,
+        payer = depositor,
+        space = RfqEscrow::SPACE
+    )]
+    pub escrow: Box<Account<'info, RfqEscrow>>,
+    #[account(init,
+        seeds = [&escrow.key().to_bytes()[..], b"rfqEscrowVault"],
+        bump,
+        payer = depositor,
+        token::mint = escrow_mint,
+        token::authority = escrow,
+    )]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> PrepareRfqSettlement<'info> {
+    pub fn accounts(ctx: &Context<PrepareRfqSettlement<'info>>, leg: &RfqLeg) -> Result<()> {
+        leg.validate(&ctx.accounts.option_market)?;
+        if ctx.accounts.escrow_mint.key() != leg.escrow_mint(&ctx.accounts.option_market) {
+            return Err(errors::ErrorCode::RfqEscrowMintDoesNotMatchLeg.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(leg_index: u8)]
+pub struct PrepareRfqSettlementWithMint<'info> {
+    /// The seller, must own the `underlying_asset_src`
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub settlement_authority: Signer<'info>,
+    /// CHECK: Only used as a seed with the depositor, so a trade's escrows can't be
+    /// created by anyone else
+    pub trade: AccountInfo<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut)]
+    pub underlying_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    #[account(init,
+        seeds = [&trade.key().to_bytes()[..], &[leg_index], depositor.key().as_ref(), b"rfqEscrow"],
+        bump,
+        payer = depositor,
+        space = RfqEscrow::SPACE
+    )]
+    pub escrow: Box<Account<'info, RfqEscrow>>,
+    /// Receives the minted OptionTokens until the trade settles
+    #[account(init,
+        seeds = [&escrow.key().to_bytes()[..], b"rfqEscrowVault"],
+        bump,
+        payer = depositor,
+        token::mint = option_mint,
+        token::authority = escrow,
+    )]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Handled
+    #[account(mut)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+impl<'info> PrepareRfqSettlementWithMint<'info> {
+    pub fn accounts(ctx: &Context<PrepareRfqSettlementWithMint<'info>>, leg: &RfqLeg) -> Result<()> {
+        leg.validate(&ctx.accounts.option_market)?;
+        // The minted OptionTokens are escrowed like any other OptionToken leg
+        if leg.asset != RfqLegAsset::OptionToken {
+            return Err(errors::ErrorCode::RfqEscrowMintDoesNotMatchLeg.into())
+        }
+        let option_market = &ctx.accounts.option_market;
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.option_mint.key() != option_market.option_mint {
+            return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.writer_token_mint.key() != option_market.writer_token_mint {
+            return Err(errors::ErrorCode::WriterTokenMintDoesNotMatchMarket.into())
+        }
+        if *ctx.accounts.fee_owner.key != fees::fee_owner_key::ID {
+            return Err(errors::ErrorCode::FeeOwnerDoesNotMatchProgram.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SettleRfqLeg<'info> {
+    pub settlement_authority: Signer<'info>,
+    /// CHECK: Receives the rent of the escrow accounts
+    #[account(mut)]
+    pub depositor: AccountInfo<'info>,
+    #[account(mut,
+        has_one = settlement_authority @ errors::ErrorCode::RfqSettlementAuthorityDoesNotMatch,
+        has_one = depositor @ errors::ErrorCode::RfqDepositorDoesNotMatch,
+        close = depositor
+    )]
+    pub escrow: Box<Account<'info, RfqEscrow>>,
+    #[account(mut, address = escrow.vault)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    #[account(address = escrow.leg.option_market @ errors::ErrorCode::RfqLegDoesNotMatchOptionMarket)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    /// Receives the OptionTokens of the leg
+    #[account(mut)]
+    pub counterparty_option_dest: Box<Account<'info, TokenAccount>>,
+    /// Receives the WriterTokens when the depositor locked collateral
+    #[account(mut)]
+    pub depositor_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for markets too small to take an underlying fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+    /// Pays the SOL mint fee of collateral legs in those markets
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> SettleRfqLeg<'info> {
+    pub fn accounts(ctx: &Context<SettleRfqLeg<'info>>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if ctx.accounts.option_mint.key() != option_market.option_mint {
+            return Err(errors::ErrorCode::OptionTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.writer_token_mint.key() != option_market.writer_token_mint {
+            return Err(errors::ErrorCode::WriterTokenMintDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.underlying_asset_pool.key() != option_market.underlying_asset_pool {
+            return Err(errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket.into())
+        }
+        if ctx.accounts.counterparty_option_dest.owner != ctx.accounts.escrow.counterparty {
+            return Err(errors::ErrorCode::RfqDestinationOwnerDoesNotMatch.into())
+        }
+        if ctx.accounts.depositor_writer_token_dest.owner != ctx.accounts.escrow.depositor {
+            return Err(errors::ErrorCode::RfqDestinationOwnerDoesNotMatch.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevertRfqLeg<'info> {
+    pub settlement_authority: Signer<'info>,
+    /// CHECK: Receives the rent of the escrow accounts
+    #[account(mut)]
+    pub depositor: AccountInfo<'info>,
+    #[account(mut,
+        has_one = settlement_authority @ errors::ErrorCode::RfqSettlementAuthorityDoesNotMatch,
+        has_one = depositor @ errors::ErrorCode::RfqDepositorDoesNotMatch,
+        close = depositor
+    )]
+    pub escrow: Box<Account<'info, RfqEscrow>>,
+    #[account(mut, address = escrow.vault)]
+    pub escrow_vault: Box<Account<'info, TokenAccount>>,
+    /// Receives the locked assets
+    #[account(mut,
+        constraint = depositor_dest.owner == escrow.depositor @ errors::ErrorCode::RfqDestinationOwnerDoesNotMatch
+    )]
+    pub depositor_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, auto_exercise::auto_exercise_closed, errors};
+
+/// Whether the old market's OptionTokens are worthless, so only WriterTokens are burned
+/// to roll out of it
+pub fn roll_from_closed_market(option_market: &OptionMarket, unix_timestamp: i64) -> bool {
+    option_market.knocked_out || option_market.expiration_unix_timestamp < unix_timestamp
+}
+
+#[derive(Accounts)]
+pub struct RollPosition<'info> {
+    /// The owner of the token accounts burned from
+    pub user_authority: Signer<'info>,
+    pub old_option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = old_option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub old_option_mint: Box<Account<'info, Mint>>,
+    /// Not burned from when the old market is expired or knocked out
+    #[account(mut)]
+    pub old_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = old_option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub old_writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub old_writer_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = old_option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub old_underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    pub new_option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = new_option_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub new_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = new_option_market.writer_token_mint @ errors::ErrorCode::WriterTokenMintDoesNotMatchMarket)]
+    pub new_writer_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_writer_token_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = new_option_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub new_underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> RollPosition<'info> {
+    pub fn accounts(ctx: &Context<RollPosition>, size: u64) -> Result<()> {
+        let old_market = &ctx.accounts.old_option_market;
+        let new_market = &ctx.accounts.new_option_market;
+        // The collateral moves pool to pool, so each contract must lock the same amount
+        if old_market.key() == new_market.key()
+            || old_market.underlying_asset_mint != new_market.underlying_asset_mint
+            || old_market.underlying_amount_per_contract != new_market.underlying_amount_per_contract {
+            return Err(errors::ErrorCode::RollMarketsDoNotMatch.into())
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if roll_from_closed_market(old_market, now) {
+            // Like `close_post_expiration`, expired positions can't roll out while
+            // keepers can still auto exercise against the pool
+            if !old_market.knocked_out {
+                auto_exercise_closed(old_market, now)?;
+            }
+            // Validate the underlying kept by net exercises stays in the pool for its writers
+            let available = ctx.accounts.old_underlying_asset_pool.amount
+                .saturating_sub(old_market.net_exercised_underlying);
+            let underlying_amount = old_market.underlying_amount_per_contract
+                .checked_mul(size)
+                .ok_or(errors::ErrorCode::NumberOverflow)?;
+            if available < underlying_amount {
+                return Err(errors::ErrorCode::NotEnoughUnderlyingAssetsInPool.into())
+            }
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<RollPosition>) -> Result<()> {
+        if ctx.accounts.new_option_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
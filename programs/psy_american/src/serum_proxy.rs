@@ -1,16 +1,129 @@
 use anchor_lang::prelude::*;
+use anchor_spl::dex::serum_dex::critbit::{Slab, SlabView};
+use anchor_spl::dex::serum_dex::instruction::NewOrderInstructionV3;
+use anchor_spl::token;
 use psyfi_serum_dex_permissioned::{Context, MarketMiddleware};
 use solana_program::clock::Clock;
+use std::convert::TryInto;
 
-use crate::{OptionMarket, errors};
+use crate::{OptionMarket, admin, errors, oracle, position_limit::PositionLimit, price_band::PriceBand, referral_config::ReferralConfig, trading_allowlist::TradingAllowlist};
+
+/// The fields of a Serum market the middlewares need. They're read from the raw account
+/// data because permissioned markets are stored as `MarketStateV2`, which shares its
+/// prefix with `MarketState`.
+pub struct SerumMarketInfo {
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub event_queue: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+}
+impl SerumMarketInfo {
+    pub fn load(market: &AccountInfo, dex_program_id: &Pubkey) -> Result<Self> {
+        if market.owner != dex_program_id {
+            return Err(ProgramError::IllegalOwner.into())
+        }
+        let data = market.try_borrow_data()?;
+        if data.len() < 365 {
+            return Err(ProgramError::InvalidAccountData.into())
+        }
+        // Offsets include the 5 byte "serum" padding at the start of the account
+        Ok(Self {
+            coin_mint: Pubkey::new(&data[53..85]),
+            pc_mint: Pubkey::new(&data[85..117]),
+            event_queue: Pubkey::new(&data[253..285]),
+            coin_lot_size: u64::from_le_bytes(data[349..357].try_into().unwrap()),
+            pc_lot_size: u64::from_le_bytes(data[357..365].try_into().unwrap()),
+        })
+    }
+
+    /// Convert a limit price in pc lots per coin lot into the quote asset's native
+    /// units per option contract. OptionTokens have 0 decimals, so a native coin unit
+    /// is one contract.
+    pub fn native_price_per_contract(&self, limit_price: u64) -> Result<u64> {
+        let price = (limit_price as u128)
+            .checked_mul(self.pc_lot_size as u128)
+            .ok_or(errors::ErrorCode::NumberOverflow)?
+            / self.coin_lot_size as u128;
+        price.try_into().map_err(|_| errors::ErrorCode::NumberOverflow.into())
+    }
+}
+
+/// Sum the quantity, in coin lots, of the orders an OpenOrders account has resting on
+/// the book. Orders that were filled but not yet cranked are no longer on the book and
+/// are not counted.
+pub fn resting_order_quantity(open_orders: &AccountInfo, bids: &AccountInfo, asks: &AccountInfo) -> Result<u64> {
+    // OpenOrders layout offsets, including the 5 byte "serum" padding
+    const FREE_SLOT_BITS: usize = 109;
+    const IS_BID_BITS: usize = 125;
+    const ORDERS: usize = 141;
+
+    let open_orders_data = open_orders.try_borrow_data()?;
+    if open_orders_data.len() < ORDERS + 16 * 128 {
+        return Err(ProgramError::InvalidAccountData.into())
+    }
+    let free_slot_bits = u128::from_le_bytes(open_orders_data[FREE_SLOT_BITS..IS_BID_BITS].try_into().unwrap());
+    let is_bid_bits = u128::from_le_bytes(open_orders_data[IS_BID_BITS..ORDERS].try_into().unwrap());
+    let mut bids_data = bids.try_borrow_mut_data()?;
+    let mut asks_data = asks.try_borrow_mut_data()?;
+    // Strip the padding and the account flags, the same way the DEX loads the book
+    let bids_len = bids_data.len();
+    let asks_len = asks_data.len();
+    let bids_slab = Slab::new(&mut bids_data[13..bids_len - 7]);
+    let asks_slab = Slab::new(&mut asks_data[13..asks_len - 7]);
+
+    let mut quantity: u64 = 0;
+    for slot in 0..128 {
+        if free_slot_bits & (1u128 << slot) != 0 {
+            continue
+        }
+        let offset = ORDERS + 16 * slot;
+        let order_id = u128::from_le_bytes(open_orders_data[offset..offset + 16].try_into().unwrap());
+        let slab = if is_bid_bits & (1u128 << slot) != 0 { &bids_slab } else { &asks_slab };
+        let leaf_quantity = slab
+            .find_by_key(order_id)
+            .and_then(|handle| slab.get(handle))
+            .and_then(|node| node.as_leaf())
+            .map(|leaf| leaf.quantity())
+            .unwrap_or(0);
+        quantity = quantity.checked_add(leaf_quantity).ok_or(errors::ErrorCode::NumberOverflow)?;
+    }
+    Ok(quantity)
+}
+
+/// Check the account at `config_index` is the config PDA of the account at `seed_index`
+/// and `seed`. Clients always send the config slot, whether or not the config has been
+/// created, so leaving it out can't skip the market's restrictions.
+fn validate_config_account(
+    ctx: &Context,
+    config_index: usize,
+    seed_index: usize,
+    seed: &[u8],
+    mismatch: errors::ErrorCode,
+) -> Result<()> {
+    if ctx.accounts.len() <= config_index.max(seed_index) {
+        return Err(ProgramError::NotEnoughAccountKeys.into())
+    }
+    let (config_key, _bump) = Pubkey::find_program_address(
+        &[ctx.accounts[seed_index].key.as_ref(), seed],
+        ctx.program_id
+    );
+    if *ctx.accounts[config_index].key != config_key {
+        return Err(mismatch.into())
+    }
+    Ok(())
+}
 
 pub struct Validation {
     pub market_auth_bump: u8,
+    /// Set when the prune was requested by the admin instead of after expiry
+    pub admin_prune: bool,
 }
 impl Validation {
     pub fn new() -> Self {
         Self {
-            market_auth_bump: 0
+            market_auth_bump: 0,
+            admin_prune: false,
         }
     }
 }
@@ -19,21 +132,39 @@ impl MarketMiddleware for Validation {
         // Strip the Validation discriminator
         let disc = data[0];
         *data = &data[1..];
-        // 6 is the Prune instruction, strip and set the marketAuthorityBump
-        if disc == 6 {
+        // 6 is the Prune instruction and 7 is the admin Prune instruction, strip and
+        // set the marketAuthorityBump
+        if disc == 6 || disc == 7 {
             self.market_auth_bump = data[0];
             *data = &data[1..];
         }
+        self.admin_prune = disc == 7;
         Ok(())
     }
 
     fn prune(&self, ctx: &mut Context, _limit: &mut u16) -> Result<()> {
-        // Validate that the OptionMarket has expired
         // deserialize the OptionMarket
         let option_market_account = ctx.accounts[0].clone();
         ctx.accounts = (&ctx.accounts[1..]).to_vec();
         let option_market_acct = Account::<OptionMarket>::try_from(&option_market_account)?;
-        if option_market_acct.into_inner().expiration_unix_timestamp >= Clock::get()?.unix_timestamp {
+        if self.admin_prune {
+            // The admin can pull a single OpenOrders account's orders from a live
+            // market, i.e. when the owner's key is compromised
+            let admin_account = ctx.accounts[0].clone();
+            ctx.accounts = (&ctx.accounts[1..]).to_vec();
+            if !admin_account.is_signer || *admin_account.key != admin::admin_key::ID {
+                return Err(errors::ErrorCode::InvalidAdmin.into())
+            }
+            emit!(OpenOrdersPruned {
+                option_market: *option_market_account.key,
+                serum_market: *ctx.accounts[0].key,
+                open_orders: *ctx.accounts[4].key,
+                open_orders_owner: *ctx.accounts[5].key,
+                admin: *admin_account.key,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        } else if option_market_acct.into_inner().expiration_unix_timestamp >= Clock::get()?.unix_timestamp {
+            // Validate that the OptionMarket has expired
             return Err(errors::ErrorCode::CannotPruneActiveMarket.into())
         }
         // Sign with the seeds
@@ -55,6 +186,182 @@ impl MarketMiddleware for Validation {
     }
 }
 
+#[event]
+/// Audit record of the admin pruning an OpenOrders account before expiry
+pub struct OpenOrdersPruned {
+    pub option_market: Pubkey,
+    pub serum_market: Pubkey,
+    pub open_orders: Pubkey,
+    pub open_orders_owner: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Replaces the fixed key check of `ReferralFees` with the ReferralConfig of the market
+pub struct Referral;
+impl Referral {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl MarketMiddleware for Referral {
+    fn settle_funds(&self, ctx: &mut Context) -> Result<()> {
+        // The client prepends the market's ReferralConfig address, strip it
+        validate_config_account(ctx, 0, 1, b"referralConfig", errors::ErrorCode::ReferralConfigDoesNotMatchMarket)?;
+        let referral_config_account = ctx.accounts[0].clone();
+        ctx.accounts = (&ctx.accounts[1..]).to_vec();
+
+        // The owner of the referrer's quote token account
+        let referral = token::accessor::authority(&ctx.accounts[9])?;
+        if referral_config_account.data_is_empty() {
+            // No policy has been set, keep paying the default referral
+            if referral != referral::ID {
+                return Err(errors::ErrorCode::InvalidReferral.into())
+            }
+            return Ok(())
+        }
+        let referral_config = Account::<ReferralConfig>::try_from(&referral_config_account)?;
+        if !referral_config.policy.allows(&referral) {
+            return Err(errors::ErrorCode::InvalidReferral.into())
+        }
+        Ok(())
+    }
+}
+
+/// Rejects orders priced below the option's intrinsic value (less the PriceBand's
+/// tolerance) or above the PriceBand's maximum. Markets without a PriceBand are not
+/// restricted.
+pub struct PriceBands;
+impl PriceBands {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl MarketMiddleware for PriceBands {
+    fn new_order_v3(&self, ctx: &mut Context, ix: &mut NewOrderInstructionV3) -> Result<()> {
+        // The client prepends the OptionMarket, its PriceBand, its OracleConfig and the
+        // oracle account, strip them
+        if ctx.accounts.len() < 4 {
+            return Err(ProgramError::NotEnoughAccountKeys.into())
+        }
+        validate_config_account(ctx, 1, 0, b"priceBand", errors::ErrorCode::PriceBandDoesNotMatchMarket)?;
+        let option_market_account = ctx.accounts[0].clone();
+        let price_band_account = ctx.accounts[1].clone();
+        let oracle_config_account = ctx.accounts[2].clone();
+        let oracle_account = ctx.accounts[3].clone();
+        ctx.accounts = (&ctx.accounts[4..]).to_vec();
+
+        let option_market = Account::<OptionMarket>::try_from(&option_market_account)?;
+        let market_info = SerumMarketInfo::load(&ctx.accounts[0], ctx.dex_program_id)?;
+        if market_info.coin_mint != option_market.option_mint {
+            return Err(errors::ErrorCode::CoinMintIsNotOptionMint.into())
+        }
+        if price_band_account.data_is_empty() {
+            return Ok(())
+        }
+        let price_band = Account::<PriceBand>::try_from(&price_band_account)?;
+        // Intrinsic value is denominated in the quote asset, so it can only be compared
+        // to prices on a Serum market quoted in the same asset
+        if market_info.pc_mint != option_market.quote_asset_mint {
+            return Err(errors::ErrorCode::PcMintIsNotQuoteAsset.into())
+        }
+
+        let price = market_info.native_price_per_contract(ix.limit_price.get())?;
+        if price > price_band.max_price {
+            return Err(errors::ErrorCode::OrderPriceAboveMaximum.into())
+        }
+        let oracle_config = oracle::load_oracle_config(option_market_account.key, &oracle_config_account)?;
+        let oracle_price = oracle_config.load_price(&oracle_account, Clock::get()?.unix_timestamp)?;
+        let intrinsic_value = oracle_price
+            .contract_value(&option_market, &oracle_config)?
+            .saturating_sub(option_market.quote_amount_per_contract);
+        if price < price_band.min_price(intrinsic_value) {
+            return Err(errors::ErrorCode::OrderPriceBelowIntrinsicValue.into())
+        }
+        Ok(())
+    }
+}
+
+/// Rejects orders that would take an owner's resting orders over the OptionMarket's
+/// PositionLimit. Markets without a PositionLimit are not restricted.
+pub struct PositionLimits;
+impl PositionLimits {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl MarketMiddleware for PositionLimits {
+    fn new_order_v3(&self, ctx: &mut Context, ix: &mut NewOrderInstructionV3) -> Result<()> {
+        // The client prepends the OptionMarket and its PositionLimit, strip them
+        validate_config_account(ctx, 1, 0, b"positionLimit", errors::ErrorCode::PositionLimitDoesNotMatchMarket)?;
+        let option_market_account = ctx.accounts[0].clone();
+        let position_limit_account = ctx.accounts[1].clone();
+        ctx.accounts = (&ctx.accounts[2..]).to_vec();
+
+        let option_market = Account::<OptionMarket>::try_from(&option_market_account)?;
+        let market_info = SerumMarketInfo::load(&ctx.accounts[0], ctx.dex_program_id)?;
+        if market_info.coin_mint != option_market.option_mint {
+            return Err(errors::ErrorCode::CoinMintIsNotOptionMint.into())
+        }
+        if position_limit_account.data_is_empty() {
+            return Ok(())
+        }
+        let position_limit = Account::<PositionLimit>::try_from(&position_limit_account)?;
+
+        // new_order accounts: 1 is the OpenOrders, 4 and 5 are the bids and asks
+        let resting = resting_order_quantity(&ctx.accounts[1], &ctx.accounts[4], &ctx.accounts[5])?;
+        let contracts = resting
+            .checked_add(ix.max_coin_qty.get())
+            .and_then(|lots| lots.checked_mul(market_info.coin_lot_size))
+            .ok_or(errors::ErrorCode::NumberOverflow)?;
+        if contracts > position_limit.max_contracts {
+            return Err(errors::ErrorCode::PositionLimitExceeded.into())
+        }
+        Ok(())
+    }
+}
+
+/// Only lets the owners on a Serum market's TradingAllowlist init OpenOrders and place
+/// orders. Markets without a TradingAllowlist are not restricted.
+pub struct TradingAllowlists;
+impl TradingAllowlists {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Strip the prepended TradingAllowlist and check `owner` is on it
+    fn check_owner(ctx: &mut Context, market_index: usize, owner_index: usize) -> Result<()> {
+        validate_config_account(
+            ctx,
+            0,
+            market_index + 1,
+            b"tradingAllowlist",
+            errors::ErrorCode::TradingAllowlistDoesNotMatchMarket
+        )?;
+        let trading_allowlist_account = ctx.accounts[0].clone();
+        ctx.accounts = (&ctx.accounts[1..]).to_vec();
+        if trading_allowlist_account.data_is_empty() {
+            return Ok(())
+        }
+        let trading_allowlist = Account::<TradingAllowlist>::try_from(&trading_allowlist_account)?;
+        if !trading_allowlist.allows(ctx.accounts[owner_index].key) {
+            return Err(errors::ErrorCode::TraderNotAllowlisted.into())
+        }
+        Ok(())
+    }
+}
+impl MarketMiddleware for TradingAllowlists {
+    fn init_open_orders(&self, ctx: &mut Context) -> Result<()> {
+        // init_open_orders accounts: 3 is the owner, 4 is the market
+        Self::check_owner(ctx, 4, 3)
+    }
+
+    fn new_order_v3(&self, ctx: &mut Context, _ix: &mut NewOrderInstructionV3) -> Result<()> {
+        // new_order accounts: 0 is the market, 7 is the owner
+        Self::check_owner(ctx, 0, 7)
+    }
+}
+
 pub mod referral {
     solana_program::declare_id!("6c33US7ErPmLXZog9SyChQUYUrrJY51k4GmzdhrbhNnD");
 }
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{OptionMarket, errors, oracle::{OracleConfig, OracleSource}};
+
+/// How many seconds after expiration the settlement price can be recorded in
+pub const SETTLEMENT_WINDOW: i64 = 15 * 60;
+
+/// How many seconds after expiration the oracle price recorded as the settlement price
+/// can be published. Keepers record the first price published at or after expiration.
+pub const SETTLEMENT_PUBLISH_TOLERANCE: i64 = 15;
+
+/// Check `timestamp` is within the settlement window of the OptionMarket's expiration
+pub fn in_settlement_window(option_market: &OptionMarket, timestamp: i64) -> bool {
+    expiration_in_settlement_window(option_market.expiration_unix_timestamp, timestamp)
+}
+
+/// Check `timestamp` is within the settlement window of `expiration`, which opens at
+/// expiration
+pub fn expiration_in_settlement_window(expiration: i64, timestamp: i64) -> bool {
+    timestamp >= expiration
+        && timestamp <= expiration.saturating_add(SETTLEMENT_WINDOW)
+}
+
+/// Check an oracle price published at `publish_time` can settle a market expiring at
+/// `expiration`
+pub fn is_settlement_publish_time(expiration: i64, publish_time: i64) -> bool {
+    publish_time >= expiration
+        && publish_time <= expiration.saturating_add(SETTLEMENT_PUBLISH_TOLERANCE)
+}
+
+#[derive(Accounts)]
+pub struct RecordSettlementPrice<'info> {
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"oracleConfig"],
+        bump = oracle_config.bump_seed,
+    )]
+    pub oracle_config: Box<Account<'info, OracleConfig>>,
+    /// CHECK: Validated against the OracleConfig when the price is loaded
+    pub oracle: AccountInfo<'info>,
+}
+impl<'info> RecordSettlementPrice<'info> {
+    pub fn unrecorded(ctx: &Context<RecordSettlementPrice>) -> Result<()> {
+        // The settlement price can never be overwritten
+        if ctx.accounts.option_market.settlement_slot != 0 {
+            return Err(errors::ErrorCode::SettlementPriceAlreadyRecorded.into())
+        }
+        // Markets settled from a TWAP can't be settled from their OracleConfig as well
+        if ctx.accounts.option_market.settlement_source != OracleSource::Pyth {
+            return Err(errors::ErrorCode::WrongSettlementSource.into())
+        }
+        if !in_settlement_window(&ctx.accounts.option_market, Clock::get()?.unix_timestamp) {
+            return Err(errors::ErrorCode::OutsideSettlementWindow.into())
+        }
+        Ok(())
+    }
+}
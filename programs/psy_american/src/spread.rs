@@ -0,0 +1,257 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{OptionMarket, errors, exercise_schedule::has_exercise_schedule, fees};
+
+#[account]
+/// A vertical call spread written by `owner`. Long OptionTokens of `long_market` are
+/// escrowed in place of the underlying that normally backs the `short_market` options
+/// minted against them, with quote collateral for the difference in strikes when the
+/// long strike is higher.
+///
+/// The spread's short options are ordinary OptionTokens of the short market, but the
+/// short market's pool holds no underlying for them. Holders exercise them through
+/// `exercise_spread`, which exercises the escrowed long options to deliver the
+/// underlying. Exercises from the pool still work while the pool has underlying.
+pub struct SpreadPosition {
+    pub owner: Pubkey,
+    /// The market whose OptionTokens are escrowed
+    pub long_market: Pubkey,
+    /// The market whose OptionTokens are minted
+    pub short_market: Pubkey,
+    /// Holds the escrowed long OptionTokens
+    pub long_option_vault: Pubkey,
+    /// Holds the quote collateral and the quote left over from exercises
+    pub quote_vault: Pubkey,
+    /// The number of short options minted and not yet exercised through the spread. The
+    /// same number of long OptionTokens are escrowed.
+    pub written: u64,
+    /// Bump seed for the SpreadPosition PDA
+    pub bump_seed: u8,
+}
+
+impl SpreadPosition {
+    pub const SPACE: usize = 8 + 32 * 5 + 8 + 1;
+}
+
+/// The quote that has to be locked per spread contract, on top of the escrowed long
+/// option, so an exercise can always pay the long strike. The short exerciser pays the
+/// short strike, so only a long strike above it has to be collateralized.
+pub fn spread_collateral_per_contract(long_market: &OptionMarket, short_market: &OptionMarket) -> u64 {
+    long_market.quote_amount_per_contract.saturating_sub(short_market.quote_amount_per_contract)
+}
+
+/// Validate the two markets form a vertical call spread whose long leg can always be
+/// exercised to deliver the short leg's underlying
+pub fn validate_spread_markets(long_market: &Account<OptionMarket>, short_market: &Account<OptionMarket>) -> Result<()> {
+    if long_market.key() == short_market.key()
+        || long_market.underlying_asset_mint != short_market.underlying_asset_mint
+        || long_market.quote_asset_mint != short_market.quote_asset_mint
+        || long_market.underlying_amount_per_contract != short_market.underlying_amount_per_contract
+        || long_market.expiration_unix_timestamp != short_market.expiration_unix_timestamp {
+        return Err(errors::ErrorCode::SpreadMarketsDoNotMatch.into())
+    }
+    // The long leg must be exercisable whenever the short leg is
+    if has_exercise_schedule(long_market) || long_market.knocked_out {
+        return Err(errors::ErrorCode::SpreadLongMarketNotExercisable.into())
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitSpreadPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub long_market: Box<Account<'info, OptionMarket>>,
+    pub short_market: Box<Account<'info, OptionMarket>>,
+    #[account(address = long_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub long_option_mint: Box<Account<'info, Mint>>,
+    #[account(address = long_market.quote_asset_mint @ errors::ErrorCode::QuoteAssetMintDoesNotMatchMarket)]
+    pub quote_asset_mint: Box<Account<'info, Mint>>,
+    #[account(init,
+        seeds = [
+            &owner.key().to_bytes()[..],
+            &long_market.key().to_bytes()[..],
+            &short_market.key().to_bytes()[..],
+            b"spreadPosition"
+        ],
+        bump,
+        payer = owner,
+        space = SpreadPosition::SPACE
+    )]
+    pub spread_position: Box<Account<'info, SpreadPosition>>,
+    #[account(init,
+        seeds = [&spread_position.key().to_bytes()[..], b"longOptionVault"],
+        bump,
+        payer = owner,
+        token::mint = long_option_mint,
+        token::authority = spread_position,
+    )]
+    pub long_option_vault: Box<Account<'info, TokenAccount>>,
+    #[account(init,
+        seeds = [&spread_position.key().to_bytes()[..], b"quoteVault"],
+        bump,
+        payer = owner,
+        token::mint = quote_asset_mint,
+        token::authority = spread_position,
+    )]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct OpenSpread<'info> {
+    /// Owns the long OptionTokens, quote and underlying for the mint fee, and pays the
+    /// SOL mint fee of small markets
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut,
+        has_one = owner @ errors::ErrorCode::SpreadOwnerDoesNotMatch,
+        has_one = long_market @ errors::ErrorCode::SpreadMarketsDoNotMatch,
+        has_one = short_market @ errors::ErrorCode::SpreadMarketsDoNotMatch,
+        has_one = long_option_vault,
+        has_one = quote_vault,
+    )]
+    pub spread_position: Box<Account<'info, SpreadPosition>>,
+    pub long_market: Box<Account<'info, OptionMarket>>,
+    pub short_market: Box<Account<'info, OptionMarket>>,
+    /// Knock outs would leave the escrowed options unexercisable. Barriers can only be
+    /// added before any options are minted, so an empty one stays empty while the
+    /// spread holds long options.
+    /// CHECK: Only checked to be uninitialized
+    #[account(
+        seeds = [&long_market.key().to_bytes()[..], b"knockOutBarrier"],
+        bump,
+    )]
+    pub long_knock_out_barrier: AccountInfo<'info>,
+    #[account(mut)]
+    pub long_option_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub long_option_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+    /// Pays the short market's mint fee
+    #[account(mut)]
+    pub underlying_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = short_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub short_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub minted_option_dest: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Receives the SOL fee for markets too small to take an underlying fee from
+    #[account(mut, address = fees::fee_owner_key::ID @ errors::ErrorCode::FeeOwnerDoesNotMatchProgram)]
+    pub fee_owner: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+impl<'info> OpenSpread<'info> {
+    pub fn accounts(ctx: &Context<OpenSpread>) -> Result<()> {
+        if !ctx.accounts.long_knock_out_barrier.data_is_empty() {
+            return Err(errors::ErrorCode::SpreadLongMarketNotExercisable.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<OpenSpread>) -> Result<()> {
+        if ctx.accounts.short_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantMint.into())
+        }
+        Ok(())
+    }
+}
+
+/// Exercises short options of a SpreadPosition by exercising its escrowed long options.
+/// The exerciser pays the short strike into the quote vault, which pays the long strike.
+#[derive(Accounts)]
+pub struct ExerciseSpread<'info> {
+    /// Owns the short OptionTokens and quote
+    pub user_authority: Signer<'info>,
+    #[account(mut,
+        has_one = long_market @ errors::ErrorCode::SpreadMarketsDoNotMatch,
+        has_one = short_market @ errors::ErrorCode::SpreadMarketsDoNotMatch,
+        has_one = long_option_vault,
+        has_one = quote_vault,
+    )]
+    pub spread_position: Box<Account<'info, SpreadPosition>>,
+    pub long_market: Box<Account<'info, OptionMarket>>,
+    pub short_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut, address = short_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub short_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub exerciser_option_token_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_src: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = long_market.option_mint @ errors::ErrorCode::OptionTokenMintDoesNotMatchMarket)]
+    pub long_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub long_option_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = long_market.quote_asset_pool @ errors::ErrorCode::QuotePoolAccountDoesNotMatchMarket)]
+    pub long_quote_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = long_market.underlying_asset_pool @ errors::ErrorCode::UnderlyingPoolAccountDoesNotMatchMarket)]
+    pub long_underlying_asset_pool: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub underlying_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> ExerciseSpread<'info> {
+    pub fn accounts(ctx: &Context<ExerciseSpread>, size: u64) -> Result<()> {
+        if size > ctx.accounts.spread_position.written {
+            return Err(errors::ErrorCode::SpreadSizeExceeded.into())
+        }
+        if ctx.accounts.underlying_asset_dest.mint != ctx.accounts.short_market.underlying_asset_mint {
+            return Err(errors::ErrorCode::UnderlyingDestMintDoesNotMatchUnderlyingAsset.into())
+        }
+        Ok(())
+    }
+    pub fn unexpired_market(ctx: &Context<ExerciseSpread>) -> Result<()> {
+        if ctx.accounts.short_market.expiration_unix_timestamp < Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketExpiredCantExercise.into())
+        }
+        Ok(())
+    }
+}
+
+/// Returns the escrowed long OptionTokens and the quote vault to the owner once the
+/// short options can no longer be exercised, and closes the SpreadPosition
+#[derive(Accounts)]
+pub struct CloseSpread<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut,
+        has_one = owner @ errors::ErrorCode::SpreadOwnerDoesNotMatch,
+        has_one = short_market @ errors::ErrorCode::SpreadMarketsDoNotMatch,
+        has_one = long_option_vault,
+        has_one = quote_vault,
+        close = owner
+    )]
+    pub spread_position: Box<Account<'info, SpreadPosition>>,
+    pub short_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut)]
+    pub long_option_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub long_option_dest: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_asset_dest: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+impl<'info> CloseSpread<'info> {
+    /// Short options can't be exercised after expiration, or at all once knocked out
+    pub fn expired_market(ctx: &Context<CloseSpread>) -> Result<()> {
+        let short_market = &ctx.accounts.short_market;
+        if !short_market.knocked_out
+            && short_market.expiration_unix_timestamp >= Clock::get()?.unix_timestamp {
+            return Err(errors::ErrorCode::OptionMarketNotExpiredCantClose.into())
+        }
+        Ok(())
+    }
+}
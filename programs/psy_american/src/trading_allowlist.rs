@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{OptionMarket, admin, errors};
+
+/// The maximum number of wallets a TradingAllowlist can hold
+pub const MAX_ALLOWLISTED_TRADERS: usize = 100;
+
+#[account]
+/// The wallets allowed to open orders on, and trade, a permissioned PsyOptions Serum
+/// market. Markets without a TradingAllowlist can be traded by anyone.
+pub struct TradingAllowlist {
+    /// The Serum market the allowlist applies to
+    pub serum_market: Pubkey,
+    /// The owners allowed to init OpenOrders and place orders
+    pub traders: Vec<Pubkey>,
+    /// Bump seed for the TradingAllowlist PDA
+    pub bump_seed: u8,
+}
+
+impl TradingAllowlist {
+    pub const SPACE: usize = 8 + 32 + (4 + 32 * MAX_ALLOWLISTED_TRADERS) + 1;
+
+    pub fn allows(&self, trader: &Pubkey) -> bool {
+        self.traders.contains(trader)
+    }
+
+    pub fn add(&mut self, trader: Pubkey) -> Result<()> {
+        if self.allows(&trader) {
+            return Err(errors::ErrorCode::TraderAlreadyAllowlisted.into())
+        }
+        if self.traders.len() >= MAX_ALLOWLISTED_TRADERS {
+            return Err(errors::ErrorCode::TradingAllowlistFull.into())
+        }
+        self.traders.push(trader);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, trader: &Pubkey) -> Result<()> {
+        let index = self.traders
+            .iter()
+            .position(|key| key == trader)
+            .ok_or(errors::ErrorCode::TraderNotAllowlisted)?;
+        self.traders.swap_remove(index);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitTradingAllowlist<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    pub pc_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Only used as a seed, the seeds constraint validates it belongs to the OptionMarket
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], &pc_mint.key().to_bytes()[..], b"serumMarket"],
+        bump,
+    )]
+    pub serum_market: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&serum_market.key().to_bytes()[..], b"tradingAllowlist"],
+        bump,
+        payer = admin,
+        space = TradingAllowlist::SPACE
+    )]
+    pub trading_allowlist: Box<Account<'info, TradingAllowlist>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTradingAllowlist<'info> {
+    #[account(address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub trading_allowlist: Box<Account<'info, TradingAllowlist>>,
+}
@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+use std::convert::{TryFrom, TryInto};
+
+use crate::{OptionMarket, admin, errors, oracle::OracleSource, serum_proxy::SerumMarketInfo, settlement::in_settlement_window};
+
+/// The most samples a TwapAccumulator keeps. Once full the oldest sample is dropped.
+pub const MAX_TWAP_SAMPLES: usize = 128;
+/// The longest TWAP window, in minutes, an accumulator can be configured with
+pub const MAX_TWAP_WINDOW_MINUTES: u16 = 24 * 60;
+
+// Serum EventQueue layout, offsets include the 5 byte "serum" padding
+const EVENT_QUEUE_HEADER_END: usize = 37;
+const EVENT_SIZE: usize = 88;
+const EVENT_FLAG_FILL: u8 = 0x1;
+const EVENT_FLAG_BID: u8 = 0x4;
+const EVENT_FLAG_MAKER: u8 = 0x8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct TwapSample {
+    /// When the crank observed the price
+    pub timestamp: i64,
+    /// The value of `underlying_amount_per_contract` in the quote asset's native units
+    pub price: u64,
+}
+
+#[account]
+/// Samples the trade price of an OptionMarket's underlying on its own Serum market so a
+/// settlement price can be recorded without an oracle feed
+pub struct TwapAccumulator {
+    pub option_market: Pubkey,
+    /// The Serum market trading the underlying asset against the quote asset
+    pub serum_market: Pubkey,
+    /// The Serum market's event queue the crank reads fills from
+    pub event_queue: Pubkey,
+    /// The TWAP is taken over this many minutes before expiration
+    pub window_minutes: u16,
+    /// The event queue sequence number the crank has read up to
+    pub last_seq_num: u64,
+    /// Samples in the order they were observed, at most one per bucket
+    pub samples: Vec<TwapSample>,
+    /// Bump seed for the TwapAccumulator PDA
+    pub bump_seed: u8,
+}
+
+impl TwapAccumulator {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 2 + 8 + (4 + MAX_TWAP_SAMPLES * 16) + 1;
+
+    pub fn window_start(&self, option_market: &OptionMarket) -> i64 {
+        option_market.expiration_unix_timestamp
+            .saturating_sub(self.window_minutes as i64 * 60)
+    }
+
+    /// The window is split into buckets of this many seconds so one sample per bucket
+    /// covers the whole window without dropping any
+    pub fn bucket_seconds(&self) -> i64 {
+        let window_seconds = self.window_minutes as i64 * 60;
+        let buckets = MAX_TWAP_SAMPLES as i64 - 1;
+        ((window_seconds + buckets - 1) / buckets).max(1)
+    }
+
+    /// Check the bucket `timestamp` falls in hasn't been sampled. Cranking again within a
+    /// bucket can't add samples, so self trades can't crowd the window out.
+    pub fn bucket_is_open(&self, option_market: &OptionMarket, timestamp: i64) -> bool {
+        let window_start = self.window_start(option_market);
+        let bucket = |timestamp: i64| (timestamp - window_start).div_euclid(self.bucket_seconds());
+        self.samples.last().map_or(true, |sample| bucket(sample.timestamp) < bucket(timestamp))
+    }
+
+    pub fn push(&mut self, sample: TwapSample) {
+        if self.samples.len() >= MAX_TWAP_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push(sample);
+    }
+
+    /// The time weighted average of the samples between `start` and `end`. Each sample
+    /// holds until the next one is observed. Returns None when no sample covers any of
+    /// the period.
+    pub fn twap(&self, start: i64, end: i64) -> Option<u64> {
+        let mut weighted_sum: u128 = 0;
+        let mut duration: u128 = 0;
+        for (i, sample) in self.samples.iter().enumerate() {
+            let from = sample.timestamp.max(start);
+            let to = self.samples.get(i + 1)
+                .map_or(end, |next| next.timestamp)
+                .min(end);
+            if to <= from {
+                continue
+            }
+            weighted_sum += sample.price as u128 * (to - from) as u128;
+            duration += (to - from) as u128;
+        }
+        if duration == 0 {
+            return None
+        }
+        u64::try_from(weighted_sum / duration).ok()
+    }
+}
+
+/// The sequence number the next event pushed to a Serum event queue will get
+pub fn event_queue_seq_num(event_queue: &AccountInfo) -> Result<u64> {
+    let data = event_queue.try_borrow_data()?;
+    if data.len() < EVENT_QUEUE_HEADER_END + EVENT_SIZE + 7 {
+        return Err(ProgramError::InvalidAccountData.into())
+    }
+    Ok(u64::from_le_bytes(data[29..37].try_into().unwrap()))
+}
+
+/// Find the price of the most recent maker fill in a Serum event queue with a sequence
+/// number of at least `from_seq_num`. Maker fills are used because their quote amounts
+/// only differ from the traded price by the rebate. Returns the fill's native pc and coin
+/// quantities along with the queue's next sequence number.
+pub fn latest_maker_fill(event_queue: &AccountInfo, from_seq_num: u64) -> Result<(Option<(u64, u64)>, u64)> {
+    let data = event_queue.try_borrow_data()?;
+    if data.len() < EVENT_QUEUE_HEADER_END + EVENT_SIZE + 7 {
+        return Err(ProgramError::InvalidAccountData.into())
+    }
+    let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let head = read_u64(13);
+    let count = read_u64(21);
+    let seq_num = read_u64(29);
+    let capacity = ((data.len() - EVENT_QUEUE_HEADER_END - 7) / EVENT_SIZE) as u64;
+
+    let mut latest = None;
+    for i in 0..count {
+        // Events that were already read by a previous crank are skipped
+        if seq_num.wrapping_sub(count).wrapping_add(i) < from_seq_num {
+            continue
+        }
+        let offset = EVENT_QUEUE_HEADER_END + ((head + i) % capacity) as usize * EVENT_SIZE;
+        let flags = data[offset];
+        if flags & EVENT_FLAG_FILL == 0 || flags & EVENT_FLAG_MAKER == 0 {
+            continue
+        }
+        let native_qty_released = read_u64(offset + 8);
+        let native_qty_paid = read_u64(offset + 16);
+        let native_fee_or_rebate = read_u64(offset + 24);
+        let (native_pc, native_coin) = if flags & EVENT_FLAG_BID != 0 {
+            (native_qty_paid.saturating_add(native_fee_or_rebate), native_qty_released)
+        } else {
+            (native_qty_released.saturating_sub(native_fee_or_rebate), native_qty_paid)
+        };
+        if native_coin > 0 {
+            latest = Some((native_pc, native_coin));
+        }
+    }
+    Ok((latest, seq_num))
+}
+
+#[derive(Accounts)]
+pub struct InitTwapAccumulator<'info> {
+    #[account(mut, address = admin::admin_key::ID @ errors::ErrorCode::InvalidAdmin)]
+    pub admin: Signer<'info>,
+    /// Switched to settle from the TWAP instead of its OracleConfig
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    /// CHECK: Parsed and checked against the OptionMarket's assets
+    pub serum_market: AccountInfo<'info>,
+    /// CHECK: Checked against the Serum market, sampling starts from its current sequence number
+    pub event_queue: AccountInfo<'info>,
+    #[account(init,
+        seeds = [&option_market.key().to_bytes()[..], b"twapAccumulator"],
+        bump,
+        payer = admin,
+        space = TwapAccumulator::SPACE
+    )]
+    pub twap_accumulator: Box<Account<'info, TwapAccumulator>>,
+
+    pub system_program: Program<'info, System>,
+}
+impl<'info> InitTwapAccumulator<'info> {
+    pub fn accounts(ctx: &Context<InitTwapAccumulator<'info>>, window_minutes: u16) -> Result<()> {
+        if window_minutes == 0 || window_minutes > MAX_TWAP_WINDOW_MINUTES {
+            return Err(errors::ErrorCode::InvalidTwapWindow.into())
+        }
+        let market_info = SerumMarketInfo::load(&ctx.accounts.serum_market, &anchor_spl::dex::ID)?;
+        let option_market = &ctx.accounts.option_market;
+        if market_info.coin_mint != option_market.underlying_asset_mint
+            || market_info.pc_mint != option_market.quote_asset_mint {
+            return Err(errors::ErrorCode::TwapSerumMarketDoesNotMatchAssets.into())
+        }
+        if ctx.accounts.event_queue.key() != market_info.event_queue {
+            return Err(errors::ErrorCode::TwapEventQueueDoesNotMatch.into())
+        }
+        if option_market.settlement_slot != 0 {
+            return Err(errors::ErrorCode::SettlementPriceAlreadyRecorded.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CrankTwap<'info> {
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(mut,
+        seeds = [&option_market.key().to_bytes()[..], b"twapAccumulator"],
+        bump = twap_accumulator.bump_seed,
+        has_one = event_queue @ errors::ErrorCode::TwapEventQueueDoesNotMatch,
+    )]
+    pub twap_accumulator: Box<Account<'info, TwapAccumulator>>,
+    /// CHECK: Checked against the TwapAccumulator and parsed as a Serum event queue
+    pub event_queue: AccountInfo<'info>,
+}
+impl<'info> CrankTwap<'info> {
+    pub fn in_window(ctx: &Context<CrankTwap>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let option_market = &ctx.accounts.option_market;
+        if now < ctx.accounts.twap_accumulator.window_start(option_market)
+            || now > option_market.expiration_unix_timestamp {
+            return Err(errors::ErrorCode::OutsideTwapWindow.into())
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordTwapSettlementPrice<'info> {
+    #[account(mut)]
+    pub option_market: Box<Account<'info, OptionMarket>>,
+    #[account(
+        seeds = [&option_market.key().to_bytes()[..], b"twapAccumulator"],
+        bump = twap_accumulator.bump_seed,
+    )]
+    pub twap_accumulator: Box<Account<'info, TwapAccumulator>>,
+}
+impl<'info> RecordTwapSettlementPrice<'info> {
+    pub fn unrecorded(ctx: &Context<RecordTwapSettlementPrice>) -> Result<()> {
+        let option_market = &ctx.accounts.option_market;
+        if option_market.settlement_slot != 0 {
+            return Err(errors::ErrorCode::SettlementPriceAlreadyRecorded.into())
+        }
+        if option_market.settlement_source != OracleSource::SerumTwap {
+            return Err(errors::ErrorCode::WrongSettlementSource.into())
+        }
+        // The whole window must have elapsed before the TWAP can be taken
+        if !in_settlement_window(option_market, Clock::get()?.unix_timestamp) {
+            return Err(errors::ErrorCode::OutsideSettlementWindow.into())
+        }
+        Ok(())
+    }
+}
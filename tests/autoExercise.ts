@@ -0,0 +1,289 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  closePostExpiration,
+  createExerciser,
+  createMinter,
+  initOptionMarket,
+  initSetup,
+  wait,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { mintOptionsTx } from "../packages/psyoptions-ts/src";
+import {
+  createPythPrice,
+  initPythOracleConfig,
+  loadAdmin,
+  setPythPrice,
+} from "../utils/oracle";
+import { MockPyth } from "../target/types/mock_pyth";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("autoExercise", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+
+  const holder = anchor.web3.Keypair.generate();
+  let quoteToken: Token;
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        holder.publicKey,
+        10_000_000_000
+      ),
+      "confirmed"
+    );
+    let remainingAccounts, instructions;
+    ({ quoteToken, optionMarket, remainingAccounts, instructions } =
+      await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  describe("before expiration", () => {
+    it("should error", async () => {
+      const { optionAccount, quoteAccount, underlyingAccount } =
+        await createExerciser(
+          provider.connection,
+          holder,
+          mintAuthority,
+          quoteToken,
+          0,
+          optionMarket.optionMint,
+          optionMarket.underlyingAssetMint
+        );
+      const [exerciseAuthority] = await PublicKey.findProgramAddress(
+        [
+          optionMarket.key.toBuffer(),
+          new TextEncoder().encode("exerciseAuthority"),
+        ],
+        program.programId
+      );
+      try {
+        await program.rpc.autoExercise(new anchor.BN(1), {
+          accounts: {
+            keeper: wallet.publicKey,
+            optionMarket: optionMarket.key,
+            exerciseAuthority,
+            optionMint: optionMarket.optionMint,
+            exerciserOptionTokenSrc: optionAccount.publicKey,
+            underlyingAssetPool: optionMarket.underlyingAssetPool,
+            underlyingAssetDest: underlyingAccount.publicKey,
+            quoteAssetPool: optionMarket.quoteAssetPool,
+            quoteAssetSrc: quoteAccount.publicKey,
+            keeperUnderlyingDest: underlyingAccount.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "Auto exercise is only allowed in the window after expiration";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("after expiration in the money", () => {
+    const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
+    const size = new anchor.BN(2);
+    const now = () => new anchor.BN(Math.floor(Date.now() / 1000));
+
+    let market: OptionMarketV2;
+    let underlyingToken: Token;
+    let exerciseAuthority: PublicKey;
+    let holderOptionAcct: Keypair;
+    let holderQuoteAcct: Keypair;
+    let holderUnderlyingAcct: Keypair;
+    let holderWriterAcct: Keypair;
+    let keeperUnderlyingDest: PublicKey;
+
+    const balance = async (account: PublicKey) =>
+      new anchor.BN(
+        (await provider.connection.getTokenAccountBalance(account)).value.amount
+      );
+
+    before(async () => {
+      const admin = await loadAdmin(provider);
+      let marketQuoteToken: Token, remainingAccounts, instructions;
+      ({
+        optionMarket: market,
+        underlyingToken,
+        quoteToken: marketQuoteToken,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program, {
+        expiration: now().addn(4),
+      }));
+      await initOptionMarket(
+        program,
+        payer,
+        market,
+        remainingAccounts,
+        instructions
+      );
+      // The holder writes the options and keeps the quote to exercise them
+      ({
+        optionAccount: holderOptionAcct,
+        quoteAccount: holderQuoteAcct,
+        underlyingAccount: holderUnderlyingAcct,
+        writerTokenAccount: holderWriterAcct,
+      } = await createMinter(
+        provider.connection,
+        holder,
+        mintAuthority,
+        underlyingToken,
+        size.muln(2).mul(market.underlyingAmountPerContract).toNumber(),
+        market.optionMint,
+        market.writerTokenMint,
+        marketQuoteToken,
+        size.mul(market.quoteAmountPerContract).toNumber()
+      ));
+      await mintOptionsTx(
+        program,
+        holder,
+        holderOptionAcct,
+        holderWriterAcct,
+        holderUnderlyingAcct,
+        size,
+        market
+      );
+      // Opt in by delegating the OptionTokens and quote to the exercise authority
+      [exerciseAuthority] = await PublicKey.findProgramAddress(
+        [market.key.toBuffer(), new TextEncoder().encode("exerciseAuthority")],
+        program.programId
+      );
+      await new Token(
+        provider.connection,
+        market.optionMint,
+        TOKEN_PROGRAM_ID,
+        payer
+      ).approve(
+        holderOptionAcct.publicKey,
+        exerciseAuthority,
+        holder,
+        [],
+        size.toNumber()
+      );
+      await marketQuoteToken.approve(
+        holderQuoteAcct.publicKey,
+        exerciseAuthority,
+        holder,
+        [],
+        size.mul(market.quoteAmountPerContract).toNumber()
+      );
+      keeperUnderlyingDest = await underlyingToken.createAccount(
+        wallet.publicKey
+      );
+
+      // The strike is 5 quote per underlying, the market settles at 6
+      const oracle = await createPythPrice(mockPyth, payer, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: now(),
+      });
+      const oracleConfig = await initPythOracleConfig(
+        program,
+        admin,
+        market,
+        oracle
+      );
+      // Wait so the market is expired
+      await wait(6000);
+      await setPythPrice(mockPyth, oracle, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: market.expirationUnixTimestamp,
+      });
+      await program.rpc.recordSettlementPrice({
+        accounts: {
+          optionMarket: market.key,
+          oracleConfig,
+          oracle,
+        },
+      });
+    });
+
+    it("should exercise the delegated options and pay the keeper", async () => {
+      const holderUnderlyingBefore = await balance(
+        holderUnderlyingAcct.publicKey
+      );
+      await program.rpc.autoExercise(size, {
+        accounts: {
+          keeper: wallet.publicKey,
+          optionMarket: market.key,
+          exerciseAuthority,
+          optionMint: market.optionMint,
+          exerciserOptionTokenSrc: holderOptionAcct.publicKey,
+          underlyingAssetPool: market.underlyingAssetPool,
+          underlyingAssetDest: holderUnderlyingAcct.publicKey,
+          quoteAssetPool: market.quoteAssetPool,
+          quoteAssetSrc: holderQuoteAcct.publicKey,
+          keeperUnderlyingDest,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      // Keepers earn 5bps of the underlying delivered
+      const underlyingAmount = size.mul(market.underlyingAmountPerContract);
+      const keeperFee = underlyingAmount.muln(5).divn(10_000);
+      assert.equal(
+        (await balance(holderOptionAcct.publicKey)).toString(),
+        "0"
+      );
+      assert.equal((await balance(holderQuoteAcct.publicKey)).toString(), "0");
+      assert.equal(
+        (await balance(market.quoteAssetPool)).toString(),
+        size.mul(market.quoteAmountPerContract).toString()
+      );
+      assert.equal(
+        (await balance(holderUnderlyingAcct.publicKey))
+          .sub(holderUnderlyingBefore)
+          .toString(),
+        underlyingAmount.sub(keeperFee).toString()
+      );
+      assert.equal(
+        (await balance(keeperUnderlyingDest)).toString(),
+        keeperFee.toString()
+      );
+    });
+
+    it("should keep writers from closing until the window closes", async () => {
+      try {
+        await closePostExpiration(
+          program,
+          holder,
+          size,
+          market.key,
+          market.writerTokenMint,
+          holderWriterAcct.publicKey,
+          market.underlyingAssetPool,
+          holderUnderlyingAcct.publicKey
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "Writers can't close until the options settle out of the money or the auto exercise window closes";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
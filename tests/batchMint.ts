@@ -0,0 +1,134 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { AccountMeta, Keypair, SystemProgram } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import {
+  feeAmountPerContract,
+  FEE_OWNER_KEY,
+} from "../packages/psyoptions-ts/src/fees";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("batchMint", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const minter = anchor.web3.Keypair.generate();
+
+  let optionMarket: OptionMarketV2;
+  let minterAccounts: {
+    optionAccount: Keypair;
+    underlyingAccount: Keypair;
+    writerTokenAccount: Keypair;
+  };
+
+  const legAccounts = (market: OptionMarketV2): AccountMeta[] =>
+    [
+      market.key,
+      market.optionMint,
+      minterAccounts.optionAccount.publicKey,
+      market.writerTokenMint,
+      minterAccounts.writerTokenAccount.publicKey,
+      market.underlyingAssetPool,
+      market.mintFeeAccount,
+    ].map((pubkey, i) => ({ pubkey, isWritable: i > 0, isSigner: false }));
+
+  const batchMint = (
+    legs: { optionMarket: anchor.web3.PublicKey; size: anchor.BN }[],
+    remainingAccounts: AccountMeta[]
+  ) =>
+    program.rpc.batchMint(legs, {
+      accounts: {
+        userAuthority: minter.publicKey,
+        underlyingAssetSrc: minterAccounts.underlyingAccount.publicKey,
+        feeOwner: FEE_OWNER_KEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      remainingAccounts,
+      signers: [minter],
+    });
+
+  before(async () => {
+    await Promise.all(
+      [payer, minter].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    const {
+      quoteToken,
+      underlyingToken,
+      optionMarket: market,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program);
+    optionMarket = market;
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    minterAccounts = await createMinter(
+      provider.connection,
+      minter,
+      mintAuthority,
+      underlyingToken,
+      optionMarket.underlyingAmountPerContract
+        .add(feeAmountPerContract(optionMarket.underlyingAmountPerContract))
+        .muln(2)
+        .toNumber(),
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    );
+  });
+
+  it("should mint every leg from one source account", async () => {
+    await batchMint(
+      [
+        { optionMarket: optionMarket.key, size: new anchor.BN(1) },
+        { optionMarket: optionMarket.key, size: new anchor.BN(1) },
+      ],
+      [...legAccounts(optionMarket), ...legAccounts(optionMarket)]
+    );
+    const options = await provider.connection.getTokenAccountBalance(
+      minterAccounts.optionAccount.publicKey
+    );
+    assert.equal(options.value.amount, "2");
+    const writerTokens = await provider.connection.getTokenAccountBalance(
+      minterAccounts.writerTokenAccount.publicKey
+    );
+    assert.equal(writerTokens.value.amount, "2");
+    const underlying = await provider.connection.getTokenAccountBalance(
+      minterAccounts.underlyingAccount.publicKey
+    );
+    assert.equal(underlying.value.amount, "0");
+  });
+
+  it("should error when the remaining accounts don't match the legs", async () => {
+    try {
+      await batchMint(
+        [
+          { optionMarket: optionMarket.key, size: new anchor.BN(1) },
+          { optionMarket: optionMarket.key, size: new anchor.BN(1) },
+        ],
+        legAccounts(optionMarket)
+      );
+      assert.ok(false);
+    } catch (err) {
+      const errMsg = "Remaining accounts do not match the batch mint legs";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
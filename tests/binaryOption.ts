@@ -0,0 +1,286 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createUnderlyingAndQuoteMints, wait } from "../utils/helpers";
+import { createPythPrice, setPythPrice } from "../utils/oracle";
+import { MockPyth } from "../target/types/mock_pyth";
+import { PsyAmerican } from "../target/types/psy_american";
+
+const deriveBinaryMarket = async (
+  programId: PublicKey,
+  underlyingMint: PublicKey,
+  quoteMint: PublicKey,
+  underlyingAmountPerContract: anchor.BN,
+  strike: anchor.BN,
+  payoutPerContract: anchor.BN,
+  expiration: anchor.BN,
+  payoutCondition: number,
+  oracle: PublicKey
+) => {
+  const textEncoder = new TextEncoder();
+  const [binaryMarket] = await PublicKey.findProgramAddress(
+    [
+      underlyingMint.toBuffer(),
+      quoteMint.toBuffer(),
+      underlyingAmountPerContract.toArrayLike(Buffer, "le", 8),
+      strike.toArrayLike(Buffer, "le", 8),
+      payoutPerContract.toArrayLike(Buffer, "le", 8),
+      expiration.toArrayLike(Buffer, "le", 8),
+      Buffer.from([payoutCondition]),
+      oracle.toBuffer(),
+      textEncoder.encode("binaryMarket"),
+    ],
+    programId
+  );
+  const [optionMint] = await PublicKey.findProgramAddress(
+    [binaryMarket.toBuffer(), textEncoder.encode("optionToken")],
+    programId
+  );
+  const [writerTokenMint] = await PublicKey.findProgramAddress(
+    [binaryMarket.toBuffer(), textEncoder.encode("writerToken")],
+    programId
+  );
+  const [quoteAssetPool] = await PublicKey.findProgramAddress(
+    [binaryMarket.toBuffer(), textEncoder.encode("quoteAssetPool")],
+    programId
+  );
+  return { binaryMarket, optionMint, writerTokenMint, quoteAssetPool };
+};
+
+describe("Binary options", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let underlyingToken: Token;
+  let quoteToken: Token;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    ({ underlyingToken, quoteToken } = await createUnderlyingAndQuoteMints(
+      provider,
+      payer,
+      mintAuthority
+    ));
+  });
+
+  describe("initializeBinaryMarket", () => {
+    it("should error when the oracle is not a price account", async () => {
+      const underlyingAmountPerContract = new anchor.BN(1_000_000_000);
+      const strike = new anchor.BN(100_000_000);
+      const payoutPerContract = new anchor.BN(10_000_000);
+      const expiration = new anchor.BN(new Date().getTime() / 1000 + 3600);
+      const oracle = SystemProgram.programId;
+      const { binaryMarket, optionMint, writerTokenMint, quoteAssetPool } =
+        await deriveBinaryMarket(
+          program.programId,
+          underlyingToken.publicKey,
+          quoteToken.publicKey,
+          underlyingAmountPerContract,
+          strike,
+          payoutPerContract,
+          expiration,
+          0,
+          oracle
+        );
+      try {
+        await program.rpc.initializeBinaryMarket(
+          underlyingAmountPerContract,
+          strike,
+          payoutPerContract,
+          expiration,
+          { above: {} },
+          { pyth: {} },
+          {
+            accounts: {
+              authority: payer.publicKey,
+              underlyingAssetMint: underlyingToken.publicKey,
+              quoteAssetMint: quoteToken.publicKey,
+              oracle,
+              optionMint,
+              writerTokenMint,
+              quoteAssetPool,
+              binaryMarket,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              rent: SYSVAR_RENT_PUBKEY,
+              systemProgram: SystemProgram.programId,
+            },
+            signers: [payer],
+          }
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Oracle account is not a valid price account";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("settled above the strike", () => {
+    const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
+    const now = () => new anchor.BN(Math.floor(Date.now() / 1000));
+    // The test mints have 0 decimals, the strike is 5 quote per underlying
+    const underlyingAmountPerContract = new anchor.BN(1);
+    const strike = new anchor.BN(5);
+    const payoutPerContract = new anchor.BN(100);
+    const size = new anchor.BN(2);
+
+    let expiration: anchor.BN;
+    let oracle: PublicKey;
+    let market: {
+      binaryMarket: PublicKey;
+      optionMint: PublicKey;
+      writerTokenMint: PublicKey;
+      quoteAssetPool: PublicKey;
+    };
+    let quoteAccount: PublicKey;
+    let optionAccount: PublicKey;
+    let writerTokenAccount: PublicKey;
+
+    const claimBinaryPayout = (
+      claimTokenMint: PublicKey,
+      claimTokenSrc: PublicKey
+    ) =>
+      program.rpc.claimBinaryPayout(size, {
+        accounts: {
+          userAuthority: payer.publicKey,
+          binaryMarket: market.binaryMarket,
+          claimTokenMint,
+          claimTokenSrc,
+          quoteAssetPool: market.quoteAssetPool,
+          quoteAssetDest: quoteAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [payer],
+      });
+
+    const balance = async (account: PublicKey) =>
+      (await provider.connection.getTokenAccountBalance(account)).value.amount;
+
+    before(async () => {
+      oracle = await createPythPrice(mockPyth, payer, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: now(),
+      });
+      expiration = now().addn(4);
+      market = await deriveBinaryMarket(
+        program.programId,
+        underlyingToken.publicKey,
+        quoteToken.publicKey,
+        underlyingAmountPerContract,
+        strike,
+        payoutPerContract,
+        expiration,
+        0,
+        oracle
+      );
+      await program.rpc.initializeBinaryMarket(
+        underlyingAmountPerContract,
+        strike,
+        payoutPerContract,
+        expiration,
+        { above: {} },
+        { pyth: {} },
+        {
+          accounts: {
+            authority: payer.publicKey,
+            underlyingAssetMint: underlyingToken.publicKey,
+            quoteAssetMint: quoteToken.publicKey,
+            oracle,
+            optionMint: market.optionMint,
+            writerTokenMint: market.writerTokenMint,
+            quoteAssetPool: market.quoteAssetPool,
+            binaryMarket: market.binaryMarket,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: SYSVAR_RENT_PUBKEY,
+            systemProgram: SystemProgram.programId,
+          },
+          signers: [payer],
+        }
+      );
+
+      quoteAccount = await quoteToken.createAccount(payer.publicKey);
+      await quoteToken.mintTo(
+        quoteAccount,
+        mintAuthority,
+        [],
+        size.mul(payoutPerContract).toNumber()
+      );
+      optionAccount = await new Token(
+        provider.connection,
+        market.optionMint,
+        TOKEN_PROGRAM_ID,
+        payer
+      ).createAccount(payer.publicKey);
+      writerTokenAccount = await new Token(
+        provider.connection,
+        market.writerTokenMint,
+        TOKEN_PROGRAM_ID,
+        payer
+      ).createAccount(payer.publicKey);
+      await program.rpc.mintBinaryOption(size, {
+        accounts: {
+          userAuthority: payer.publicKey,
+          binaryMarket: market.binaryMarket,
+          quoteAssetSrc: quoteAccount,
+          quoteAssetPool: market.quoteAssetPool,
+          optionMint: market.optionMint,
+          mintedOptionDest: optionAccount,
+          writerTokenMint: market.writerTokenMint,
+          mintedWriterTokenDest: writerTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [payer],
+      });
+
+      // Wait so the market is expired
+      await wait(6000);
+      await setPythPrice(mockPyth, oracle, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: expiration,
+      });
+      await program.rpc.recordBinarySettlementPrice({
+        accounts: {
+          binaryMarket: market.binaryMarket,
+          oracle,
+        },
+      });
+    });
+
+    it("should record the oracle price of a contract", async () => {
+      const binaryMarket = await program.account.binaryMarket.fetch(
+        market.binaryMarket
+      );
+      assert.equal(binaryMarket.settlementPrice.toString(), "6");
+      assert.ok(binaryMarket.settlementSlot.gtn(0));
+    });
+
+    it("should error when the writer claims", async () => {
+      try {
+        await claimBinaryPayout(market.writerTokenMint, writerTokenAccount);
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Claimed tokens pay nothing at the settlement price";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+
+    it("should pay the holder the payout", async () => {
+      await claimBinaryPayout(market.optionMint, optionAccount);
+      assert.equal(await balance(optionAccount), "0");
+      assert.equal(
+        await balance(quoteAccount),
+        size.mul(payoutPerContract).toString()
+      );
+      assert.equal(await balance(market.quoteAssetPool), "0");
+    });
+  });
+});
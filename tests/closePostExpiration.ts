@@ -20,12 +20,15 @@ import {
 import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
 import { mintOptionsTx } from "../packages/psyoptions-ts/src";
 import { AnchorError, Program } from "@project-serum/anchor";
+import { recordPythSettlementPrice } from "../utils/oracle";
+import { MockPyth } from "../target/types/mock_pyth";
 import { PsyAmerican } from "../target/types/psy_american";
 
 describe("closePostExpiration", () => {
   const payer = anchor.web3.Keypair.generate();
   const mintAuthority = anchor.web3.Keypair.generate();
   const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
   const provider = program.provider;
 
   const minter = anchor.web3.Keypair.generate();
@@ -149,7 +152,38 @@ describe("closePostExpiration", () => {
       size = new u64(1);
     });
 
+    it("should error until the options settle out of the money", async () => {
+      try {
+        await closePostExpiration(
+          program,
+          minter,
+          size,
+          optionMarket.key,
+          optionMarket.writerTokenMint,
+          minterWriterAcct.publicKey,
+          optionMarket.underlyingAssetPool,
+          minterUnderlyingAccount.publicKey
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errorMsg =
+          "Writers can't close until the options settle out of the money or the auto exercise window closes";
+        assert.equal((err as AnchorError).error.errorMessage, errorMsg);
+      }
+    });
+
     describe("proper close post expiration", () => {
+      before(async () => {
+        // The strike is 5 quote per underlying, the market settles at 4
+        await recordPythSettlementPrice(
+          program,
+          mockPyth,
+          payer,
+          optionMarket,
+          new anchor.BN(4)
+        );
+      });
+
       it("should burn the WriteToken and transfer the underlying", async () => {
         const writerToken = new Token(
           provider.connection,
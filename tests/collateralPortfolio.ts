@@ -0,0 +1,239 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  Keypair,
+  PublicKey,
+  SYSVAR_RENT_PUBKEY,
+  SystemProgram,
+} from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import {
+  FEE_OWNER_KEY,
+  feeAmountPerContract,
+} from "../packages/psyoptions-ts/src/fees";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Collateral portfolios", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const owner = anchor.web3.Keypair.generate();
+
+  let underlyingToken: Token;
+  let optionMarket: OptionMarketV2;
+  let collateralPortfolio: PublicKey;
+  let underlyingVault: PublicKey;
+  let ownerOptions: Keypair;
+  let ownerWriterTokens: Keypair;
+  let ownerUnderlying: Keypair;
+
+  const mintFromPortfolio = (size: number) =>
+    program.rpc.mintFromPortfolio(new anchor.BN(size), {
+      accounts: {
+        owner: owner.publicKey,
+        collateralPortfolio,
+        underlyingVault,
+        optionMarket: optionMarket.key,
+        underlyingAssetPool: optionMarket.underlyingAssetPool,
+        optionMint: optionMarket.optionMint,
+        mintedOptionDest: ownerOptions.publicKey,
+        writerTokenMint: optionMarket.writerTokenMint,
+        mintedWriterTokenDest: ownerWriterTokens.publicKey,
+        feeOwner: FEE_OWNER_KEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      remainingAccounts: [
+        {
+          pubkey: optionMarket.mintFeeAccount,
+          isWritable: true,
+          isSigner: false,
+        },
+      ],
+      signers: [owner],
+    });
+
+  before(async () => {
+    await Promise.all(
+      [payer, owner].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let quoteToken: Token;
+    let remainingAccounts, instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    ({
+      optionAccount: ownerOptions,
+      writerTokenAccount: ownerWriterTokens,
+      underlyingAccount: ownerUnderlying,
+    } = await createMinter(
+      provider.connection,
+      owner,
+      mintAuthority,
+      underlyingToken,
+      optionMarket.underlyingAmountPerContract.muln(2).toNumber(),
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    ));
+
+    [collateralPortfolio] = await PublicKey.findProgramAddress(
+      [
+        owner.publicKey.toBuffer(),
+        optionMarket.underlyingAssetMint.toBuffer(),
+        optionMarket.quoteAssetMint.toBuffer(),
+        optionMarket.expirationUnixTimestamp.toBuffer("le", 8),
+        new TextEncoder().encode("collateralPortfolio"),
+      ],
+      program.programId
+    );
+    [underlyingVault] = await PublicKey.findProgramAddress(
+      [
+        collateralPortfolio.toBuffer(),
+        new TextEncoder().encode("underlyingVault"),
+      ],
+      program.programId
+    );
+    await program.rpc.initCollateralPortfolio(
+      optionMarket.expirationUnixTimestamp,
+      {
+        accounts: {
+          owner: owner.publicKey,
+          underlyingAssetMint: optionMarket.underlyingAssetMint,
+          quoteAssetMint: optionMarket.quoteAssetMint,
+          collateralPortfolio,
+          underlyingVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+        signers: [owner],
+      }
+    );
+    await program.rpc.addPortfolioMarket({
+      accounts: {
+        owner: owner.publicKey,
+        collateralPortfolio,
+        optionMarket: optionMarket.key,
+      },
+      signers: [owner],
+    });
+  });
+
+  it("should error when adding a market twice", async () => {
+    try {
+      await program.rpc.addPortfolioMarket({
+        accounts: {
+          owner: owner.publicKey,
+          collateralPortfolio,
+          optionMarket: optionMarket.key,
+        },
+        signers: [owner],
+      });
+      assert.ok(false);
+    } catch (err) {
+      const errMsg = "OptionMarket is already in the portfolio";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  it("should error minting without collateral", async () => {
+    try {
+      await mintFromPortfolio(1);
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Portfolio collateral does not cover the underlying and mint fee";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  describe("with collateral deposited", () => {
+    before(async () => {
+      await program.rpc.depositPortfolioCollateral(
+        optionMarket.underlyingAmountPerContract.muln(2),
+        {
+          accounts: {
+            owner: owner.publicKey,
+            collateralPortfolio,
+            underlyingAssetSrc: ownerUnderlying.publicKey,
+            underlyingVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [owner],
+        }
+      );
+    });
+
+    it("should mint options backed in the market's pool", async () => {
+      const poolBefore = await provider.connection.getTokenAccountBalance(
+        optionMarket.underlyingAssetPool
+      );
+      await mintFromPortfolio(1);
+      const options = await provider.connection.getTokenAccountBalance(
+        ownerOptions.publicKey
+      );
+      assert.equal(options.value.amount, "1");
+      const writerTokens = await provider.connection.getTokenAccountBalance(
+        ownerWriterTokens.publicKey
+      );
+      assert.equal(writerTokens.value.amount, "1");
+      const poolAfter = await provider.connection.getTokenAccountBalance(
+        optionMarket.underlyingAssetPool
+      );
+      assert.equal(
+        new anchor.BN(poolAfter.value.amount)
+          .sub(new anchor.BN(poolBefore.value.amount))
+          .toString(),
+        optionMarket.underlyingAmountPerContract.toString()
+      );
+      const vault = await provider.connection.getTokenAccountBalance(
+        underlyingVault
+      );
+      assert.equal(
+        vault.value.amount,
+        optionMarket.underlyingAmountPerContract
+          .sub(feeAmountPerContract(optionMarket.underlyingAmountPerContract))
+          .toString()
+      );
+      const portfolio = await program.account.collateralPortfolio.fetch(
+        collateralPortfolio
+      );
+      assert.ok(portfolio.markets[0].equals(optionMarket.key));
+    });
+
+    it("should error minting more than the vault covers", async () => {
+      try {
+        await mintFromPortfolio(1);
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "Portfolio collateral does not cover the underlying and mint fee";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
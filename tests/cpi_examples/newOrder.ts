@@ -8,6 +8,10 @@ import { OptionMarketV2 } from "../../packages/psyoptions-ts/src/types";
 import { initOptionMarket, initSetup } from "../../utils/helpers";
 import {
   createMintAndVault,
+  deriveOracleConfig,
+  derivePositionLimit,
+  derivePriceBand,
+  deriveTradingAllowlist,
   DEX_PID,
   getMarketAndAuthorityInfo,
   initMarket,
@@ -245,6 +249,32 @@ describe("cpi_examples newOrder", () => {
               coinVault: marketProxy.market._decoded.baseVault,
               // @ts-ignore: Dumb serum stuff
               pcVault: marketProxy.market._decoded.quoteVault,
+              psyOptionMarket: optionMarket.key,
+              priceBand: (
+                await derivePriceBand(
+                  americanOptionsProgram.programId,
+                  optionMarket.key
+                )
+              )[0],
+              oracleConfig: (
+                await deriveOracleConfig(
+                  americanOptionsProgram.programId,
+                  optionMarket.key
+                )
+              )[0],
+              oracle: SystemProgram.programId,
+              positionLimit: (
+                await derivePositionLimit(
+                  americanOptionsProgram.programId,
+                  optionMarket.key
+                )
+              )[0],
+              tradingAllowlist: (
+                await deriveTradingAllowlist(
+                  americanOptionsProgram.programId,
+                  marketProxy.market.address
+                )
+              )[0],
 
               systemProgram: SystemProgram.programId,
               tokenProgram: TOKEN_PROGRAM_ID,
@@ -330,6 +360,44 @@ describe("cpi_examples newOrder", () => {
                 coinVault: marketProxy.market._decoded.baseVault,
                 // @ts-ignore: Dumb serum stuff
                 pcVault: marketProxy.market._decoded.quoteVault,
+                psyOptionMarket: optionMarket.key,
+                priceBand: (
+                  await derivePriceBand(
+                    americanOptionsProgram.programId,
+                    optionMarket.key
+                  )
+                )[0],
+                oracleConfig: (
+                  await deriveOracleConfig(
+                    americanOptionsProgram.programId,
+                    optionMarket.key
+                  )
+                )[0],
+                oracle: SystemProgram.programId,
+                positionLimit: (
+                  await derivePositionLimit(
+                    americanOptionsProgram.programId,
+                    optionMarket.key
+                  )
+                )[0],
+                tradingAllowlist: (
+                  await deriveTradingAllowlist(
+                    americanOptionsProgram.programId,
+                    marketProxy.market.address
+                  )
+                )[0],
+              positionLimit: (
+                await derivePositionLimit(
+                  americanOptionsProgram.programId,
+                  optionMarket.key
+                )
+              )[0],
+              tradingAllowlist: (
+                await deriveTradingAllowlist(
+                  americanOptionsProgram.programId,
+                  marketProxy.market.address
+                )
+              )[0],
 
                 systemProgram: SystemProgram.programId,
                 tokenProgram: TOKEN_PROGRAM_ID,
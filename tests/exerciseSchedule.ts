@@ -0,0 +1,178 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token } from "@solana/spl-token";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  createExerciser,
+  initOptionMarket,
+  initOptionMarketWithSchedule,
+  initSetup,
+} from "../utils/helpers";
+import {
+  OptionMarketWithKey,
+  instructions as psyAmericanInstructions,
+  parseTransactionError,
+} from "@mithraic-labs/psy-american";
+import { AnchorProvider, Program, Wallet } from "@project-serum/anchor";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Exercise schedules", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as anchor.Program<PsyAmerican>;
+  const provider = program.provider;
+  const exerciser = anchor.web3.Keypair.generate();
+  const exerciserProgram = new Program(
+    program.idl,
+    program.programId,
+    new AnchorProvider(provider.connection, new Wallet(exerciser), {})
+  );
+
+  const now = Math.floor(new Date().getTime() / 1000);
+  const expiration = new anchor.BN(now + 3600);
+  // A single window that opens well after the tests run
+  const windows = [
+    { start: new anchor.BN(now + 1800), end: new anchor.BN(now + 2400) },
+  ];
+
+  let quoteToken: Token;
+  let underlyingToken: Token;
+  let optionMarket: OptionMarketWithKey;
+  let exerciseSchedule: PublicKey;
+  let exerciserOptionAcct: Keypair;
+  let exerciserQuoteAcct: Keypair;
+  let exerciserUnderlyingAcct: Keypair;
+
+  before(async () => {
+    await Promise.all(
+      [payer, exerciser].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            100 * LAMPORTS_PER_SOL
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let remainingAccounts, instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program, {
+      expiration,
+      exerciseWindows: windows,
+    }));
+    exerciseSchedule = await initOptionMarketWithSchedule(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+      windows
+    );
+    ({
+      optionAccount: exerciserOptionAcct,
+      quoteAccount: exerciserQuoteAcct,
+      underlyingAccount: exerciserUnderlyingAcct,
+    } = await createExerciser(
+      provider.connection,
+      exerciser,
+      mintAuthority,
+      quoteToken,
+      optionMarket.quoteAmountPerContract.toNumber(),
+      optionMarket.optionMint,
+      underlyingToken.publicKey
+    ));
+  });
+
+  it("should store the windows and their hash", async () => {
+    const schedule = await program.account.exerciseSchedule.fetch(
+      exerciseSchedule
+    );
+    assert.equal(schedule.optionMarket.toString(), optionMarket.key.toString());
+    assert.equal(schedule.windows.length, 1);
+    assert.equal(
+      schedule.windows[0].start.toString(),
+      windows[0].start.toString()
+    );
+    const market = await program.account.optionMarket.fetch(optionMarket.key);
+    assert.ok(market.exerciseScheduleHash.some((byte: number) => byte !== 0));
+  });
+
+  it("should leave the American market with the same terms free", async () => {
+    const {
+      optionMarket: americanMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program, {
+      expiration,
+      underlyingToken,
+      quoteToken,
+    });
+    assert.ok(!americanMarket.key.equals(optionMarket.key));
+    await initOptionMarket(
+      program,
+      payer,
+      americanMarket,
+      remainingAccounts,
+      instructions
+    );
+    const market = await program.account.optionMarket.fetch(
+      americanMarket.key
+    );
+    assert.ok(market.exerciseScheduleHash.every((byte: number) => byte === 0));
+  });
+
+  describe("exerciseOptionV2", () => {
+    const exerciseIx = () =>
+      psyAmericanInstructions.exerciseOptionsV2Instruction(
+        exerciserProgram,
+        new anchor.BN(1),
+        optionMarket,
+        exerciserOptionAcct.publicKey,
+        exerciserUnderlyingAcct.publicKey,
+        exerciserQuoteAcct.publicKey
+      );
+
+    it("should error when the ExerciseSchedule is missing", async () => {
+      try {
+        await exerciserProgram.provider.sendAndConfirm!(
+          new Transaction().add(exerciseIx())
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "OptionMarket has an exercise schedule, it must be the last remaining account";
+        assert.equal(parseTransactionError(err).msg, errMsg);
+      }
+    });
+
+    it("should error outside every exercise window", async () => {
+      const instruction = exerciseIx();
+      instruction.keys.push({
+        pubkey: exerciseSchedule,
+        isWritable: false,
+        isSigner: false,
+      });
+      try {
+        await exerciserProgram.provider.sendAndConfirm!(
+          new Transaction().add(instruction)
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "OptionMarket can't be exercised outside its exercise windows";
+        assert.equal(parseTransactionError(err).msg, errMsg);
+      }
+    });
+  });
+});
@@ -0,0 +1,232 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID, u64 } from "@solana/spl-token";
+import {
+  AccountMeta,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  SystemProgram,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  createExerciser,
+  createMinter,
+  initOptionMarket,
+  initSetup,
+} from "../utils/helpers";
+import {
+  OptionMarketWithKey,
+  instructions as psyAmericanInstructions,
+} from "@mithraic-labs/psy-american";
+import {
+  AnchorError,
+  AnchorProvider,
+  Program,
+  Wallet,
+} from "@project-serum/anchor";
+import {
+  FEE_OWNER_KEY,
+  feeAmountPerContract,
+} from "../packages/psyoptions-ts/src/fees";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("flashExercise", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as anchor.Program<PsyAmerican>;
+  const provider = program.provider;
+
+  const minter = anchor.web3.Keypair.generate();
+  const minterProgram = new Program(
+    program.idl,
+    program.programId,
+    new AnchorProvider(provider.connection, new Wallet(minter), {})
+  );
+  const exerciser = anchor.web3.Keypair.generate();
+
+  let quoteToken: Token;
+  let underlyingToken: Token;
+  let optionToken: Token;
+  let optionMarket: OptionMarketWithKey;
+  let exerciserOptionAcct: Keypair;
+  let exerciserQuoteAcct: Keypair;
+  let exerciserUnderlyingAcct: Keypair;
+  const size = new u64(2);
+
+  before(async () => {
+    await Promise.all(
+      [payer, minter, exerciser].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            100 * LAMPORTS_PER_SOL
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let remainingAccounts, instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    const {
+      optionAccount: minterOptionAcct,
+      underlyingAccount: minterUnderlyingAccount,
+      writerTokenAccount: minterWriterAcct,
+    } = await createMinter(
+      provider.connection,
+      minter,
+      mintAuthority,
+      underlyingToken,
+      new anchor.BN(10)
+        .mul(optionMarket.underlyingAmountPerContract)
+        .muln(2)
+        .toNumber(),
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    );
+    const { ix: mintOptionsIx } =
+      await psyAmericanInstructions.mintOptionV2Instruction(
+        minterProgram,
+        minterOptionAcct.publicKey,
+        minterWriterAcct.publicKey,
+        minterUnderlyingAccount.publicKey,
+        new anchor.BN(10),
+        optionMarket
+      );
+    await program.provider.sendAndConfirm!(
+      new Transaction().add(mintOptionsIx),
+      [minter]
+    );
+    ({
+      optionAccount: exerciserOptionAcct,
+      quoteAccount: exerciserQuoteAcct,
+      underlyingAccount: exerciserUnderlyingAcct,
+    } = await createExerciser(
+      provider.connection,
+      exerciser,
+      mintAuthority,
+      quoteToken,
+      new anchor.BN(10).mul(optionMarket.quoteAmountPerContract).toNumber(),
+      optionMarket.optionMint,
+      underlyingToken.publicKey
+    ));
+    await optionToken.transfer(
+      minterOptionAcct.publicKey,
+      exerciserOptionAcct.publicKey,
+      minter,
+      [],
+      new u64(10)
+    );
+  });
+
+  const exerciseFee = () =>
+    feeAmountPerContract(optionMarket.quoteAmountPerContract).mul(size);
+
+  // Uses an SPL Token transfer as the callback that repays the strike
+  const flashExercise = (repayment: anchor.BN) => {
+    const repayIx = Token.createTransferInstruction(
+      TOKEN_PROGRAM_ID,
+      exerciserQuoteAcct.publicKey,
+      optionMarket.quoteAssetPool,
+      exerciser.publicKey,
+      [],
+      new u64(repayment.toString())
+    );
+    return program.rpc.flashExercise(size, repayIx.data, {
+      accounts: {
+        optionAuthority: exerciser.publicKey,
+        optionMarket: optionMarket.key,
+        optionMint: optionMarket.optionMint,
+        exerciserOptionTokenSrc: exerciserOptionAcct.publicKey,
+        underlyingAssetPool: optionMarket.underlyingAssetPool,
+        underlyingAssetDest: exerciserUnderlyingAcct.publicKey,
+        quoteAssetPool: optionMarket.quoteAssetPool,
+        callbackProgram: TOKEN_PROGRAM_ID,
+        feeOwner: FEE_OWNER_KEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      remainingAccounts: [...exerciseFeeAccounts(), ...repayIx.keys],
+      signers: [exerciser],
+    });
+  };
+
+  // The exercise fee account comes before the callback's accounts
+  const exerciseFeeAccounts = (): AccountMeta[] =>
+    exerciseFee().gtn(0)
+      ? [
+          {
+            pubkey: optionMarket.exerciseFeeAccount,
+            isWritable: true,
+            isSigner: false,
+          },
+        ]
+      : [];
+
+  it("should deliver the underlying when the callback pays the strike and fee", async () => {
+    const quotePoolBefore = await quoteToken.getAccountInfo(
+      optionMarket.quoteAssetPool
+    );
+    const feesBefore = exerciseFee().gtn(0)
+      ? (await quoteToken.getAccountInfo(optionMarket.exerciseFeeAccount))
+          .amount
+      : new anchor.BN(0);
+    await flashExercise(
+      optionMarket.quoteAmountPerContract.mul(size).add(exerciseFee())
+    );
+
+    const quotePoolAfter = await quoteToken.getAccountInfo(
+      optionMarket.quoteAssetPool
+    );
+    assert.equal(
+      quotePoolAfter.amount.sub(quotePoolBefore.amount).toString(),
+      optionMarket.quoteAmountPerContract.mul(size).toString()
+    );
+    const exerciserUnderlying = await underlyingToken.getAccountInfo(
+      exerciserUnderlyingAcct.publicKey
+    );
+    assert.equal(
+      exerciserUnderlying.amount.toString(),
+      optionMarket.underlyingAmountPerContract.mul(size).toString()
+    );
+    if (exerciseFee().gtn(0)) {
+      const feesAfter = await quoteToken.getAccountInfo(
+        optionMarket.exerciseFeeAccount
+      );
+      assert.equal(
+        feesAfter.amount.sub(feesBefore).toString(),
+        exerciseFee().toString()
+      );
+    }
+  });
+
+  it("should error when the callback doesn't pay the exercise fee", async () => {
+    try {
+      await flashExercise(
+        optionMarket.quoteAmountPerContract
+          .mul(size)
+          .add(exerciseFee())
+          .subn(1)
+      );
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Flash exercise callback did not pay the strike and exercise fee into the quote asset pool";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
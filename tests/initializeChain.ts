@@ -0,0 +1,149 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  AccountMeta,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  chainStrikeAccounts,
+  deriveOptionChain,
+  deriveQuoteRegistry,
+  initSetup,
+  listRegistryMarkets,
+  registerMarket,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("initializeChain", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+  let feeAccounts: AccountMeta[];
+  let instructions: TransactionInstruction[];
+
+  const initializeChain = async (strikes: anchor.BN[]) => {
+    const [optionChainKey] = await deriveOptionChain(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.quoteAssetMint,
+      optionMarket.underlyingAmountPerContract,
+      optionMarket.expirationUnixTimestamp
+    );
+    const strikeAccounts = await Promise.all(
+      strikes.map((strike) =>
+        chainStrikeAccounts(
+          program.programId,
+          optionMarket.underlyingAssetMint,
+          optionMarket.quoteAssetMint,
+          optionMarket.underlyingAmountPerContract,
+          strike,
+          optionMarket.expirationUnixTimestamp
+        )
+      )
+    );
+    await program.rpc.initializeChain(
+      optionMarket.underlyingAmountPerContract,
+      optionMarket.expirationUnixTimestamp,
+      strikes,
+      {
+        accounts: {
+          authority: payer.publicKey,
+          underlyingAssetMint: optionMarket.underlyingAssetMint,
+          quoteAssetMint: optionMarket.quoteAssetMint,
+          optionChain: optionChainKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        },
+        remainingAccounts: [...feeAccounts, ...strikeAccounts.flat()],
+        signers: [payer],
+        instructions,
+      }
+    );
+    return { optionChainKey, strikeAccounts };
+  };
+
+  beforeEach(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    ({
+      optionMarket,
+      remainingAccounts: feeAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+  });
+
+  it("should create a market for every strike", async () => {
+    const strikes = [
+      new anchor.BN("50000000000"),
+      new anchor.BN("60000000000"),
+    ];
+    const { optionChainKey, strikeAccounts } = await initializeChain(strikes);
+
+    const optionChain = await program.account.optionChain.fetch(
+      optionChainKey
+    );
+    assert.deepEqual(
+      optionChain.strikes.map((strike: anchor.BN) => strike.toString()),
+      strikes.map((strike) => strike.toString())
+    );
+    for (let i = 0; i < strikes.length; i++) {
+      const [market, optionMint, writerTokenMint] = strikeAccounts[i];
+      assert.ok(optionChain.optionMarkets[i].equals(market.pubkey));
+      const onChainMarket = (await program.account.optionMarket.fetch(
+        market.pubkey
+      )) as OptionMarketV2;
+      assert.ok(onChainMarket.optionMint.equals(optionMint.pubkey));
+      assert.ok(onChainMarket.writerTokenMint.equals(writerTokenMint.pubkey));
+      assert.ok(onChainMarket.quoteAmountPerContract.eq(strikes[i]));
+      assert.ok(
+        onChainMarket.exerciseFeeAccount.equals(optionMarket.exerciseFeeAccount)
+      );
+    }
+  });
+
+  it("should register every market of the chain", async () => {
+    const strikes = [
+      new anchor.BN("50000000000"),
+      new anchor.BN("60000000000"),
+    ];
+    const { strikeAccounts } = await initializeChain(strikes);
+    for (const [market] of strikeAccounts) {
+      await registerMarket(program, payer, market.pubkey);
+    }
+    const [quoteRegistry] = await deriveQuoteRegistry(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.quoteAssetMint
+    );
+    const markets = await listRegistryMarkets(program, quoteRegistry);
+    assert.deepEqual(
+      markets.map((market) => market.toString()),
+      strikeAccounts.map(([market]) => market.pubkey.toString())
+    );
+  });
+
+  it("should error when the strikes are not ascending", async () => {
+    try {
+      await initializeChain([
+        new anchor.BN("60000000000"),
+        new anchor.BN("50000000000"),
+      ]);
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Chain strikes must be ascending, non zero and fit in the OptionChain";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
@@ -0,0 +1,339 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  closePostExpiration,
+  createExerciser,
+  createMinter,
+  exerciseOptionTx,
+  initOptionMarket,
+  initSetup,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { mintOptionsTx } from "../packages/psyoptions-ts/src";
+import {
+  createPythPrice,
+  initPythOracleConfig,
+  loadAdmin,
+  setPythPrice,
+} from "../utils/oracle";
+import { deriveKnockOutBarrier, deriveOracleConfig } from "../utils/serum";
+import { MockPyth } from "../target/types/mock_pyth";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Knock-out barriers", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    let remainingAccounts, instructions;
+    ({ optionMarket, remainingAccounts, instructions } = await initSetup(
+      provider,
+      payer,
+      mintAuthority,
+      program
+    ));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  it("should start not knocked out", async () => {
+    const market = await program.account.optionMarket.fetch(optionMarket.key);
+    assert.equal(market.knockedOut, false);
+  });
+
+  describe("initKnockOutBarrier", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [oracleConfig] = await deriveOracleConfig(
+        program.programId,
+        optionMarket.key
+      );
+      const [knockOutBarrier] = await deriveKnockOutBarrier(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.initKnockOutBarrier(
+          new anchor.BN(1_000),
+          { upAndOut: {} },
+          {
+            accounts: {
+              admin: payer.publicKey,
+              optionMarket: optionMarket.key,
+              optionMint: optionMarket.optionMint,
+              oracleConfig,
+              knockOutBarrier,
+              systemProgram: SystemProgram.programId,
+            },
+            signers: [payer],
+          }
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("triggerKnockOut", () => {
+    it("should error when the OptionMarket has no barrier", async () => {
+      const [oracleConfig] = await deriveOracleConfig(
+        program.programId,
+        optionMarket.key
+      );
+      const [knockOutBarrier] = await deriveKnockOutBarrier(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.triggerKnockOut({
+          accounts: {
+            optionMarket: optionMarket.key,
+            knockOutBarrier,
+            oracleConfig,
+            oracle: SystemProgram.programId,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "The program expected this account to be already initialized";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("price breaches the barrier", () => {
+    const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
+    const minter = anchor.web3.Keypair.generate();
+    const exerciser = anchor.web3.Keypair.generate();
+    const size = new anchor.BN(10);
+
+    let admin: Keypair;
+    let market: OptionMarketV2;
+    let underlyingToken: Token;
+    let quoteToken: Token;
+    let exerciseFeeKey: PublicKey;
+    let oracle: PublicKey;
+    let oracleConfig: PublicKey;
+    let knockOutBarrier: PublicKey;
+    let minterOptionAcct: Keypair;
+    let minterWriterAcct: Keypair;
+    let minterUnderlyingAcct: Keypair;
+    let exerciserOptionAcct: Keypair;
+    let exerciserQuoteAcct: Keypair;
+    let exerciserUnderlyingAcct: Keypair;
+
+    const now = () => new anchor.BN(Math.floor(Date.now() / 1000));
+
+    before(async () => {
+      admin = await loadAdmin(provider);
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          minter.publicKey,
+          10_000_000_000
+        ),
+        "confirmed"
+      );
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          exerciser.publicKey,
+          10_000_000_000
+        ),
+        "confirmed"
+      );
+      let remainingAccounts, instructions;
+      ({
+        optionMarket: market,
+        underlyingToken,
+        quoteToken,
+        exerciseFeeKey,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program));
+      await initOptionMarket(
+        program,
+        payer,
+        market,
+        remainingAccounts,
+        instructions
+      );
+
+      // The strike is 5 quote per underlying, the barrier is knocked out at 8
+      oracle = await createPythPrice(mockPyth, payer, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: now(),
+      });
+      oracleConfig = await initPythOracleConfig(program, admin, market, oracle);
+      [knockOutBarrier] = await deriveKnockOutBarrier(
+        program.programId,
+        market.key
+      );
+      await program.rpc.initKnockOutBarrier(
+        market.underlyingAmountPerContract.muln(8),
+        { upAndOut: {} },
+        {
+          accounts: {
+            admin: admin.publicKey,
+            optionMarket: market.key,
+            optionMint: market.optionMint,
+            oracleConfig,
+            knockOutBarrier,
+            systemProgram: SystemProgram.programId,
+          },
+          signers: [admin],
+        }
+      );
+
+      ({
+        optionAccount: minterOptionAcct,
+        underlyingAccount: minterUnderlyingAcct,
+        writerTokenAccount: minterWriterAcct,
+      } = await createMinter(
+        provider.connection,
+        minter,
+        mintAuthority,
+        underlyingToken,
+        size.muln(2).mul(market.underlyingAmountPerContract).toNumber(),
+        market.optionMint,
+        market.writerTokenMint,
+        quoteToken
+      ));
+      await mintOptionsTx(
+        program,
+        minter,
+        minterOptionAcct,
+        minterWriterAcct,
+        minterUnderlyingAcct,
+        size,
+        market
+      );
+      ({
+        optionAccount: exerciserOptionAcct,
+        quoteAccount: exerciserQuoteAcct,
+        underlyingAccount: exerciserUnderlyingAcct,
+      } = await createExerciser(
+        provider.connection,
+        exerciser,
+        mintAuthority,
+        quoteToken,
+        size.muln(2).mul(market.quoteAmountPerContract).toNumber(),
+        market.optionMint,
+        market.underlyingAssetMint
+      ));
+      const optionToken = new Token(
+        provider.connection,
+        market.optionMint,
+        TOKEN_PROGRAM_ID,
+        payer
+      );
+      await optionToken.transfer(
+        minterOptionAcct.publicKey,
+        exerciserOptionAcct.publicKey,
+        minter,
+        [],
+        size
+      );
+    });
+
+    it("should error when the price does not breach the barrier", async () => {
+      try {
+        await program.rpc.triggerKnockOut({
+          accounts: {
+            optionMarket: market.key,
+            knockOutBarrier,
+            oracleConfig,
+            oracle,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Oracle price does not breach the barrier";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+
+    it("should knock out the OptionMarket", async () => {
+      await setPythPrice(mockPyth, oracle, {
+        price: new anchor.BN(10),
+        expo: 0,
+        publishTime: now(),
+      });
+      await program.rpc.triggerKnockOut({
+        accounts: {
+          optionMarket: market.key,
+          knockOutBarrier,
+          oracleConfig,
+          oracle,
+        },
+      });
+      const optionMarket = await program.account.optionMarket.fetch(
+        market.key
+      );
+      assert.equal(optionMarket.knockedOut, true);
+    });
+
+    it("should not allow the options to be exercised", async () => {
+      try {
+        await exerciseOptionTx(
+          program,
+          size,
+          market.key,
+          market.optionMint,
+          exerciser,
+          exerciser,
+          exerciserOptionAcct.publicKey,
+          market.underlyingAssetPool,
+          exerciserUnderlyingAcct.publicKey,
+          market.quoteAssetPool,
+          exerciserQuoteAcct.publicKey,
+          [{ pubkey: exerciseFeeKey, isWritable: true, isSigner: false }]
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "OptionMarket was knocked out";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+
+    it("should let writers close before expiration", async () => {
+      const underlyingBefore = await underlyingToken.getAccountInfo(
+        minterUnderlyingAcct.publicKey
+      );
+      await closePostExpiration(
+        program,
+        minter,
+        size,
+        market.key,
+        market.writerTokenMint,
+        minterWriterAcct.publicKey,
+        market.underlyingAssetPool,
+        minterUnderlyingAcct.publicKey
+      );
+      const underlyingAfter = await underlyingToken.getAccountInfo(
+        minterUnderlyingAcct.publicKey
+      );
+      assert.equal(
+        underlyingAfter.amount.sub(underlyingBefore.amount).toString(),
+        size.mul(market.underlyingAmountPerContract).toString()
+      );
+    });
+  });
+});
@@ -0,0 +1,130 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { SystemProgram } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  deriveExpiryRegistry,
+  deriveQuoteRegistry,
+  deriveRegistryPage,
+  initOptionMarket,
+  initSetup,
+  listRegistryMarkets,
+  registerMarket,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("marketRegistry", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    const {
+      optionMarket: market,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program);
+    optionMarket = market;
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  it("should not register markets on initialization", async () => {
+    const [quoteRegistry] = await deriveQuoteRegistry(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.quoteAssetMint
+    );
+    assert.equal(
+      await program.account.marketRegistry.fetchNullable(quoteRegistry),
+      null
+    );
+  });
+
+  it("should append the market to its quote registry", async () => {
+    await registerMarket(program, payer, optionMarket.key);
+    const [quoteRegistry] = await deriveQuoteRegistry(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.quoteAssetMint
+    );
+    const registry = await program.account.marketRegistry.fetch(quoteRegistry);
+    assert.equal(registry.marketCount.toNumber(), 1);
+    assert.equal(registry.currentPage, 0);
+    const markets = await listRegistryMarkets(program, quoteRegistry);
+    assert.deepEqual(
+      markets.map((market) => market.toString()),
+      [optionMarket.key.toString()]
+    );
+  });
+
+  it("should append the market to its expiry registry", async () => {
+    const [expiryRegistry] = await deriveExpiryRegistry(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.expirationUnixTimestamp
+    );
+    const markets = await listRegistryMarkets(program, expiryRegistry);
+    assert.deepEqual(
+      markets.map((market) => market.toString()),
+      [optionMarket.key.toString()]
+    );
+  });
+
+  it("should error when the market is already registered", async () => {
+    try {
+      await registerMarket(program, payer, optionMarket.key);
+      assert.ok(false);
+    } catch (err) {
+      // The MarketRegistration PDA already exists
+      assert.ok(!(err instanceof assert.AssertionError));
+    }
+  });
+
+  it("should error when opening a page after a page that isn't full", async () => {
+    const [quoteRegistry] = await deriveQuoteRegistry(
+      program.programId,
+      optionMarket.underlyingAssetMint,
+      optionMarket.quoteAssetMint
+    );
+    const [currentPage] = await deriveRegistryPage(
+      program.programId,
+      quoteRegistry,
+      0
+    );
+    const [nextPage] = await deriveRegistryPage(
+      program.programId,
+      quoteRegistry,
+      1
+    );
+    try {
+      await program.rpc.openRegistryPage({
+        accounts: {
+          payer: payer.publicKey,
+          registry: quoteRegistry,
+          currentPage,
+          nextPage,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [payer],
+      });
+      assert.ok(false);
+    } catch (err) {
+      const errMsg = "A new MarketRegistryPage can only follow a full page";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
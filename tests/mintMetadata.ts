@@ -0,0 +1,106 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { SystemProgram } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  initOptionMarket,
+  initOptionMarketWithMetadata,
+  initSetup,
+  mintMetadataAccounts,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("mintMetadata", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    const {
+      optionMarket: market,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(
+      provider,
+      payer,
+      mintAuthority,
+      program,
+      // Whole number timestamp for a deterministic date
+      { expiration: new anchor.BN(1924905600) }
+    );
+    optionMarket = market;
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  it("should error when the metadata isn't the mint's Metaplex metadata", async () => {
+    const accounts = await mintMetadataAccounts(
+      program.programId,
+      optionMarket
+    );
+    try {
+      await program.rpc.initMintMetadata({
+        accounts: {
+          payer: payer.publicKey,
+          optionMarket: optionMarket.key,
+          ...accounts,
+          underlyingAssetMetadata: accounts.quoteAssetMetadata,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [payer],
+      });
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Metadata account is not the mint's Metaplex metadata address";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  it("should name mints without Metaplex metadata by their address", async () => {
+    const {
+      optionMarket: namedMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program, {
+      expiration: new anchor.BN(1924905600),
+    });
+    const accounts = await initOptionMarketWithMetadata(
+      program,
+      payer,
+      namedMarket,
+      remainingAccounts,
+      instructions
+    );
+    // The test mints have 0 decimals, 50 quote per 10 underlying
+    const name = `${namedMarket.underlyingAssetMint
+      .toBase58()
+      .slice(0, 4)}-${namedMarket.quoteAssetMint
+      .toBase58()
+      .slice(0, 4)} 5C 2030-12-31`;
+    const option = await program.account.mintMetadata.fetch(
+      accounts.optionMetadata
+    );
+    assert.equal(option.name, name);
+    assert.ok(option.mint.equals(namedMarket.optionMint));
+    assert.ok(option.market.equals(namedMarket.key));
+    const writer = await program.account.mintMetadata.fetch(
+      accounts.writerMetadata
+    );
+    assert.equal(writer.name, `${name}-WRT`);
+    assert.ok(writer.mint.equals(namedMarket.writerTokenMint));
+  });
+});
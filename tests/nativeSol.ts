@@ -0,0 +1,305 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { NATIVE_MINT, Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  AccountMeta,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+} from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import { mintOptionsTx } from "../packages/psyoptions-ts/src";
+import {
+  feeAmountPerContract,
+  FEE_OWNER_KEY,
+} from "../packages/psyoptions-ts/src/fees";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("nativeSol", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const minter = anchor.web3.Keypair.generate();
+
+  let optionMarket: OptionMarketV2;
+  let optionDest: PublicKey;
+  let writerTokenDest: PublicKey;
+
+  const mintOptionNative = (
+    market: OptionMarketV2,
+    remainingAccounts: AccountMeta[]
+  ) =>
+    program.rpc.mintOptionNative(new anchor.BN(1), {
+      accounts: {
+        userAuthority: minter.publicKey,
+        optionMarket: market.key,
+        underlyingAssetPool: market.underlyingAssetPool,
+        optionMint: market.optionMint,
+        mintedOptionDest: optionDest,
+        writerTokenMint: market.writerTokenMint,
+        mintedWriterTokenDest: writerTokenDest,
+        feeOwner: FEE_OWNER_KEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      remainingAccounts,
+      signers: [minter],
+    });
+
+  before(async () => {
+    await Promise.all(
+      [payer, minter].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            5_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    const {
+      optionMarket: market,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program, {
+      underlyingAmountPerContract: new anchor.BN(1_000_000_000),
+      underlyingToken: new Token(
+        provider.connection,
+        NATIVE_MINT,
+        TOKEN_PROGRAM_ID,
+        payer
+      ),
+    });
+    optionMarket = market;
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    optionDest = await new Token(
+      provider.connection,
+      optionMarket.optionMint,
+      TOKEN_PROGRAM_ID,
+      payer
+    ).createAccount(minter.publicKey);
+    writerTokenDest = await new Token(
+      provider.connection,
+      optionMarket.writerTokenMint,
+      TOKEN_PROGRAM_ID,
+      payer
+    ).createAccount(minter.publicKey);
+  });
+
+  it("should wrap lamports into the underlying pool", async () => {
+    await mintOptionNative(optionMarket, [
+      {
+        pubkey: optionMarket.mintFeeAccount,
+        isWritable: true,
+        isSigner: false,
+      },
+    ]);
+    const pool = await provider.connection.getTokenAccountBalance(
+      optionMarket.underlyingAssetPool
+    );
+    assert.equal(
+      pool.value.amount,
+      optionMarket.underlyingAmountPerContract.toString()
+    );
+    const mintFee = await provider.connection.getTokenAccountBalance(
+      optionMarket.mintFeeAccount
+    );
+    assert.ok(
+      new anchor.BN(mintFee.value.amount).gte(
+        feeAmountPerContract(optionMarket.underlyingAmountPerContract)
+      )
+    );
+    const options = await provider.connection.getTokenAccountBalance(
+      optionDest
+    );
+    assert.equal(options.value.amount, "1");
+  });
+
+  it("should error minting on a market without a native underlying", async () => {
+    const {
+      optionMarket: market,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program);
+    await initOptionMarket(
+      program,
+      payer,
+      market,
+      remainingAccounts,
+      instructions
+    );
+    try {
+      await mintOptionNative(market, remainingAccounts);
+      assert.ok(false);
+    } catch (err) {
+      const errMsg = "The OptionMarket's underlying asset must be native SOL";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  describe("burnWriterForQuoteNative", () => {
+    let quoteMarket: OptionMarketV2;
+    let minterOptionAcct: anchor.web3.Keypair;
+    let minterWriterAcct: anchor.web3.Keypair;
+    let minterUnderlyingAcct: anchor.web3.Keypair;
+
+    before(async () => {
+      const {
+        optionMarket: market,
+        underlyingToken,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program, {
+        quoteAmountPerContract: new anchor.BN(1_000_000_000),
+        quoteToken: new Token(
+          provider.connection,
+          NATIVE_MINT,
+          TOKEN_PROGRAM_ID,
+          payer
+        ),
+      });
+      quoteMarket = market;
+      await initOptionMarket(
+        program,
+        payer,
+        quoteMarket,
+        remainingAccounts,
+        instructions
+      );
+      ({
+        optionAccount: minterOptionAcct,
+        writerTokenAccount: minterWriterAcct,
+        underlyingAccount: minterUnderlyingAcct,
+      } = await createMinter(
+        provider.connection,
+        minter,
+        mintAuthority,
+        underlyingToken,
+        quoteMarket.underlyingAmountPerContract.muln(2).toNumber(),
+        quoteMarket.optionMint,
+        quoteMarket.writerTokenMint,
+        new Token(provider.connection, NATIVE_MINT, TOKEN_PROGRAM_ID, payer)
+      ));
+      await mintOptionsTx(
+        program,
+        minter,
+        minterOptionAcct,
+        minterWriterAcct,
+        minterUnderlyingAcct,
+        new anchor.BN(1),
+        quoteMarket
+      );
+      // Exercise with lamports so the quote pool holds wrapped SOL
+      await program.rpc.exerciseOptionNative(new anchor.BN(1), {
+        accounts: {
+          userAuthority: minter.publicKey,
+          optionAuthority: minter.publicKey,
+          optionMarket: quoteMarket.key,
+          optionMint: quoteMarket.optionMint,
+          exerciserOptionTokenSrc: minterOptionAcct.publicKey,
+          underlyingAssetPool: quoteMarket.underlyingAssetPool,
+          underlyingAssetDest: minterUnderlyingAcct.publicKey,
+          quoteAssetPool: quoteMarket.quoteAssetPool,
+          feeOwner: FEE_OWNER_KEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        },
+        remainingAccounts: [
+          {
+            pubkey: quoteMarket.exerciseFeeAccount,
+            isWritable: true,
+            isSigner: false,
+          },
+        ],
+        signers: [minter],
+      });
+    });
+
+    it("should unwrap the quote to the writer as lamports", async () => {
+      const [unwrapAccount] = await PublicKey.findProgramAddress(
+        [
+          quoteMarket.key.toBuffer(),
+          minter.publicKey.toBuffer(),
+          new TextEncoder().encode("nativeUnwrap"),
+        ],
+        program.programId
+      );
+      const lamportsBefore = await provider.connection.getBalance(
+        minter.publicKey
+      );
+      await program.rpc.burnWriterForQuoteNative(new anchor.BN(1), {
+        accounts: {
+          userAuthority: minter.publicKey,
+          optionMarket: quoteMarket.key,
+          writerTokenMint: quoteMarket.writerTokenMint,
+          writerTokenSrc: minterWriterAcct.publicKey,
+          quoteAssetPool: quoteMarket.quoteAssetPool,
+          nativeMint: NATIVE_MINT,
+          unwrapAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+        signers: [minter],
+      });
+      // The provider pays the transaction fee and the unwrap account's rent is refunded
+      const lamportsAfter = await provider.connection.getBalance(
+        minter.publicKey
+      );
+      assert.equal(
+        lamportsAfter - lamportsBefore,
+        quoteMarket.quoteAmountPerContract.toNumber()
+      );
+      const pool = await provider.connection.getTokenAccountBalance(
+        quoteMarket.quoteAssetPool
+      );
+      assert.equal(pool.value.amount, "0");
+      assert.equal(
+        await provider.connection.getAccountInfo(unwrapAccount),
+        null
+      );
+    });
+
+    it("should error on a market without a native quote", async () => {
+      const [unwrapAccount] = await PublicKey.findProgramAddress(
+        [
+          optionMarket.key.toBuffer(),
+          minter.publicKey.toBuffer(),
+          new TextEncoder().encode("nativeUnwrap"),
+        ],
+        program.programId
+      );
+      try {
+        await program.rpc.burnWriterForQuoteNative(new anchor.BN(1), {
+          accounts: {
+            userAuthority: minter.publicKey,
+            optionMarket: optionMarket.key,
+            writerTokenMint: optionMarket.writerTokenMint,
+            writerTokenSrc: writerTokenDest,
+            quoteAssetPool: optionMarket.quoteAssetPool,
+            nativeMint: NATIVE_MINT,
+            unwrapAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+          },
+          signers: [minter],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "The OptionMarket's quote asset must be native SOL";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
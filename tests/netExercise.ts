@@ -0,0 +1,127 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Keypair, SystemProgram } from "@solana/web3.js";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { deriveOracleConfig } from "../utils/serum";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Net exercise", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const holder = anchor.web3.Keypair.generate();
+
+  let underlyingToken: Token;
+  let optionMarket: OptionMarketV2;
+  let holderOptions: Keypair;
+  let holderUnderlying: Keypair;
+  let holderWriterTokens: Keypair;
+
+  before(async () => {
+    await Promise.all(
+      [payer, holder].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let quoteToken: Token;
+    let remainingAccounts, instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    ({
+      optionAccount: holderOptions,
+      underlyingAccount: holderUnderlying,
+      writerTokenAccount: holderWriterTokens,
+    } = await createMinter(
+      provider.connection,
+      holder,
+      mintAuthority,
+      underlyingToken,
+      0,
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    ));
+  });
+
+  it("should start with nothing net exercised", async () => {
+    const market = await program.account.optionMarket.fetch(optionMarket.key);
+    assert.equal(market.netExercisedContracts.toString(), "0");
+    assert.equal(market.netExercisedUnderlying.toString(), "0");
+  });
+
+  describe("exerciseOptionNet", () => {
+    it("should error when the OptionMarket has no OracleConfig", async () => {
+      const [oracleConfig] = await deriveOracleConfig(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.exerciseOptionNet(new anchor.BN(1), {
+          accounts: {
+            optionAuthority: holder.publicKey,
+            optionMarket: optionMarket.key,
+            oracleConfig,
+            oracle: SystemProgram.programId,
+            optionMint: optionMarket.optionMint,
+            exerciserOptionTokenSrc: holderOptions.publicKey,
+            underlyingAssetPool: optionMarket.underlyingAssetPool,
+            underlyingAssetDest: holderUnderlying.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [holder],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "The program expected this account to be already initialized";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("burnWriterForNetExercise", () => {
+    it("should error when no contracts were net exercised", async () => {
+      try {
+        await program.rpc.burnWriterForNetExercise(new anchor.BN(1), {
+          accounts: {
+            userAuthority: holder.publicKey,
+            optionMarket: optionMarket.key,
+            writerTokenMint: optionMarket.writerTokenMint,
+            writerTokenSrc: holderWriterTokens.publicKey,
+            underlyingAssetPool: optionMarket.underlyingAssetPool,
+            underlyingAssetDest: holderUnderlying.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [holder],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "Not enough net exercised contracts to burn the WriterTokens against";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
@@ -0,0 +1,250 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  Keypair,
+  PublicKey,
+  SYSVAR_RENT_PUBKEY,
+  SystemProgram,
+} from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  createMinter,
+  initOptionMarket,
+  initSetup,
+  mintMetadataAccounts,
+} from "../utils/helpers";
+import { feeAmountPerContract } from "../packages/psyoptions-ts/src/fees";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Cash-secured puts", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const writer = anchor.web3.Keypair.generate();
+  const textEncoder = new TextEncoder();
+
+  // The call market only supplies the mints and the quote fee account
+  let optionMarket: OptionMarketV2;
+  let putMarket: PublicKey;
+  let optionMint: PublicKey;
+  let writerTokenMint: PublicKey;
+  let quoteAssetPool: PublicKey;
+  let underlyingAssetPool: PublicKey;
+  let writerAccounts: {
+    optionAccount: Keypair;
+    quoteAccount: Keypair;
+    underlyingAccount: Keypair;
+    writerTokenAccount: Keypair;
+  };
+
+  before(async () => {
+    await Promise.all(
+      [payer, writer].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let quoteToken: Token;
+    let underlyingToken: Token;
+    let remainingAccounts, instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+
+    [putMarket] = await PublicKey.findProgramAddress(
+      [
+        optionMarket.underlyingAssetMint.toBuffer(),
+        optionMarket.quoteAssetMint.toBuffer(),
+        optionMarket.underlyingAmountPerContract.toBuffer("le", 8),
+        optionMarket.quoteAmountPerContract.toBuffer("le", 8),
+        optionMarket.expirationUnixTimestamp.toBuffer("le", 8),
+        textEncoder.encode("putMarket"),
+      ],
+      program.programId
+    );
+    [
+      [optionMint],
+      [writerTokenMint],
+      [quoteAssetPool],
+      [underlyingAssetPool],
+    ] = await Promise.all(
+      [
+        "optionToken",
+        "writerToken",
+        "quoteAssetPool",
+        "underlyingAssetPool",
+      ].map((seed) =>
+        PublicKey.findProgramAddress(
+          [putMarket.toBuffer(), textEncoder.encode(seed)],
+          program.programId
+        )
+      )
+    );
+    await program.rpc.initializePutMarket(
+      optionMarket.underlyingAmountPerContract,
+      optionMarket.quoteAmountPerContract,
+      optionMarket.expirationUnixTimestamp,
+      {
+        accounts: {
+          authority: payer.publicKey,
+          underlyingAssetMint: optionMarket.underlyingAssetMint,
+          quoteAssetMint: optionMarket.quoteAssetMint,
+          feeAccount: optionMarket.exerciseFeeAccount,
+          optionMint,
+          writerTokenMint,
+          quoteAssetPool,
+          underlyingAssetPool,
+          putMarket,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [payer],
+      }
+    );
+    writerAccounts = await createMinter(
+      provider.connection,
+      writer,
+      mintAuthority,
+      underlyingToken,
+      optionMarket.underlyingAmountPerContract.toNumber(),
+      optionMint,
+      writerTokenMint,
+      quoteToken,
+      optionMarket.quoteAmountPerContract
+        .add(feeAmountPerContract(optionMarket.quoteAmountPerContract))
+        .toNumber()
+    );
+  });
+
+  it("should lock the strike in quote and take the fee on it", async () => {
+    const feeBefore = await provider.connection.getTokenAccountBalance(
+      optionMarket.exerciseFeeAccount
+    );
+    await program.rpc.mintPut(new anchor.BN(1), {
+      accounts: {
+        userAuthority: writer.publicKey,
+        putMarket,
+        quoteAssetSrc: writerAccounts.quoteAccount.publicKey,
+        quoteAssetPool,
+        optionMint,
+        mintedOptionDest: writerAccounts.optionAccount.publicKey,
+        writerTokenMint,
+        mintedWriterTokenDest: writerAccounts.writerTokenAccount.publicKey,
+        feeAccount: optionMarket.exerciseFeeAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      },
+      signers: [writer],
+    });
+    const pool = await provider.connection.getTokenAccountBalance(
+      quoteAssetPool
+    );
+    assert.equal(
+      pool.value.amount,
+      optionMarket.quoteAmountPerContract.toString()
+    );
+    const feeAfter = await provider.connection.getTokenAccountBalance(
+      optionMarket.exerciseFeeAccount
+    );
+    assert.equal(
+      new anchor.BN(feeAfter.value.amount)
+        .sub(new anchor.BN(feeBefore.value.amount))
+        .toString(),
+      feeAmountPerContract(optionMarket.quoteAmountPerContract).toString()
+    );
+  });
+
+  it("should exchange the underlying for the strike on exercise", async () => {
+    await program.rpc.exercisePut(new anchor.BN(1), {
+      accounts: {
+        userAuthority: writer.publicKey,
+        putMarket,
+        optionMint,
+        exerciserOptionTokenSrc: writerAccounts.optionAccount.publicKey,
+        underlyingAssetSrc: writerAccounts.underlyingAccount.publicKey,
+        underlyingAssetPool,
+        quoteAssetPool,
+        quoteAssetDest: writerAccounts.quoteAccount.publicKey,
+        feeAccount: optionMarket.exerciseFeeAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [writer],
+    });
+    const underlyingPool = await provider.connection.getTokenAccountBalance(
+      underlyingAssetPool
+    );
+    assert.equal(
+      underlyingPool.value.amount,
+      optionMarket.underlyingAmountPerContract.toString()
+    );
+    const quotePool = await provider.connection.getTokenAccountBalance(
+      quoteAssetPool
+    );
+    assert.equal(quotePool.value.amount, "0");
+  });
+
+  it("should name the put mints with a P", async () => {
+    const accounts = await mintMetadataAccounts(program.programId, {
+      underlyingAssetMint: optionMarket.underlyingAssetMint,
+      quoteAssetMint: optionMarket.quoteAssetMint,
+      optionMint,
+      writerTokenMint,
+    });
+    await program.rpc.initPutMintMetadata({
+      accounts: {
+        payer: payer.publicKey,
+        putMarket,
+        ...accounts,
+        systemProgram: SystemProgram.programId,
+      },
+      signers: [payer],
+    });
+    const option = await program.account.mintMetadata.fetch(
+      accounts.optionMetadata
+    );
+    // The test mints have 0 decimals, 50 quote per 10 underlying
+    assert.ok(/^\S{4}-\S{4} 5P \d{4}-\d{2}-\d{2}$/.test(option.name));
+    assert.ok(option.market.equals(putMarket));
+  });
+
+  it("should error closing before expiration", async () => {
+    try {
+      await program.rpc.closePutPostExpiration(new anchor.BN(1), {
+        accounts: {
+          userAuthority: writer.publicKey,
+          putMarket,
+          writerTokenMint,
+          writerTokenSrc: writerAccounts.writerTokenAccount.publicKey,
+          quoteAssetPool,
+          quoteAssetDest: writerAccounts.quoteAccount.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [writer],
+      });
+      assert.ok(false);
+    } catch (err) {
+      const errMsg = "OptionMarket has not expired, can't close";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
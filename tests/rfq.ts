@@ -0,0 +1,328 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token, TOKEN_PROGRAM_ID, u64 } from "@solana/spl-token";
+import {
+  AccountMeta,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+} from "@solana/web3.js";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import {
+  FEE_OWNER_KEY,
+  feeAmountPerContract,
+} from "../packages/psyoptions-ts/src/fees";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { PsyAmerican } from "../target/types/psy_american";
+
+const deriveRfqEscrow = async (
+  programId: PublicKey,
+  trade: PublicKey,
+  legIndex: number,
+  depositor: PublicKey
+) => {
+  const [escrow] = await PublicKey.findProgramAddress(
+    [
+      trade.toBuffer(),
+      Buffer.from([legIndex]),
+      depositor.toBuffer(),
+      new TextEncoder().encode("rfqEscrow"),
+    ],
+    programId
+  );
+  const [escrowVault] = await PublicKey.findProgramAddress(
+    [escrow.toBuffer(), new TextEncoder().encode("rfqEscrowVault")],
+    programId
+  );
+  return { escrow, escrowVault };
+};
+
+describe("RFQ instrument adapter", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  // Stands in for the RFQ program's PDA
+  const settlementAuthority = anchor.web3.Keypair.generate();
+  const seller = anchor.web3.Keypair.generate();
+  const buyer = anchor.web3.Keypair.generate();
+
+  let underlyingToken: Token;
+  let optionToken: Token;
+  let optionMarket: OptionMarketV2;
+  let remainingAccounts: AccountMeta[] = [];
+  let sellerUnderlying: Keypair;
+  let sellerWriterTokens: Keypair;
+  let buyerOptions: Keypair;
+  const size = new u64(2);
+
+  before(async () => {
+    await Promise.all(
+      [payer, seller, buyer].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    let quoteToken: Token;
+    let instructions;
+    ({
+      quoteToken,
+      underlyingToken,
+      optionToken,
+      optionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    ({
+      underlyingAccount: sellerUnderlying,
+      writerTokenAccount: sellerWriterTokens,
+    } = await createMinter(
+      provider.connection,
+      seller,
+      mintAuthority,
+      underlyingToken,
+      size.mul(optionMarket.underlyingAmountPerContract).muln(5).toNumber(),
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    ));
+    ({ optionAccount: buyerOptions } = await createMinter(
+      provider.connection,
+      buyer,
+      mintAuthority,
+      underlyingToken,
+      0,
+      optionMarket.optionMint,
+      optionMarket.writerTokenMint,
+      quoteToken
+    ));
+  });
+
+  const collateralLeg = () => ({
+    optionMarket: optionMarket.key,
+    asset: { collateral: {} },
+    amount: size,
+  });
+
+  const prepareCollateral = async (trade: PublicKey) => {
+    const { escrow, escrowVault } = await deriveRfqEscrow(
+      program.programId,
+      trade,
+      0,
+      seller.publicKey
+    );
+    await program.rpc.prepareRfqSettlement(
+      0,
+      collateralLeg(),
+      buyer.publicKey,
+      {
+        accounts: {
+          depositor: seller.publicKey,
+          settlementAuthority: settlementAuthority.publicKey,
+          trade,
+          optionMarket: optionMarket.key,
+          escrowMint: optionMarket.underlyingAssetMint,
+          depositorSrc: sellerUnderlying.publicKey,
+          escrow,
+          escrowVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+        signers: [seller, settlementAuthority],
+      }
+    );
+    return { escrow, escrowVault };
+  };
+
+  describe("validateRfqLeg", () => {
+    it("should validate a leg of a real OptionMarket", async () => {
+      await program.rpc.validateRfqLeg(collateralLeg(), {
+        accounts: { optionMarket: optionMarket.key },
+      });
+    });
+
+    it("should error when the leg references another OptionMarket", async () => {
+      try {
+        await program.rpc.validateRfqLeg(
+          { ...collateralLeg(), optionMarket: new Keypair().publicKey },
+          { accounts: { optionMarket: optionMarket.key } }
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "RFQ leg does not reference this OptionMarket";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("settleRfqLeg", () => {
+    it("should mint the leg to the counterparty from the locked collateral", async () => {
+      const mintFeeBefore = await underlyingToken.getAccountInfo(
+        optionMarket.mintFeeAccount
+      );
+      const { escrow, escrowVault } = await prepareCollateral(
+        new Keypair().publicKey
+      );
+      await program.rpc.settleRfqLeg({
+        accounts: {
+          settlementAuthority: settlementAuthority.publicKey,
+          depositor: seller.publicKey,
+          escrow,
+          escrowVault,
+          optionMarket: optionMarket.key,
+          optionMint: optionMarket.optionMint,
+          writerTokenMint: optionMarket.writerTokenMint,
+          underlyingAssetPool: optionMarket.underlyingAssetPool,
+          counterpartyOptionDest: buyerOptions.publicKey,
+          depositorWriterTokenDest: sellerWriterTokens.publicKey,
+          feeOwner: FEE_OWNER_KEY,
+          feePayer: payer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        },
+        remainingAccounts,
+        signers: [settlementAuthority, payer],
+      });
+      const buyerOptionAcct = await optionToken.getAccountInfo(
+        buyerOptions.publicKey
+      );
+      assert.equal(buyerOptionAcct.amount.toString(), size.toString());
+      const mintFeeAcct = await underlyingToken.getAccountInfo(
+        optionMarket.mintFeeAccount
+      );
+      assert.equal(
+        mintFeeAcct.amount.sub(mintFeeBefore.amount).toString(),
+        feeAmountPerContract(optionMarket.underlyingAmountPerContract)
+          .mul(size)
+          .toString()
+      );
+      const escrowInfo = await provider.connection.getAccountInfo(escrow);
+      assert.equal(escrowInfo, null);
+    });
+  });
+
+  describe("prepareRfqSettlementWithMint", () => {
+    it("should mint the OptionTokens into escrow and the WriterTokens to the seller", async () => {
+      const writerToken = new Token(
+        provider.connection,
+        optionMarket.writerTokenMint,
+        TOKEN_PROGRAM_ID,
+        payer
+      );
+      const writerTokensBefore = await writerToken.getAccountInfo(
+        sellerWriterTokens.publicKey
+      );
+      const trade = new Keypair().publicKey;
+      const { escrow, escrowVault } = await deriveRfqEscrow(
+        program.programId,
+        trade,
+        0,
+        seller.publicKey
+      );
+      await program.rpc.prepareRfqSettlementWithMint(
+        0,
+        {
+          optionMarket: optionMarket.key,
+          asset: { optionToken: {} },
+          amount: size,
+        },
+        buyer.publicKey,
+        {
+          accounts: {
+            depositor: seller.publicKey,
+            settlementAuthority: settlementAuthority.publicKey,
+            trade,
+            optionMarket: optionMarket.key,
+            underlyingAssetSrc: sellerUnderlying.publicKey,
+            underlyingAssetPool: optionMarket.underlyingAssetPool,
+            optionMint: optionMarket.optionMint,
+            writerTokenMint: optionMarket.writerTokenMint,
+            mintedWriterTokenDest: sellerWriterTokens.publicKey,
+            escrow,
+            escrowVault,
+            feeOwner: FEE_OWNER_KEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+          },
+          remainingAccounts,
+          signers: [seller, settlementAuthority],
+        }
+      );
+      const escrowVaultAcct = await optionToken.getAccountInfo(escrowVault);
+      assert.equal(escrowVaultAcct.amount.toString(), size.toString());
+      const writerTokensAfter = await writerToken.getAccountInfo(
+        sellerWriterTokens.publicKey
+      );
+      assert.equal(
+        writerTokensAfter.amount.sub(writerTokensBefore.amount).toString(),
+        size.toString()
+      );
+    });
+  });
+
+  describe("revertRfqLeg", () => {
+    it("should return the locked collateral to the depositor", async () => {
+      const before = await underlyingToken.getAccountInfo(
+        sellerUnderlying.publicKey
+      );
+      const { escrow, escrowVault } = await prepareCollateral(
+        new Keypair().publicKey
+      );
+      await program.rpc.revertRfqLeg({
+        accounts: {
+          settlementAuthority: settlementAuthority.publicKey,
+          depositor: seller.publicKey,
+          escrow,
+          escrowVault,
+          depositorDest: sellerUnderlying.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [settlementAuthority],
+      });
+      const after = await underlyingToken.getAccountInfo(
+        sellerUnderlying.publicKey
+      );
+      assert.equal(after.amount.toString(), before.amount.toString());
+    });
+
+    it("should error when the signer is not the settlement authority", async () => {
+      const { escrow, escrowVault } = await prepareCollateral(
+        new Keypair().publicKey
+      );
+      try {
+        await program.rpc.revertRfqLeg({
+          accounts: {
+            settlementAuthority: seller.publicKey,
+            depositor: seller.publicKey,
+            escrow,
+            escrowVault,
+            depositorDest: sellerUnderlying.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [seller],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the escrow's settlement authority";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
@@ -0,0 +1,110 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { createMinter, initOptionMarket, initSetup } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("rollPosition", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const writer = anchor.web3.Keypair.generate();
+
+  let oldMarket: OptionMarketV2;
+  let newMarket: OptionMarketV2;
+  let oldAccounts: { optionAccount: Keypair; writerTokenAccount: Keypair };
+  let newAccounts: { optionAccount: Keypair; writerTokenAccount: Keypair };
+
+  const rollPosition = (from: OptionMarketV2, to: OptionMarketV2) =>
+    program.rpc.rollPosition(new anchor.BN(1), {
+      accounts: {
+        userAuthority: writer.publicKey,
+        oldOptionMarket: from.key,
+        oldOptionMint: from.optionMint,
+        oldOptionTokenSrc: oldAccounts.optionAccount.publicKey,
+        oldWriterTokenMint: from.writerTokenMint,
+        oldWriterTokenSrc: oldAccounts.writerTokenAccount.publicKey,
+        oldUnderlyingAssetPool: from.underlyingAssetPool,
+        newOptionMarket: to.key,
+        newOptionMint: to.optionMint,
+        mintedOptionDest: newAccounts.optionAccount.publicKey,
+        newWriterTokenMint: to.writerTokenMint,
+        mintedWriterTokenDest: newAccounts.writerTokenAccount.publicKey,
+        newUnderlyingAssetPool: to.underlyingAssetPool,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [writer],
+    });
+
+  before(async () => {
+    await Promise.all(
+      [payer, writer].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(
+            keypair.publicKey,
+            10_000_000_000
+          ),
+          "confirmed"
+        )
+      )
+    );
+    const markets = [];
+    for (let i = 0; i < 2; i++) {
+      const {
+        quoteToken,
+        underlyingToken,
+        optionMarket,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program);
+      await initOptionMarket(
+        program,
+        payer,
+        optionMarket,
+        remainingAccounts,
+        instructions
+      );
+      const accounts = await createMinter(
+        provider.connection,
+        writer,
+        mintAuthority,
+        underlyingToken,
+        0,
+        optionMarket.optionMint,
+        optionMarket.writerTokenMint,
+        quoteToken
+      );
+      markets.push({ optionMarket, accounts });
+    }
+    [
+      { optionMarket: oldMarket, accounts: oldAccounts },
+      { optionMarket: newMarket, accounts: newAccounts },
+    ] = markets;
+  });
+
+  it("should error rolling to a market with a different underlying", async () => {
+    try {
+      await rollPosition(oldMarket, newMarket);
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Positions can only roll between markets with the same underlying and contract size";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  it("should error rolling to the same market", async () => {
+    try {
+      await rollPosition(oldMarket, oldMarket);
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "Positions can only roll between markets with the same underlying and contract size";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+});
@@ -0,0 +1,60 @@
+/**
+ * Test that only the admin can configure the PositionLimit of an OptionMarket.
+ */
+import * as anchor from "@project-serum/anchor";
+import { assert } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import { Program } from "@project-serum/anchor";
+import { initOptionMarket, initSetup } from "../../utils/helpers";
+import { OptionMarketV2 } from "../../packages/psyoptions-ts/src/types";
+import { derivePositionLimit } from "../../utils/serum";
+import { PsyAmerican } from "../../target/types/psy_american";
+
+describe("Serum position limits", () => {
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+
+  const mintAuthority = anchor.web3.Keypair.generate();
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    const {
+      optionMarket: newOptionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, wallet.payer, mintAuthority, program);
+    optionMarket = newOptionMarket;
+    await initOptionMarket(
+      program,
+      wallet.payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  describe("initPositionLimit", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [positionLimit] = await derivePositionLimit(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.initPositionLimit(new anchor.BN(100), {
+          accounts: {
+            admin: wallet.publicKey,
+            optionMarket: optionMarket.key,
+            positionLimit,
+            systemProgram: SystemProgram.programId,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as anchor.AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
@@ -0,0 +1,92 @@
+/**
+ * Test that only the admin can configure the oracle and PriceBand of an OptionMarket.
+ */
+import * as anchor from "@project-serum/anchor";
+import { assert } from "chai";
+import { SystemProgram } from "@solana/web3.js";
+import { Program } from "@project-serum/anchor";
+import { initOptionMarket, initSetup } from "../../utils/helpers";
+import { OptionMarketV2 } from "../../packages/psyoptions-ts/src/types";
+import { deriveOracleConfig, derivePriceBand } from "../../utils/serum";
+import { PsyAmerican } from "../../target/types/psy_american";
+
+describe("Serum price bands", () => {
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const oracle = anchor.web3.Keypair.generate();
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    const {
+      optionMarket: newOptionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, wallet.payer, mintAuthority, program);
+    optionMarket = newOptionMarket;
+    await initOptionMarket(
+      program,
+      wallet.payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  describe("initOracleConfig", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [oracleConfig] = await deriveOracleConfig(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.initOracleConfig({ pyth: {} }, new anchor.BN(60), {
+          accounts: {
+            admin: wallet.publicKey,
+            optionMarket: optionMarket.key,
+            underlyingAssetMint: optionMarket.underlyingAssetMint,
+            quoteAssetMint: optionMarket.quoteAssetMint,
+            oracle: oracle.publicKey,
+            oracleConfig,
+            systemProgram: SystemProgram.programId,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as anchor.AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("initPriceBand", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [oracleConfig] = await deriveOracleConfig(
+        program.programId,
+        optionMarket.key
+      );
+      const [priceBand] = await derivePriceBand(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.initPriceBand(100, new anchor.BN(1_000_000), {
+          accounts: {
+            admin: wallet.publicKey,
+            optionMarket: optionMarket.key,
+            oracleConfig,
+            priceBand,
+            systemProgram: SystemProgram.programId,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as anchor.AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
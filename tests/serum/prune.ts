@@ -13,6 +13,7 @@ import {
   initMarket,
   marketLoader,
   openOrdersSeed,
+  toAdminPrune,
 } from "../../utils/serum";
 import { MarketProxy, OpenOrders } from "@project-serum/serum";
 import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
@@ -203,6 +204,30 @@ describe("Serum Prune", () => {
       l2 = await bids.getL2(3);
       assert.equal(l2.length, 3);
     });
+
+    it("should error when the admin prune is not signed by the admin", async () => {
+      const tx = new anchor.web3.Transaction();
+      tx.add(
+        toAdminPrune(
+          await marketProxy.instruction.prune(openOrdersKey, marketAuthority),
+          optionMarket.key,
+          wallet.publicKey
+        )
+      );
+      try {
+        await provider.sendAndConfirm!(tx);
+        assert.ok(false);
+      } catch (err) {
+        const programError = parseTransactionError(err);
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal(programError.msg, errMsg);
+      }
+
+      // Assert that the order book has not changed
+      const bids = await marketProxy.market.loadBids(provider.connection);
+      const l2 = await bids.getL2(3);
+      assert.equal(l2.length, 3);
+    });
   });
 
   describe("option market is expired", () => {
@@ -0,0 +1,163 @@
+/**
+ * Test that referral rebates can only be routed to the recipients allowed by the
+ * market's ReferralConfig.
+ */
+import * as anchor from "@project-serum/anchor";
+import { assert } from "chai";
+import { Keypair, PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { MarketProxy } from "@project-serum/serum";
+import { Program } from "@project-serum/anchor";
+import { parseTransactionError } from "@mithraic-labs/psy-american";
+import { initOptionMarket, initSetup } from "../../utils/helpers";
+import { OptionMarketV2 } from "../../packages/psyoptions-ts/src/types";
+import {
+  createMintAndVault,
+  deriveReferralConfig,
+  DEX_PID,
+  getMarketAndAuthorityInfo,
+  initMarket,
+  marketLoader,
+  openOrdersSeed,
+} from "../../utils/serum";
+import { PsyAmerican } from "../../target/types/psy_american";
+
+describe("Serum referral", () => {
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+
+  const mintAuthority = anchor.web3.Keypair.generate();
+  let optionMarket: OptionMarketV2;
+  let marketProxy: MarketProxy,
+    marketAuthorityBump: number,
+    usdcMint: PublicKey,
+    usdcAccount: PublicKey,
+    usdcToken: Token,
+    openOrdersKey: PublicKey,
+    optionAccount: PublicKey;
+
+  before(async () => {
+    const {
+      optionMarket: newOptionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, wallet.payer, mintAuthority, program);
+    optionMarket = newOptionMarket;
+    await initOptionMarket(
+      program,
+      wallet.payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    [usdcMint, usdcAccount] = await createMintAndVault(
+      provider,
+      new anchor.BN("1000000000000000000"),
+      undefined,
+      6
+    );
+    ({ marketAuthorityBump } = await getMarketAndAuthorityInfo(
+      program,
+      optionMarket,
+      DEX_PID,
+      usdcMint
+    ));
+    ({ marketA: marketProxy } = await initMarket(
+      provider,
+      program,
+      marketLoader(provider, program, optionMarket.key, marketAuthorityBump),
+      optionMarket,
+      usdcMint
+    ));
+    usdcToken = new Token(
+      provider.connection,
+      usdcMint,
+      TOKEN_PROGRAM_ID,
+      wallet.payer
+    );
+    const optionToken = new Token(
+      provider.connection,
+      optionMarket.optionMint,
+      TOKEN_PROGRAM_ID,
+      wallet.payer
+    );
+    optionAccount = await optionToken.createAssociatedTokenAccount(
+      wallet.publicKey
+    );
+    [openOrdersKey] = await PublicKey.findProgramAddress(
+      [
+        openOrdersSeed,
+        DEX_PID.toBuffer(),
+        marketProxy.market.address.toBuffer(),
+        wallet.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const dummy = new Keypair();
+    const tx = new Transaction();
+    tx.add(
+      await marketProxy.instruction.initOpenOrders(
+        wallet.publicKey,
+        marketProxy.market.address,
+        dummy.publicKey,
+        dummy.publicKey
+      )
+    );
+    await provider.sendAndConfirm!(tx);
+  });
+
+  describe("initReferralConfig", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [referralConfig] = await deriveReferralConfig(
+        program.programId,
+        marketProxy.market.address
+      );
+      try {
+        await program.rpc.initReferralConfig(
+          { fixed: { recipient: wallet.publicKey } },
+          {
+            accounts: {
+              admin: wallet.publicKey,
+              optionMarket: optionMarket.key,
+              pcMint: usdcMint,
+              serumMarket: marketProxy.market.address,
+              referralConfig,
+              systemProgram: SystemProgram.programId,
+            },
+          }
+        );
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as anchor.AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("Market without a ReferralConfig", () => {
+    it("should error when the referral is not owned by the fee owner", async () => {
+      const referral = await usdcToken.createAccount(wallet.publicKey);
+      const tx = new Transaction();
+      tx.add(
+        await marketProxy.instruction.settleFunds(
+          openOrdersKey,
+          wallet.publicKey,
+          optionAccount,
+          usdcAccount,
+          referral
+        )
+      );
+      try {
+        await provider.sendAndConfirm!(tx);
+        assert.ok(false);
+      } catch (err) {
+        const programError = parseTransactionError(err);
+        const errMsg =
+          "Referral account is not an allowed recipient for this market";
+        assert.equal(programError.msg, errMsg);
+      }
+    });
+  });
+});
@@ -0,0 +1,150 @@
+/**
+ * Test that only the admin can restrict who trades a Serum market, and that markets
+ * without a TradingAllowlist can be traded by anyone.
+ */
+import * as anchor from "@project-serum/anchor";
+import { assert } from "chai";
+import { Keypair, PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
+import { MarketProxy, OpenOrders } from "@project-serum/serum";
+import { Program } from "@project-serum/anchor";
+import { initOptionMarket, initSetup } from "../../utils/helpers";
+import { OptionMarketV2 } from "../../packages/psyoptions-ts/src/types";
+import {
+  createMintAndVault,
+  deriveTradingAllowlist,
+  DEX_PID,
+  getMarketAndAuthorityInfo,
+  initMarket,
+  marketLoader,
+  openOrdersSeed,
+} from "../../utils/serum";
+import { PsyAmerican } from "../../target/types/psy_american";
+
+describe("Serum trading allowlist", () => {
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+
+  const mintAuthority = anchor.web3.Keypair.generate();
+  let optionMarket: OptionMarketV2;
+  let marketProxy: MarketProxy,
+    marketAuthorityBump: number,
+    usdcMint: PublicKey;
+
+  before(async () => {
+    const {
+      optionMarket: newOptionMarket,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, wallet.payer, mintAuthority, program);
+    optionMarket = newOptionMarket;
+    await initOptionMarket(
+      program,
+      wallet.payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+    [usdcMint] = await createMintAndVault(
+      provider,
+      new anchor.BN("1000000000000000000"),
+      undefined,
+      6
+    );
+    ({ marketAuthorityBump } = await getMarketAndAuthorityInfo(
+      program,
+      optionMarket,
+      DEX_PID,
+      usdcMint
+    ));
+    ({ marketA: marketProxy } = await initMarket(
+      provider,
+      program,
+      marketLoader(provider, program, optionMarket.key, marketAuthorityBump),
+      optionMarket,
+      usdcMint
+    ));
+  });
+
+  describe("initTradingAllowlist", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [tradingAllowlist] = await deriveTradingAllowlist(
+        program.programId,
+        marketProxy.market.address
+      );
+      try {
+        await program.rpc.initTradingAllowlist({
+          accounts: {
+            admin: wallet.publicKey,
+            optionMarket: optionMarket.key,
+            pcMint: usdcMint,
+            serumMarket: marketProxy.market.address,
+            tradingAllowlist,
+            systemProgram: SystemProgram.programId,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as anchor.AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("Market without a TradingAllowlist", () => {
+    it("should let any wallet init OpenOrders", async () => {
+      const [openOrdersKey] = await PublicKey.findProgramAddress(
+        [
+          openOrdersSeed,
+          DEX_PID.toBuffer(),
+          marketProxy.market.address.toBuffer(),
+          wallet.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      const dummy = new Keypair();
+      const tx = new Transaction();
+      tx.add(
+        await marketProxy.instruction.initOpenOrders(
+          wallet.publicKey,
+          marketProxy.market.address,
+          dummy.publicKey,
+          dummy.publicKey
+        )
+      );
+      await provider.sendAndConfirm!(tx);
+
+      const openOrders = await OpenOrders.load(
+        provider.connection,
+        openOrdersKey,
+        DEX_PID
+      );
+      assert.ok(openOrders.owner.equals(openOrdersKey));
+    });
+
+    it("should error when the client leaves out the TradingAllowlist", async () => {
+      const [tradingAllowlist] = await deriveTradingAllowlist(
+        program.programId,
+        marketProxy.market.address
+      );
+      const trader = new Keypair();
+      const dummy = new Keypair();
+      const ix = await marketProxy.instruction.initOpenOrders(
+        trader.publicKey,
+        marketProxy.market.address,
+        dummy.publicKey,
+        dummy.publicKey
+      );
+      ix.keys = ix.keys.filter((key) => !key.pubkey.equals(tradingAllowlist));
+      let sent = false;
+      try {
+        await provider.sendAndConfirm!(new Transaction().add(ix), [trader]);
+        sent = true;
+      } catch (err) {
+        // The allowlist slot is required even though this market has no allowlist
+      }
+      assert.ok(!sent, "initOpenOrders without the TradingAllowlist should fail");
+    });
+  });
+});
@@ -0,0 +1,146 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { AnchorError, Program } from "@project-serum/anchor";
+import { initOptionMarket, initSetup, wait } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import {
+  createPythPrice,
+  initPythOracleConfig,
+  loadAdmin,
+  setPythPrice,
+} from "../utils/oracle";
+import { deriveOracleConfig } from "../utils/serum";
+import { MockPyth } from "../target/types/mock_pyth";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("recordSettlementPrice", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    let remainingAccounts, instructions;
+    ({ optionMarket, remainingAccounts, instructions } = await initSetup(
+      provider,
+      payer,
+      mintAuthority,
+      program
+    ));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  it("should start unrecorded", async () => {
+    const market = await program.account.optionMarket.fetch(optionMarket.key);
+    assert.equal(market.settlementSlot.toString(), "0");
+    assert.equal(market.settlementPrice.toString(), "0");
+  });
+
+  it("should error when the OptionMarket has no OracleConfig", async () => {
+    const [oracleConfig] = await deriveOracleConfig(
+      program.programId,
+      optionMarket.key
+    );
+    try {
+      await program.rpc.recordSettlementPrice({
+        accounts: {
+          optionMarket: optionMarket.key,
+          oracleConfig,
+          oracle: SystemProgram.programId,
+        },
+      });
+      assert.ok(false);
+    } catch (err) {
+      const errMsg =
+        "The program expected this account to be already initialized";
+      assert.equal((err as AnchorError).error.errorMessage, errMsg);
+    }
+  });
+
+  describe("at expiration", () => {
+    const mockPyth = anchor.workspace.MockPyth as Program<MockPyth>;
+    const now = () => new anchor.BN(Math.floor(Date.now() / 1000));
+
+    let admin: Keypair;
+    let market: OptionMarketV2;
+    let oracle: PublicKey;
+    let oracleConfig: PublicKey;
+
+    const recordSettlementPrice = () =>
+      program.rpc.recordSettlementPrice({
+        accounts: {
+          optionMarket: market.key,
+          oracleConfig,
+          oracle,
+        },
+      });
+
+    before(async () => {
+      admin = await loadAdmin(provider);
+      let remainingAccounts, instructions;
+      ({
+        optionMarket: market,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program, {
+        expiration: now().addn(4),
+      }));
+      await initOptionMarket(
+        program,
+        payer,
+        market,
+        remainingAccounts,
+        instructions
+      );
+      oracle = await createPythPrice(mockPyth, payer, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: now(),
+      });
+      oracleConfig = await initPythOracleConfig(program, admin, market, oracle);
+      // Wait so the market is expired
+      await wait(6000);
+      // The keeper records the first price published at expiration
+      await setPythPrice(mockPyth, oracle, {
+        price: new anchor.BN(6),
+        expo: 0,
+        publishTime: market.expirationUnixTimestamp,
+      });
+    });
+
+    it("should record the oracle price of a contract", async () => {
+      await recordSettlementPrice();
+      const record = await program.account.optionMarket.fetch(market.key);
+      // 6 quote per underlying, the test mints have 0 decimals
+      assert.equal(
+        record.settlementPrice.toString(),
+        market.underlyingAmountPerContract.muln(6).toString()
+      );
+      assert.ok(record.settlementOracle.equals(oracle));
+      assert.ok(record.settlementSlot.gtn(0));
+    });
+
+    it("should error when the price is already recorded", async () => {
+      try {
+        await recordSettlementPrice();
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Settlement price has already been recorded";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
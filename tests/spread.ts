@@ -0,0 +1,337 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+} from "@solana/web3.js";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { AnchorError, Program } from "@project-serum/anchor";
+import {
+  createMinter,
+  initNewTokenAccount,
+  initOptionMarket,
+  initSetup,
+} from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { mintOptionsTx } from "../packages/psyoptions-ts/src";
+import { FEE_OWNER_KEY } from "../packages/psyoptions-ts/src/fees";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Vertical spreads", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+  const textEncoder = new TextEncoder();
+  const size = new anchor.BN(2);
+
+  let underlyingToken: Token;
+  let quoteToken: Token;
+  let longMarket: OptionMarketV2;
+  let shortMarket: OptionMarketV2;
+  let otherMarket: OptionMarketV2;
+  let longOptionSrc: Keypair;
+  let quoteAssetSrc: Keypair;
+  let underlyingAssetSrc: Keypair;
+  let shortOptionDest: Keypair;
+  let spreadPosition: PublicKey;
+  let longOptionVault: PublicKey;
+  let quoteVault: PublicKey;
+
+  const deriveSpreadPosition = async (
+    long: OptionMarketV2,
+    short: OptionMarketV2
+  ) => {
+    const [spreadPosition] = await PublicKey.findProgramAddress(
+      [
+        payer.publicKey.toBuffer(),
+        long.key.toBuffer(),
+        short.key.toBuffer(),
+        textEncoder.encode("spreadPosition"),
+      ],
+      program.programId
+    );
+    const [longOptionVault] = await PublicKey.findProgramAddress(
+      [spreadPosition.toBuffer(), textEncoder.encode("longOptionVault")],
+      program.programId
+    );
+    const [quoteVault] = await PublicKey.findProgramAddress(
+      [spreadPosition.toBuffer(), textEncoder.encode("quoteVault")],
+      program.programId
+    );
+    return { spreadPosition, longOptionVault, quoteVault };
+  };
+
+  const initSpreadPosition = async (
+    long: OptionMarketV2,
+    short: OptionMarketV2
+  ) => {
+    const accounts = await deriveSpreadPosition(long, short);
+    await program.rpc.initSpreadPosition({
+      accounts: {
+        owner: payer.publicKey,
+        longMarket: long.key,
+        shortMarket: short.key,
+        longOptionMint: long.optionMint,
+        quoteAssetMint: long.quoteAssetMint,
+        ...accounts,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      },
+      signers: [payer],
+    });
+    return accounts;
+  };
+
+  const tokenBalance = async (account: PublicKey) =>
+    (await provider.connection.getTokenAccountBalance(account)).value.amount;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    let remainingAccounts, instructions;
+    ({
+      optionMarket: longMarket,
+      underlyingToken,
+      quoteToken,
+      remainingAccounts,
+      instructions,
+    } = await initSetup(provider, payer, mintAuthority, program));
+    await initOptionMarket(
+      program,
+      payer,
+      longMarket,
+      remainingAccounts,
+      instructions
+    );
+    ({ optionMarket: shortMarket, remainingAccounts, instructions } =
+      await initSetup(provider, payer, mintAuthority, program, {
+        underlyingToken,
+        quoteToken,
+        quoteAmountPerContract: new anchor.BN("60000000000"),
+        expiration: longMarket.expirationUnixTimestamp,
+      }));
+    await initOptionMarket(
+      program,
+      payer,
+      shortMarket,
+      remainingAccounts,
+      instructions
+    );
+    ({ optionMarket: otherMarket, remainingAccounts, instructions } =
+      await initSetup(provider, payer, mintAuthority, program, {
+        quoteAmountPerContract: new anchor.BN("60000000000"),
+      }));
+    await initOptionMarket(
+      program,
+      payer,
+      otherMarket,
+      remainingAccounts,
+      instructions
+    );
+
+    // Write the long options, keeping underlying for the short leg's mint fee and
+    // quote to exercise the short options
+    let writerTokenAccount: Keypair;
+    ({
+      optionAccount: longOptionSrc,
+      quoteAccount: quoteAssetSrc,
+      underlyingAccount: underlyingAssetSrc,
+      writerTokenAccount,
+    } = await createMinter(
+      provider.connection,
+      payer,
+      mintAuthority,
+      underlyingToken,
+      size.muln(2).mul(longMarket.underlyingAmountPerContract).toNumber(),
+      longMarket.optionMint,
+      longMarket.writerTokenMint,
+      quoteToken,
+      size.mul(shortMarket.quoteAmountPerContract).toNumber()
+    ));
+    await mintOptionsTx(
+      program,
+      payer,
+      longOptionSrc,
+      writerTokenAccount,
+      underlyingAssetSrc,
+      size,
+      longMarket
+    );
+    ({ tokenAccount: shortOptionDest } = await initNewTokenAccount(
+      provider.connection,
+      payer.publicKey,
+      shortMarket.optionMint,
+      payer
+    ));
+  });
+
+  describe("initSpreadPosition", () => {
+    it("should error when the markets have different underlying assets", async () => {
+      try {
+        await initSpreadPosition(longMarket, otherMarket);
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "Spread markets must share the underlying, quote, contract size and expiration";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("openSpread", () => {
+    before(async () => {
+      ({ spreadPosition, longOptionVault, quoteVault } =
+        await initSpreadPosition(longMarket, shortMarket));
+    });
+
+    it("should escrow the long options and mint the short options without underlying", async () => {
+      const shortPoolBefore = await tokenBalance(
+        shortMarket.underlyingAssetPool
+      );
+      const [longKnockOutBarrier] = await PublicKey.findProgramAddress(
+        [longMarket.key.toBuffer(), textEncoder.encode("knockOutBarrier")],
+        program.programId
+      );
+      await program.rpc.openSpread(size, {
+        accounts: {
+          owner: payer.publicKey,
+          spreadPosition,
+          longMarket: longMarket.key,
+          shortMarket: shortMarket.key,
+          longKnockOutBarrier,
+          longOptionSrc: longOptionSrc.publicKey,
+          longOptionVault,
+          quoteAssetSrc: quoteAssetSrc.publicKey,
+          quoteVault,
+          underlyingAssetSrc: underlyingAssetSrc.publicKey,
+          shortOptionMint: shortMarket.optionMint,
+          mintedOptionDest: shortOptionDest.publicKey,
+          feeOwner: FEE_OWNER_KEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        },
+        remainingAccounts: [
+          {
+            pubkey: shortMarket.mintFeeAccount,
+            isWritable: true,
+            isSigner: false,
+          },
+        ],
+        signers: [payer],
+      });
+
+      assert.equal(await tokenBalance(longOptionSrc.publicKey), "0");
+      assert.equal(await tokenBalance(longOptionVault), size.toString());
+      assert.equal(
+        await tokenBalance(shortOptionDest.publicKey),
+        size.toString()
+      );
+      // The long strike is lower, so no quote collateral is locked
+      assert.equal(await tokenBalance(quoteVault), "0");
+      assert.equal(
+        await tokenBalance(shortMarket.underlyingAssetPool),
+        shortPoolBefore
+      );
+      const position = await program.account.spreadPosition.fetch(
+        spreadPosition
+      );
+      assert.equal(position.written.toString(), size.toString());
+    });
+  });
+
+  describe("exerciseSpread", () => {
+    it("should exercise the escrowed long options to deliver the underlying", async () => {
+      const exerciseSize = new anchor.BN(1);
+      const underlyingBefore = new anchor.BN(
+        await tokenBalance(underlyingAssetSrc.publicKey)
+      );
+      const longQuotePoolBefore = new anchor.BN(
+        await tokenBalance(longMarket.quoteAssetPool)
+      );
+      await program.rpc.exerciseSpread(exerciseSize, {
+        accounts: {
+          userAuthority: payer.publicKey,
+          spreadPosition,
+          longMarket: longMarket.key,
+          shortMarket: shortMarket.key,
+          shortOptionMint: shortMarket.optionMint,
+          exerciserOptionTokenSrc: shortOptionDest.publicKey,
+          quoteAssetSrc: quoteAssetSrc.publicKey,
+          quoteVault,
+          longOptionMint: longMarket.optionMint,
+          longOptionVault,
+          longQuoteAssetPool: longMarket.quoteAssetPool,
+          longUnderlyingAssetPool: longMarket.underlyingAssetPool,
+          underlyingAssetDest: underlyingAssetSrc.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [payer],
+      });
+
+      assert.equal(
+        new anchor.BN(await tokenBalance(underlyingAssetSrc.publicKey))
+          .sub(underlyingBefore)
+          .toString(),
+        exerciseSize.mul(longMarket.underlyingAmountPerContract).toString()
+      );
+      assert.equal(
+        new anchor.BN(await tokenBalance(longMarket.quoteAssetPool))
+          .sub(longQuotePoolBefore)
+          .toString(),
+        exerciseSize.mul(longMarket.quoteAmountPerContract).toString()
+      );
+      // The vault keeps the difference in strikes for the owner
+      assert.equal(
+        await tokenBalance(quoteVault),
+        exerciseSize
+          .mul(
+            shortMarket.quoteAmountPerContract.sub(
+              longMarket.quoteAmountPerContract
+            )
+          )
+          .toString()
+      );
+      assert.equal(
+        await tokenBalance(longOptionVault),
+        size.sub(exerciseSize).toString()
+      );
+      const position = await program.account.spreadPosition.fetch(
+        spreadPosition
+      );
+      assert.equal(
+        position.written.toString(),
+        size.sub(exerciseSize).toString()
+      );
+    });
+  });
+
+  describe("closeSpread", () => {
+    it("should error before the short market expires", async () => {
+      try {
+        await program.rpc.closeSpread({
+          accounts: {
+            owner: payer.publicKey,
+            spreadPosition,
+            shortMarket: shortMarket.key,
+            longOptionVault,
+            longOptionDest: longOptionSrc.publicKey,
+            quoteVault,
+            quoteAssetDest: quoteAssetSrc.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [payer],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "OptionMarket has not expired, can't close";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+});
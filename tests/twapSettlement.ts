@@ -0,0 +1,219 @@
+import * as anchor from "@project-serum/anchor";
+import assert from "assert";
+import { Token } from "@solana/spl-token";
+import { Account, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { AnchorError, AnchorProvider, Program } from "@project-serum/anchor";
+import { Market } from "@project-serum/serum";
+import { initOptionMarket, initSetup, wait } from "../utils/helpers";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { loadAdmin } from "../utils/oracle";
+import { deriveTwapAccumulator, listSpotMarket } from "../utils/serum";
+import { PsyAmerican } from "../target/types/psy_american";
+
+describe("Serum TWAP settlement price", () => {
+  const payer = anchor.web3.Keypair.generate();
+  const mintAuthority = anchor.web3.Keypair.generate();
+  const program = anchor.workspace.PsyAmerican as Program<PsyAmerican>;
+  const provider = program.provider;
+
+  let optionMarket: OptionMarketV2;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(payer.publicKey, 10_000_000_000),
+      "confirmed"
+    );
+    let remainingAccounts, instructions;
+    ({ optionMarket, remainingAccounts, instructions } = await initSetup(
+      provider,
+      payer,
+      mintAuthority,
+      program
+    ));
+    await initOptionMarket(
+      program,
+      payer,
+      optionMarket,
+      remainingAccounts,
+      instructions
+    );
+  });
+
+  describe("initTwapAccumulator", () => {
+    it("should error when the signer is not the admin", async () => {
+      const [twapAccumulator] = await deriveTwapAccumulator(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.initTwapAccumulator(30, {
+          accounts: {
+            admin: payer.publicKey,
+            optionMarket: optionMarket.key,
+            serumMarket: new Keypair().publicKey,
+            eventQueue: new Keypair().publicKey,
+            twapAccumulator,
+            systemProgram: SystemProgram.programId,
+          },
+          signers: [payer],
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg = "Signer is not the PsyOptions admin";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("recordTwapSettlementPrice", () => {
+    it("should error when the OptionMarket has no TwapAccumulator", async () => {
+      const [twapAccumulator] = await deriveTwapAccumulator(
+        program.programId,
+        optionMarket.key
+      );
+      try {
+        await program.rpc.recordTwapSettlementPrice({
+          accounts: {
+            optionMarket: optionMarket.key,
+            twapAccumulator,
+          },
+        });
+        assert.ok(false);
+      } catch (err) {
+        const errMsg =
+          "The program expected this account to be already initialized";
+        assert.equal((err as AnchorError).error.errorMessage, errMsg);
+      }
+    });
+  });
+
+  describe("underlying trades in the window", () => {
+    const maker = anchor.web3.Keypair.generate();
+    const taker = anchor.web3.Keypair.generate();
+    const now = () => Math.floor(Date.now() / 1000);
+
+    let market: OptionMarketV2;
+    let serumMarket: Market;
+    let twapAccumulator: PublicKey;
+
+    before(async () => {
+      await Promise.all(
+        [maker, taker].map(async (keypair) =>
+          provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(
+              keypair.publicKey,
+              10_000_000_000
+            ),
+            "confirmed"
+          )
+        )
+      );
+      const admin = await loadAdmin(provider);
+      let underlyingToken: Token,
+        quoteToken: Token,
+        remainingAccounts,
+        instructions;
+      ({
+        optionMarket: market,
+        underlyingToken,
+        quoteToken,
+        remainingAccounts,
+        instructions,
+      } = await initSetup(provider, payer, mintAuthority, program, {
+        expiration: new anchor.BN(now() + 30),
+      }));
+      await initOptionMarket(
+        program,
+        payer,
+        market,
+        remainingAccounts,
+        instructions
+      );
+      serumMarket = await listSpotMarket(
+        provider as AnchorProvider,
+        underlyingToken.publicKey,
+        quoteToken.publicKey
+      );
+      // A one minute window covers the rest of the market's life
+      [twapAccumulator] = await deriveTwapAccumulator(
+        program.programId,
+        market.key
+      );
+      await program.rpc.initTwapAccumulator(1, {
+        accounts: {
+          admin: admin.publicKey,
+          optionMarket: market.key,
+          serumMarket: serumMarket.address,
+          eventQueue: serumMarket.decoded.eventQueue,
+          twapAccumulator,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [admin],
+      });
+
+      // The taker's bid crosses the maker's ask at 6 quote per underlying
+      const makerUnderlying = await underlyingToken.createAccount(
+        maker.publicKey
+      );
+      await underlyingToken.mintTo(makerUnderlying, mintAuthority, [], 10);
+      const takerQuote = await quoteToken.createAccount(taker.publicKey);
+      await quoteToken.mintTo(takerQuote, mintAuthority, [], 100);
+      await serumMarket.placeOrder(provider.connection, {
+        owner: new Account(maker.secretKey),
+        payer: makerUnderlying,
+        side: "sell",
+        price: 6,
+        size: 10,
+        orderType: "limit",
+        feeDiscountPubkey: null,
+      });
+      await serumMarket.placeOrder(provider.connection, {
+        owner: new Account(taker.secretKey),
+        payer: takerQuote,
+        side: "buy",
+        price: 6,
+        size: 10,
+        orderType: "ioc",
+        feeDiscountPubkey: null,
+      });
+    });
+
+    it("should sample the maker fill price of a contract", async () => {
+      await program.rpc.crankTwap({
+        accounts: {
+          optionMarket: market.key,
+          twapAccumulator,
+          eventQueue: serumMarket.decoded.eventQueue,
+        },
+      });
+      const accumulator = await program.account.twapAccumulator.fetch(
+        twapAccumulator
+      );
+      assert.equal(accumulator.samples.length, 1);
+      // The test mints have 0 decimals
+      assert.equal(
+        accumulator.samples[0].price.toString(),
+        market.underlyingAmountPerContract.muln(6).toString()
+      );
+    });
+
+    it("should record the TWAP as the settlement price", async () => {
+      // Wait so the market is expired
+      await wait(
+        (market.expirationUnixTimestamp.toNumber() - now() + 2) * 1000
+      );
+      await program.rpc.recordTwapSettlementPrice({
+        accounts: {
+          optionMarket: market.key,
+          twapAccumulator,
+        },
+      });
+      const record = await program.account.optionMarket.fetch(market.key);
+      assert.equal(
+        record.settlementPrice.toString(),
+        market.underlyingAmountPerContract.muln(6).toString()
+      );
+      assert.ok(record.settlementOracle.equals(twapAccumulator));
+    });
+  });
+});
@@ -27,6 +27,7 @@ import {
 } from "../packages/psyoptions-ts/src/fees";
 import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
 import { PsyAmerican } from "../target/types/psy_american";
+import { createHash } from "crypto";
 
 export const wait = (delayMS: number) =>
   new Promise((resolve) => setTimeout(resolve, delayMS));
@@ -392,6 +393,9 @@ export const initSetup = async (
     mintFeeOwner?: PublicKey;
     exerciseFeeOwner?: PublicKey;
     expiration?: anchor.BN;
+    underlyingToken?: Token;
+    quoteToken?: Token;
+    exerciseWindows?: { start: anchor.BN; end: anchor.BN }[];
   } = {}
 ) => {
   const textEncoder = new TextEncoder();
@@ -414,6 +418,8 @@ export const initSetup = async (
     payer,
     mintAuthority
   ));
+  underlyingToken = opts.underlyingToken || underlyingToken;
+  quoteToken = opts.quoteToken || quoteToken;
   [optionMarketKey, bumpSeed] = await anchor.web3.PublicKey.findProgramAddress(
     [
       underlyingToken.publicKey.toBuffer(),
@@ -421,6 +427,9 @@ export const initSetup = async (
       underlyingAmountPerContract.toBuffer("le", 8),
       quoteAmountPerContract.toBuffer("le", 8),
       expiration.toBuffer("le", 8),
+      ...(opts.exerciseWindows
+        ? [exerciseScheduleHash(opts.exerciseWindows)]
+        : []),
     ],
     program.programId
   );
@@ -577,6 +586,146 @@ export const initOptionMarket = async (
   );
 };
 
+/**
+ * Initialize the market and name its mints in one transaction, so the market
+ * never exists without its MintMetadata
+ */
+export const initOptionMarketWithMetadata = async (
+  program: anchor.Program<PsyAmerican>,
+  payer: Keypair,
+  optionMarket: OptionMarketV2,
+  remainingAccounts: AccountMeta[],
+  instructions: TransactionInstruction[]
+) => {
+  const transaction = new Transaction();
+  transaction.add(...instructions);
+  transaction.add(
+    program.instruction.initializeMarket(
+      optionMarket.underlyingAmountPerContract,
+      optionMarket.quoteAmountPerContract,
+      optionMarket.expirationUnixTimestamp,
+      optionMarket.bumpSeed,
+      {
+        accounts: {
+          authority: payer.publicKey,
+          underlyingAssetMint: optionMarket.underlyingAssetMint,
+          quoteAssetMint: optionMarket.quoteAssetMint,
+          optionMint: optionMarket.optionMint,
+          writerTokenMint: optionMarket.writerTokenMint,
+          quoteAssetPool: optionMarket.quoteAssetPool,
+          underlyingAssetPool: optionMarket.underlyingAssetPool,
+          optionMarket: optionMarket.key,
+          feeOwner: FEE_OWNER_KEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          clock: SYSVAR_CLOCK_PUBKEY,
+        },
+        remainingAccounts,
+      }
+    )
+  );
+  const metadataAccounts = await mintMetadataAccounts(
+    program.programId,
+    optionMarket
+  );
+  transaction.add(
+    program.instruction.initMintMetadata({
+      accounts: {
+        payer: payer.publicKey,
+        optionMarket: optionMarket.key,
+        ...metadataAccounts,
+        systemProgram: SystemProgram.programId,
+      },
+    })
+  );
+  await sendAndConfirmTransaction(
+    program.provider.connection,
+    transaction,
+    [payer],
+    {
+      commitment: "confirmed",
+    }
+  );
+  return metadataAccounts;
+};
+
+export const deriveExerciseSchedule = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("exerciseSchedule")],
+    programId
+  );
+
+/**
+ * The hash of the Borsh serialized exercise windows. Scheduled OptionMarkets add it to
+ * their seeds.
+ */
+export const exerciseScheduleHash = (
+  windows: { start: anchor.BN; end: anchor.BN }[]
+) => {
+  const length = Buffer.alloc(4);
+  length.writeUInt32LE(windows.length);
+  return createHash("sha256")
+    .update(
+      Buffer.concat([
+        length,
+        ...windows.flatMap((window) => [
+          window.start.toTwos(64).toArrayLike(Buffer, "le", 8),
+          window.end.toTwos(64).toArrayLike(Buffer, "le", 8),
+        ]),
+      ])
+    )
+    .digest();
+};
+
+export const initOptionMarketWithSchedule = async (
+  program: anchor.Program<PsyAmerican>,
+  payer: Keypair,
+  optionMarket: OptionMarketV2,
+  remainingAccounts: AccountMeta[],
+  instructions: TransactionInstruction[],
+  windows: { start: anchor.BN; end: anchor.BN }[]
+) => {
+  const [exerciseSchedule] = await deriveExerciseSchedule(
+    program.programId,
+    optionMarket.key
+  );
+  await program.rpc.initializeMarketWithSchedule(
+    optionMarket.underlyingAmountPerContract,
+    optionMarket.quoteAmountPerContract,
+    optionMarket.expirationUnixTimestamp,
+    optionMarket.bumpSeed,
+    windows,
+    {
+      accounts: {
+        authority: payer.publicKey,
+        underlyingAssetMint: optionMarket.underlyingAssetMint,
+        quoteAssetMint: optionMarket.quoteAssetMint,
+        optionMint: optionMarket.optionMint,
+        writerTokenMint: optionMarket.writerTokenMint,
+        quoteAssetPool: optionMarket.quoteAssetPool,
+        underlyingAssetPool: optionMarket.underlyingAssetPool,
+        optionMarket: optionMarket.key,
+        exerciseSchedule,
+        feeOwner: FEE_OWNER_KEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId,
+        clock: SYSVAR_CLOCK_PUBKEY,
+      },
+      remainingAccounts,
+      signers: [payer],
+      instructions,
+    }
+  );
+  return exerciseSchedule;
+};
+
 /**
  *
  * @param program
@@ -704,3 +853,273 @@ export const burnWriterForQuote = async (
     signers: [writer],
   });
 };
+
+export const deriveOptionChain = (
+  programId: PublicKey,
+  underlyingAssetMint: PublicKey,
+  quoteAssetMint: PublicKey,
+  underlyingAmountPerContract: BN,
+  expiration: BN
+) =>
+  PublicKey.findProgramAddress(
+    [
+      underlyingAssetMint.toBuffer(),
+      quoteAssetMint.toBuffer(),
+      underlyingAmountPerContract.toBuffer("le", 8),
+      expiration.toBuffer("le", 8),
+      new TextEncoder().encode("optionChain"),
+    ],
+    programId
+  );
+
+/**
+ * The remaining accounts `initializeChain` takes for one strike, derived the
+ * same way as `initializeMarket`
+ */
+export const chainStrikeAccounts = async (
+  programId: PublicKey,
+  underlyingAssetMint: PublicKey,
+  quoteAssetMint: PublicKey,
+  underlyingAmountPerContract: BN,
+  quoteAmountPerContract: BN,
+  expiration: BN
+): Promise<AccountMeta[]> => {
+  const [optionMarketKey] = await PublicKey.findProgramAddress(
+    [
+      underlyingAssetMint.toBuffer(),
+      quoteAssetMint.toBuffer(),
+      underlyingAmountPerContract.toBuffer("le", 8),
+      quoteAmountPerContract.toBuffer("le", 8),
+      expiration.toBuffer("le", 8),
+    ],
+    programId
+  );
+  const keys = [optionMarketKey];
+  for (const seed of [
+    "optionToken",
+    "writerToken",
+    "quoteAssetPool",
+    "underlyingAssetPool",
+  ]) {
+    const [key] = await PublicKey.findProgramAddress(
+      [optionMarketKey.toBuffer(), new TextEncoder().encode(seed)],
+      programId
+    );
+    keys.push(key);
+  }
+  return keys.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }));
+};
+
+export const deriveQuoteRegistry = (
+  programId: PublicKey,
+  underlyingAssetMint: PublicKey,
+  quoteAssetMint: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [
+      underlyingAssetMint.toBuffer(),
+      quoteAssetMint.toBuffer(),
+      new TextEncoder().encode("quoteRegistry"),
+    ],
+    programId
+  );
+
+export const deriveExpiryRegistry = (
+  programId: PublicKey,
+  underlyingAssetMint: PublicKey,
+  expiration: BN
+) =>
+  PublicKey.findProgramAddress(
+    [
+      underlyingAssetMint.toBuffer(),
+      expiration.toBuffer("le", 8),
+      new TextEncoder().encode("expiryRegistry"),
+    ],
+    programId
+  );
+
+export const deriveRegistryPage = (
+  programId: PublicKey,
+  registryKey: PublicKey,
+  pageIndex: number
+) =>
+  PublicKey.findProgramAddress(
+    [
+      registryKey.toBuffer(),
+      new BN(pageIndex).toBuffer("le", 4),
+      new TextEncoder().encode("registryPage"),
+    ],
+    programId
+  );
+
+export const deriveMarketRegistration = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [
+      optionMarketKey.toBuffer(),
+      new TextEncoder().encode("marketRegistration"),
+    ],
+    programId
+  );
+
+/**
+ * Append a market to its quote and expiry registries, creating the
+ * registries if this is their first market
+ */
+export const registerMarket = async (
+  program: anchor.Program<PsyAmerican>,
+  payer: Keypair,
+  optionMarketKey: PublicKey
+) => {
+  const optionMarket = (await program.account.optionMarket.fetch(
+    optionMarketKey
+  )) as OptionMarketV2;
+  const [quoteRegistry] = await deriveQuoteRegistry(
+    program.programId,
+    optionMarket.underlyingAssetMint,
+    optionMarket.quoteAssetMint
+  );
+  const [expiryRegistry] = await deriveExpiryRegistry(
+    program.programId,
+    optionMarket.underlyingAssetMint,
+    optionMarket.expirationUnixTimestamp
+  );
+  const instructions: TransactionInstruction[] = [];
+  const currentPage = async (
+    registryKey: PublicKey,
+    initRegistry: typeof program.instruction.initQuoteRegistry
+  ) => {
+    const registry = await program.account.marketRegistry.fetchNullable(
+      registryKey
+    );
+    const [pageKey] = await deriveRegistryPage(
+      program.programId,
+      registryKey,
+      registry ? registry.currentPage : 0
+    );
+    if (!registry) {
+      instructions.push(
+        initRegistry({
+          accounts: {
+            payer: payer.publicKey,
+            optionMarket: optionMarketKey,
+            registry: registryKey,
+            firstPage: pageKey,
+            systemProgram: SystemProgram.programId,
+          },
+        })
+      );
+    }
+    return pageKey;
+  };
+  const quoteRegistryPage = await currentPage(
+    quoteRegistry,
+    program.instruction.initQuoteRegistry
+  );
+  const expiryRegistryPage = await currentPage(
+    expiryRegistry,
+    program.instruction.initExpiryRegistry
+  );
+  const [marketRegistration] = await deriveMarketRegistration(
+    program.programId,
+    optionMarketKey
+  );
+  await program.rpc.registerMarket({
+    accounts: {
+      payer: payer.publicKey,
+      optionMarket: optionMarketKey,
+      marketRegistration,
+      quoteRegistry,
+      quoteRegistryPage,
+      expiryRegistry,
+      expiryRegistryPage,
+      systemProgram: SystemProgram.programId,
+    },
+    signers: [payer],
+    instructions,
+  });
+};
+
+/**
+ * List the markets registered in a registry, in the order they were
+ * registered. Markets nobody registered aren't listed.
+ */
+export const listRegistryMarkets = async (
+  program: anchor.Program<PsyAmerican>,
+  registryKey: PublicKey
+): Promise<PublicKey[]> => {
+  const registry = await program.account.marketRegistry.fetch(registryKey);
+  const pageKeys = await Promise.all(
+    Array.from({ length: registry.currentPage + 1 }, async (_, pageIndex) => {
+      const [pageKey] = await deriveRegistryPage(
+        program.programId,
+        registryKey,
+        pageIndex
+      );
+      return pageKey;
+    })
+  );
+  const pages = await program.account.marketRegistryPage.fetchMultiple(
+    pageKeys
+  );
+  return pages.flatMap((page) => (page ? page.markets : []));
+};
+
+export const deriveMintMetadata = (programId: PublicKey, mint: PublicKey) =>
+  PublicKey.findProgramAddress(
+    [mint.toBuffer(), new TextEncoder().encode("mintMetadata")],
+    programId
+  );
+
+export const METAPLEX_METADATA_PROGRAM_ID = new PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+
+export const deriveMetaplexMetadata = (mint: PublicKey) =>
+  PublicKey.findProgramAddress(
+    [
+      new TextEncoder().encode("metadata"),
+      METAPLEX_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+    ],
+    METAPLEX_METADATA_PROGRAM_ID
+  );
+
+/**
+ * The accounts `initMintMetadata` and `initPutMintMetadata` take besides the
+ * market
+ */
+export const mintMetadataAccounts = async (
+  programId: PublicKey,
+  market: {
+    underlyingAssetMint: PublicKey;
+    quoteAssetMint: PublicKey;
+    optionMint: PublicKey;
+    writerTokenMint: PublicKey;
+  }
+) => {
+  const [underlyingAssetMetadata] = await deriveMetaplexMetadata(
+    market.underlyingAssetMint
+  );
+  const [quoteAssetMetadata] = await deriveMetaplexMetadata(
+    market.quoteAssetMint
+  );
+  const [optionMetadata] = await deriveMintMetadata(
+    programId,
+    market.optionMint
+  );
+  const [writerMetadata] = await deriveMintMetadata(
+    programId,
+    market.writerTokenMint
+  );
+  return {
+    underlyingAssetMint: market.underlyingAssetMint,
+    quoteAssetMint: market.quoteAssetMint,
+    underlyingAssetMetadata,
+    quoteAssetMetadata,
+    optionMetadata,
+    writerMetadata,
+  };
+};
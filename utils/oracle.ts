@@ -0,0 +1,145 @@
+import * as anchor from "@project-serum/anchor";
+import fs from "fs";
+import path from "path";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { OptionMarketV2 } from "../packages/psyoptions-ts/src/types";
+import { MockPyth } from "../target/types/mock_pyth";
+import { PsyAmerican } from "../target/types/psy_american";
+import { deriveOracleConfig } from "./serum";
+
+const PYTH_MAGIC = 0xa1b2c3d4;
+const PYTH_VERSION_2 = 2;
+const PYTH_ACCOUNT_TYPE_PRICE = 3;
+const PYTH_STATUS_TRADING = 1;
+const PYTH_PRICE_ACCOUNT_LEN = 240;
+
+/**
+ * The localnet admin. The program only accepts it when built with the
+ * `test-admin` feature.
+ */
+export const loadAdmin = async (provider: anchor.Provider) => {
+  const secretKey = JSON.parse(
+    fs.readFileSync(path.join(__dirname, "../tests/keys/admin.json"), "utf-8")
+  );
+  const admin = Keypair.fromSecretKey(Uint8Array.from(secretKey));
+  await provider.connection.confirmTransaction(
+    await provider.connection.requestAirdrop(admin.publicKey, 10_000_000_000),
+    "confirmed"
+  );
+  return admin;
+};
+
+export type PythPrice = {
+  price: anchor.BN;
+  expo: number;
+  publishTime: anchor.BN;
+};
+
+const pythPriceData = ({ price, expo, publishTime }: PythPrice) => {
+  const data = Buffer.alloc(PYTH_PRICE_ACCOUNT_LEN);
+  data.writeUInt32LE(PYTH_MAGIC, 0);
+  data.writeUInt32LE(PYTH_VERSION_2, 4);
+  data.writeUInt32LE(PYTH_ACCOUNT_TYPE_PRICE, 8);
+  data.writeInt32LE(expo, 20);
+  publishTime.toTwos(64).toArrayLike(Buffer, "le", 8).copy(data, 96);
+  price.toTwos(64).toArrayLike(Buffer, "le", 8).copy(data, 208);
+  data.writeUInt32LE(PYTH_STATUS_TRADING, 224);
+  return data;
+};
+
+/** Overwrite a mock Pyth price account */
+export const setPythPrice = async (
+  mockPyth: anchor.Program<MockPyth>,
+  priceAccount: PublicKey,
+  price: PythPrice
+) => {
+  await mockPyth.rpc.write(new anchor.BN(0), pythPriceData(price), {
+    accounts: { price: priceAccount },
+  });
+};
+
+/** Create a Pyth price account owned by the mock Pyth program */
+export const createPythPrice = async (
+  mockPyth: anchor.Program<MockPyth>,
+  payer: Keypair,
+  price: PythPrice
+) => {
+  const priceAccount = new Keypair();
+  const lamports =
+    await mockPyth.provider.connection.getMinimumBalanceForRentExemption(
+      PYTH_PRICE_ACCOUNT_LEN
+    );
+  await mockPyth.rpc.write(new anchor.BN(0), pythPriceData(price), {
+    accounts: { price: priceAccount.publicKey },
+    instructions: [
+      SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: priceAccount.publicKey,
+        space: PYTH_PRICE_ACCOUNT_LEN,
+        lamports,
+        programId: mockPyth.programId,
+      }),
+    ],
+    signers: [payer, priceAccount],
+  });
+  return priceAccount.publicKey;
+};
+
+/** Configure a Pyth price account as the OptionMarket's oracle */
+export const initPythOracleConfig = async (
+  program: anchor.Program<PsyAmerican>,
+  admin: Keypair,
+  optionMarket: OptionMarketV2,
+  oracle: PublicKey,
+  maxStaleness = new anchor.BN(60)
+) => {
+  const [oracleConfig] = await deriveOracleConfig(
+    program.programId,
+    optionMarket.key
+  );
+  await program.rpc.initOracleConfig({ pyth: {} }, maxStaleness, {
+    accounts: {
+      admin: admin.publicKey,
+      optionMarket: optionMarket.key,
+      underlyingAssetMint: optionMarket.underlyingAssetMint,
+      quoteAssetMint: optionMarket.quoteAssetMint,
+      oracle,
+      oracleConfig,
+      systemProgram: SystemProgram.programId,
+    },
+    signers: [admin],
+  });
+  return oracleConfig;
+};
+
+/**
+ * Record `price` as the expired OptionMarket's settlement price, read from a new
+ * mock Pyth account published at expiration
+ */
+export const recordPythSettlementPrice = async (
+  program: anchor.Program<PsyAmerican>,
+  mockPyth: anchor.Program<MockPyth>,
+  payer: Keypair,
+  optionMarket: OptionMarketV2,
+  price: anchor.BN
+) => {
+  const admin = await loadAdmin(program.provider);
+  const oracle = await createPythPrice(mockPyth, payer, {
+    price,
+    expo: 0,
+    publishTime: optionMarket.expirationUnixTimestamp,
+  });
+  const oracleConfig = await initPythOracleConfig(
+    program,
+    admin,
+    optionMarket,
+    oracle
+  );
+  await program.rpc.recordSettlementPrice({
+    accounts: {
+      optionMarket: optionMarket.key,
+      oracleConfig,
+      oracle,
+    },
+  });
+};
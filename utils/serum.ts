@@ -53,9 +53,31 @@ export const marketLoader =
     provider: anchor.Provider,
     program: Program<PsyAmerican>,
     optionMarketKey: PublicKey,
-    marketAuthorityBump: number
+    marketAuthorityBump: number,
+    /** The oracle of the OptionMarket's OracleConfig, only read when a PriceBand is set */
+    oracle: PublicKey = SystemProgram.programId
   ) =>
   async (marketKey: PublicKey) => {
+    const [referralConfig] = await deriveReferralConfig(
+      program.programId,
+      marketKey
+    );
+    const [priceBand] = await derivePriceBand(
+      program.programId,
+      optionMarketKey
+    );
+    const [oracleConfig] = await deriveOracleConfig(
+      program.programId,
+      optionMarketKey
+    );
+    const [positionLimit] = await derivePositionLimit(
+      program.programId,
+      optionMarketKey
+    );
+    const [tradingAllowlist] = await deriveTradingAllowlist(
+      program.programId,
+      marketKey
+    );
     return new MarketProxyBuilder()
       .middleware(
         new OpenOrdersPda({
@@ -63,6 +85,12 @@ export const marketLoader =
           dexProgramId: DEX_PID,
         })
       )
+      .middleware(new Referral(referralConfig))
+      .middleware(new TradingAllowlists(tradingAllowlist))
+      .middleware(new PositionLimits(optionMarketKey, positionLimit))
+      .middleware(
+        new PriceBands(optionMarketKey, priceBand, oracleConfig, oracle)
+      )
       .middleware(new Validation(optionMarketKey, marketAuthorityBump))
       .middleware(new Logger())
       .middleware(new ReferralFees())
@@ -305,6 +333,210 @@ export class Validation implements Middleware {
   }
 }
 
+/**
+ * Turns a prune instruction built by the MarketProxy into the admin prune, which
+ * cancels a single OpenOrders account's orders before the OptionMarket expires.
+ */
+export const toAdminPrune = (
+  ix: TransactionInstruction,
+  optionMarketKey: PublicKey,
+  admin: PublicKey
+) => {
+  // Swap the Validation discriminator, keeping the marketAuthorityBump
+  ix.data = Buffer.concat([Buffer.from([7]), ix.data.slice(1)]);
+  // The admin follows the optionMarket key prepended by Validation
+  const index = ix.keys.findIndex((key) => key.pubkey.equals(optionMarketKey));
+  ix.keys.splice(index + 1, 0, {
+    pubkey: admin,
+    isWritable: false,
+    isSigner: true,
+  });
+  return ix;
+};
+
+export const deriveReferralConfig = (
+  programId: PublicKey,
+  serumMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [serumMarketKey.toBuffer(), new TextEncoder().encode("referralConfig")],
+    programId
+  );
+
+export const deriveOracleConfig = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("oracleConfig")],
+    programId
+  );
+
+export const derivePriceBand = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("priceBand")],
+    programId
+  );
+
+export const derivePositionLimit = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("positionLimit")],
+    programId
+  );
+
+export const deriveKnockOutBarrier = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("knockOutBarrier")],
+    programId
+  );
+
+export const deriveTwapAccumulator = (
+  programId: PublicKey,
+  optionMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [optionMarketKey.toBuffer(), new TextEncoder().encode("twapAccumulator")],
+    programId
+  );
+
+export const deriveTradingAllowlist = (
+  programId: PublicKey,
+  serumMarketKey: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [serumMarketKey.toBuffer(), new TextEncoder().encode("tradingAllowlist")],
+    programId
+  );
+
+/**
+ * Prepends the market's TradingAllowlist so the program can check the owner on
+ * initOpenOrders and newOrderV3. Must run before PositionLimits, whose accounts
+ * are stripped first by the program.
+ */
+export class TradingAllowlists implements Middleware {
+  tradingAllowlist: PublicKey;
+
+  constructor(tradingAllowlist: PublicKey) {
+    this.tradingAllowlist = tradingAllowlist;
+  }
+  initOpenOrders(ix: TransactionInstruction) {
+    ix.keys = [
+      { pubkey: this.tradingAllowlist, isWritable: false, isSigner: false },
+      ...ix.keys,
+    ];
+  }
+  newOrderV3(ix: TransactionInstruction) {
+    ix.keys = [
+      { pubkey: this.tradingAllowlist, isWritable: false, isSigner: false },
+      ...ix.keys,
+    ];
+  }
+  cancelOrderV2(_ix: TransactionInstruction) {}
+  cancelOrderByClientIdV2(_ix: TransactionInstruction) {}
+  settleFunds(_ix: TransactionInstruction) {}
+  closeOpenOrders(_ix: TransactionInstruction) {}
+  prune(_ix: TransactionInstruction) {}
+}
+
+/**
+ * Prepends the accounts the program needs to check an order against the
+ * OptionMarket's PositionLimit. Must run before PriceBands, whose accounts are
+ * stripped first by the program.
+ */
+export class PositionLimits implements Middleware {
+  optionMarketKey: PublicKey;
+  positionLimit: PublicKey;
+
+  constructor(optionMarketKey: PublicKey, positionLimit: PublicKey) {
+    this.optionMarketKey = optionMarketKey;
+    this.positionLimit = positionLimit;
+  }
+  initOpenOrders(_ix: TransactionInstruction) {}
+  newOrderV3(ix: TransactionInstruction) {
+    ix.keys = [
+      { pubkey: this.optionMarketKey, isWritable: false, isSigner: false },
+      { pubkey: this.positionLimit, isWritable: false, isSigner: false },
+      ...ix.keys,
+    ];
+  }
+  cancelOrderV2(_ix: TransactionInstruction) {}
+  cancelOrderByClientIdV2(_ix: TransactionInstruction) {}
+  settleFunds(_ix: TransactionInstruction) {}
+  closeOpenOrders(_ix: TransactionInstruction) {}
+  prune(_ix: TransactionInstruction) {}
+}
+
+/**
+ * Prepends the accounts the program needs to check an order against the
+ * OptionMarket's PriceBand.
+ */
+export class PriceBands implements Middleware {
+  optionMarketKey: PublicKey;
+  priceBand: PublicKey;
+  oracleConfig: PublicKey;
+  oracle: PublicKey;
+
+  constructor(
+    optionMarketKey: PublicKey,
+    priceBand: PublicKey,
+    oracleConfig: PublicKey,
+    oracle: PublicKey
+  ) {
+    this.optionMarketKey = optionMarketKey;
+    this.priceBand = priceBand;
+    this.oracleConfig = oracleConfig;
+    this.oracle = oracle;
+  }
+  initOpenOrders(_ix: TransactionInstruction) {}
+  newOrderV3(ix: TransactionInstruction) {
+    ix.keys = [
+      { pubkey: this.optionMarketKey, isWritable: false, isSigner: false },
+      { pubkey: this.priceBand, isWritable: false, isSigner: false },
+      { pubkey: this.oracleConfig, isWritable: false, isSigner: false },
+      { pubkey: this.oracle, isWritable: false, isSigner: false },
+      ...ix.keys,
+    ];
+  }
+  cancelOrderV2(_ix: TransactionInstruction) {}
+  cancelOrderByClientIdV2(_ix: TransactionInstruction) {}
+  settleFunds(_ix: TransactionInstruction) {}
+  closeOpenOrders(_ix: TransactionInstruction) {}
+  prune(_ix: TransactionInstruction) {}
+}
+
+/**
+ * Prepends the market's ReferralConfig so the program can validate the referral
+ * account on settleFunds.
+ */
+export class Referral implements Middleware {
+  referralConfig: PublicKey;
+
+  constructor(referralConfig: PublicKey) {
+    this.referralConfig = referralConfig;
+  }
+  initOpenOrders(_ix: TransactionInstruction) {}
+  newOrderV3(_ix: TransactionInstruction) {}
+  cancelOrderV2(_ix: TransactionInstruction) {}
+  cancelOrderByClientIdV2(_ix: TransactionInstruction) {}
+  settleFunds(ix: TransactionInstruction) {
+    ix.keys = [
+      { pubkey: this.referralConfig, isWritable: false, isSigner: false },
+      ...ix.keys,
+    ];
+  }
+  closeOpenOrders(_ix: TransactionInstruction) {}
+  prune(_ix: TransactionInstruction) {}
+}
+
 export const initSerum = async (
   provider: anchor.Provider,
   program: Program<PsyAmerican>,
@@ -459,3 +691,89 @@ export async function createMintAndVault(
   await provider.sendAndConfirm!(tx, [mint, vault]);
   return [mint.publicKey, vault.publicKey];
 }
+
+/**
+ * List an unpermissioned Serum market trading `baseMint` against `quoteMint`,
+ * like the underlying's spot market an OptionMarket's TWAP is sampled from
+ */
+export const listSpotMarket = async (
+  provider: AnchorProvider,
+  baseMint: PublicKey,
+  quoteMint: PublicKey,
+  baseLotSize = new BN(1),
+  quoteLotSize = new BN(1)
+) => {
+  // @ts-ignore
+  const wallet = provider.wallet as unknown as anchor.Wallet;
+  const { connection } = provider;
+  const market = new Keypair();
+  const requestQueue = new Keypair();
+  const baseVault = new Keypair();
+  const quoteVault = new Keypair();
+  const [vaultOwner, vaultSignerNonce] = await getVaultOwnerAndNonce(
+    market.publicKey
+  );
+  const { eventQueue, bids, asks } = await createFirstSetOfAccounts({
+    provider,
+    wallet,
+    dexProgramId: DEX_PID,
+  });
+
+  const vaultRent = await connection.getMinimumBalanceForRentExemption(165);
+  const tx1 = new Transaction();
+  tx1.add(
+    ...[baseVault, quoteVault].flatMap((vault, i) => [
+      SystemProgram.createAccount({
+        fromPubkey: wallet.publicKey,
+        newAccountPubkey: vault.publicKey,
+        space: 165,
+        lamports: vaultRent,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      TokenInstructions.initializeAccount({
+        account: vault.publicKey,
+        mint: i === 0 ? baseMint : quoteMint,
+        owner: vaultOwner,
+      }),
+    ])
+  );
+  const tx2 = new Transaction();
+  tx2.add(
+    SystemProgram.createAccount({
+      fromPubkey: wallet.publicKey,
+      newAccountPubkey: market.publicKey,
+      space: MARKET_STATE_LAYOUT_V3.span,
+      lamports: await connection.getMinimumBalanceForRentExemption(
+        MARKET_STATE_LAYOUT_V3.span
+      ),
+      programId: DEX_PID,
+    }),
+    SystemProgram.createAccount({
+      fromPubkey: wallet.publicKey,
+      newAccountPubkey: requestQueue.publicKey,
+      space: 5120 + 12,
+      lamports: await connection.getMinimumBalanceForRentExemption(5120 + 12),
+      programId: DEX_PID,
+    }),
+    DexInstructions.initializeMarket({
+      market: market.publicKey,
+      requestQueue: requestQueue.publicKey,
+      eventQueue: eventQueue.publicKey,
+      bids: bids.publicKey,
+      asks: asks.publicKey,
+      baseVault: baseVault.publicKey,
+      quoteVault: quoteVault.publicKey,
+      baseMint,
+      quoteMint,
+      baseLotSize,
+      quoteLotSize,
+      feeRateBps: 0,
+      vaultSignerNonce,
+      quoteDustThreshold: new BN(100),
+      programId: DEX_PID,
+    })
+  );
+  await provider.sendAndConfirm(tx1, [wallet.payer, baseVault, quoteVault]);
+  await provider.sendAndConfirm(tx2, [wallet.payer, market, requestQueue]);
+  return Market.load(connection, market.publicKey, {}, DEX_PID);
+};